@@ -0,0 +1,62 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Cd(String),
+    Select(String),
+    Reveal(String),
+    Refresh,
+    Mkdir(String),
+    Touch(String),
+    Rename(String, String),
+    Replace(String, String),
+    Delete(String),
+    Shred(String),
+    Download(String),
+    Upload(String),
+    Tag(String, Vec<String>),
+    Message(String),
+}
+
+impl Action {
+    pub fn parse(line: &str) -> Result<Action, String> {
+        let args = split_args(line)?;
+        let Some((name, rest)) = args.split_first() else { return Err("empty command".to_string()) };
+        match (name.as_str(), rest) {
+            ("cd", [path]) => Ok(Action::Cd(path.clone())),
+            ("select", [entry]) => Ok(Action::Select(entry.clone())),
+            ("reveal", [path]) => Ok(Action::Reveal(path.clone())),
+            ("refresh", []) => Ok(Action::Refresh),
+            ("mkdir", [entry]) => Ok(Action::Mkdir(entry.clone())),
+            ("touch", [entry]) => Ok(Action::Touch(entry.clone())),
+            ("rename", [from, to]) => Ok(Action::Rename(from.clone(), to.clone())),
+            ("replace", [from, to]) => Ok(Action::Replace(from.clone(), to.clone())),
+            ("delete", [entry]) => Ok(Action::Delete(entry.clone())),
+            ("download", [entry]) => Ok(Action::Download(entry.clone())),
+            ("upload", [path]) => Ok(Action::Upload(path.clone())),
+            ("tag", [entry, tags @ ..]) => Ok(Action::Tag(entry.clone(), tags.iter().flat_map(|t| crate::tags::parse(t)).collect())),
+            ("message", words) => Ok(Action::Message(words.join(" "))),
+            ("cd" | "select" | "reveal" | "mkdir" | "touch" | "rename" | "replace" | "delete" | "download" | "upload" | "refresh" | "tag", _) => Err(format!("wrong number of arguments for `{}`", name)),
+            (other, _) => Err(format!("unknown command `{}`", other)),
+        }
+    }
+}
+
+pub fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => { current.push(chars.next().ok_or("trailing backslash")?); in_arg = true; }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => { quote = Some(c); in_arg = true; }
+            (None, c) if c.is_whitespace() => if in_arg { args.push(std::mem::take(&mut current)); in_arg = false; },
+            (None, c) => { current.push(c); in_arg = true; }
+        }
+    }
+    if quote.is_some() { return Err("unterminated quote".to_string()); }
+    if in_arg { args.push(current); }
+    Ok(args)
+}
@@ -0,0 +1,163 @@
+use std::{fs::File, io::{self, Read, Seek, SeekFrom}, path::Path, process::{Command, Stdio}};
+
+const DECOMPRESSORS: [(&[&str], &str); 5] = [
+    (&[".tar.gz", ".tgz"], "gzip"),
+    (&[".tar.bz2", ".tbz2", ".tbz"], "bzip2"),
+    (&[".tar.xz", ".txz"], "xz"),
+    (&[".tar.zst", ".tzst"], "zstd"),
+    (&[".tar.lz4"], "lz4"),
+];
+
+pub struct Entry {
+    pub name: String,
+    pub size: u64,
+    pub compressed: Option<u64>,
+    pub is_dir: bool,
+}
+
+pub struct Listing {
+    pub format: &'static str,
+    pub entries: Vec<Entry>,
+    pub total: Option<usize>,
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    name.ends_with(".zip") || name.ends_with(".jar") || name.ends_with(".tar") || DECOMPRESSORS.iter().any(|(suffixes, _)| suffixes.iter().any(|s| name.ends_with(s)))
+}
+
+pub fn list(path: &Path, limit: usize) -> io::Result<Listing> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if name.ends_with(".zip") || name.ends_with(".jar") { return zip(path, limit); }
+    if let Some((_, program)) = DECOMPRESSORS.iter().find(|(suffixes, _)| suffixes.iter().any(|s| name.ends_with(s))) {
+        let mut child = Command::new(program).arg("-dc").arg(path).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("could not run {}: {}", program, e)))?;
+        let listing = tar(child.stdout.take().expect("piped stdout"), limit);
+        let _ = child.kill();
+        let _ = child.wait();
+        return listing.map(|entries| Listing { format: "tar (compressed)", entries, total: None });
+    }
+    tar(File::open(path)?, limit).map(|entries| Listing { format: "tar", entries, total: None })
+}
+
+fn zip(path: &Path, limit: usize) -> io::Result<Listing> {
+    let mut file = File::open(path)?;
+    let length = file.seek(SeekFrom::End(0))?;
+    let tail_length = length.min(65_557);
+    file.seek(SeekFrom::Start(length - tail_length))?;
+    let mut tail = vec![0; tail_length as usize];
+    file.read_exact(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21)).rev().find(|&i| tail[i..].starts_with(b"PK\x05\x06")).ok_or_else(|| invalid("no zip end-of-directory record"))?;
+    let (mut count, mut offset) = (le16(&tail, end + 10) as u64, le32(&tail, end + 16) as u64);
+    if (count == 0xFFFF || offset == 0xFFFF_FFFF) && end >= 20 && tail[end - 20..].starts_with(b"PK\x06\x07") {
+        let mut record = [0u8; 56];
+        file.seek(SeekFrom::Start(le64(&tail, end - 12)))?;
+        file.read_exact(&mut record)?;
+        if !record.starts_with(b"PK\x06\x06") { return Err(invalid("bad zip64 end-of-directory record")); }
+        (count, offset) = (le64(&record, 32), le64(&record, 48));
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut directory = Vec::new();
+    file.take(length.saturating_sub(offset)).read_to_end(&mut directory)?;
+    let mut entries = Vec::new();
+    let mut at = 0;
+    while entries.len() < limit && at + 46 <= directory.len() && directory[at..].starts_with(b"PK\x01\x02") {
+        let (name_length, extra_length, comment_length) = (le16(&directory, at + 28) as usize, le16(&directory, at + 30) as usize, le16(&directory, at + 32) as usize);
+        let name = String::from_utf8_lossy(directory.get(at + 46..at + 46 + name_length).ok_or_else(|| invalid("truncated zip directory"))?).into_owned();
+        let (mut compressed, mut size) = (le32(&directory, at + 20) as u64, le32(&directory, at + 24) as u64);
+        let extra = directory.get(at + 46 + name_length..at + 46 + name_length + extra_length).unwrap_or(&[]);
+        let mut field = 0;
+        while field + 4 <= extra.len() {
+            let (id, length) = (le16(extra, field), le16(extra, field + 2) as usize);
+            if id == 1 {
+                let mut values = extra.get(field + 4..field + 4 + length).unwrap_or(&[]).chunks_exact(8).map(|chunk| le64(chunk, 0));
+                if size == 0xFFFF_FFFF { size = values.next().unwrap_or(size); }
+                if compressed == 0xFFFF_FFFF { compressed = values.next().unwrap_or(compressed); }
+            }
+            field += 4 + length;
+        }
+        entries.push(Entry { is_dir: name.ends_with('/'), name, size, compressed: Some(compressed) });
+        at += 46 + name_length + extra_length + comment_length;
+    }
+    Ok(Listing { format: "zip", entries, total: Some(count as usize) })
+}
+
+fn tar(mut reader: impl Read, limit: usize) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut header = [0u8; 512];
+    let mut long_name: Option<String> = None;
+    while entries.len() < limit {
+        if !fill(&mut reader, &mut header)? || header.iter().all(|b| *b == 0) { break; }
+        if entries.is_empty() && long_name.is_none() && &header[257..262] != b"ustar" && !checksum_ok(&header) { return Err(invalid("not a tar archive")); }
+        let size = octal(&header[124..136]);
+        let kind = header[156];
+        let mut data = Vec::new();
+        if matches!(kind, b'L' | b'x') {
+            (&mut reader).take(size).read_to_end(&mut data)?;
+            skip(&mut reader, padding(size))?;
+        } else {
+            skip(&mut reader, size + padding(size))?;
+        }
+        match kind {
+            b'L' => long_name = Some(String::from_utf8_lossy(&data).trim_end_matches('\0').to_string()),
+            b'x' => long_name = pax_path(&data).or(long_name),
+            b'g' => {}
+            _ => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let name = text(&header[0..100]);
+                    let prefix = if &header[257..262] == b"ustar" { text(&header[345..500]) } else { String::new() };
+                    if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) }
+                });
+                entries.push(Entry { is_dir: kind == b'5' || name.ends_with('/'), name, size, compressed: None });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data).lines().find_map(|record| record.split_once(' ').and_then(|(_, field)| field.strip_prefix("path=")).map(str::to_string))
+}
+
+fn fill(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..])? {
+            0 if read == 0 => return Ok(false),
+            0 => return Err(invalid("truncated tar header")),
+            n => read += n,
+        }
+    }
+    Ok(true)
+}
+
+fn skip(reader: &mut impl Read, bytes: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(bytes), &mut io::sink())?;
+    if skipped < bytes { Err(invalid("truncated tar entry")) } else { Ok(()) }
+}
+
+fn padding(size: u64) -> u64 {
+    (512 - size % 512) % 512
+}
+
+fn octal(field: &[u8]) -> u64 {
+    if field[0] & 0x80 != 0 { return field[1..].iter().fold(0, |n, b| n << 8 | *b as u64); }
+    field.iter().skip_while(|b| **b == b' ').take_while(|b| (b'0'..=b'7').contains(b)).fold(0, |n, b| n * 8 + (b - b'0') as u64)
+}
+
+fn checksum_ok(header: &[u8; 512]) -> bool {
+    let sum: u64 = header.iter().enumerate().map(|(i, b)| if (148..156).contains(&i) { b' ' as u64 } else { *b as u64 }).sum();
+    octal(&header[148..156]) == sum
+}
+
+fn text(field: &[u8]) -> String {
+    String::from_utf8_lossy(field.split(|b| *b == 0).next().unwrap_or(&[])).into_owned()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn le16(data: &[u8], at: usize) -> u16 { data.get(at..at + 2).map_or(0, |b| u16::from_le_bytes([b[0], b[1]])) }
+fn le32(data: &[u8], at: usize) -> u32 { data.get(at..at + 4).map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])) }
+fn le64(data: &[u8], at: usize) -> u64 { data.get(at..at + 8).map_or(0, |b| u64::from_le_bytes(b.try_into().unwrap_or_default())) }
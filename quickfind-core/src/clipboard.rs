@@ -0,0 +1,114 @@
+use std::{env, fs, io::{self, Write}, process::{Command, Stdio}};
+
+pub fn copy(text: &str) -> Result<&'static str, Box<dyn std::error::Error>> {
+    if is_wsl() {
+        pipe("clip.exe", &[], text)?;
+        return Ok("clip.exe");
+    }
+    native(text)
+}
+
+pub fn paste() -> Result<String, Box<dyn std::error::Error>> {
+    if is_wsl() {
+        let text = read("powershell.exe", &["-NoProfile", "-Command", "Get-Clipboard"])?;
+        return Ok(text.strip_suffix("\r\n").unwrap_or(&text).replace("\r\n", "\n"));
+    }
+    native_paste()
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn native_paste() -> Result<String, Box<dyn std::error::Error>> {
+    use clipboard::{ClipboardContext, ClipboardProvider};
+    let mut context = ClipboardContext::new()?;
+    context.get_contents()
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn native_paste() -> Result<String, Box<dyn std::error::Error>> {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some_and(|v| !v.is_empty());
+    let x11 = env::var_os("DISPLAY").is_some_and(|v| !v.is_empty());
+    let tools: [(&'static str, &[&str], bool); 3] = [
+        ("wl-paste", &["--no-newline"], wayland),
+        ("xclip", &["-selection", "clipboard", "-o"], x11),
+        ("xsel", &["--clipboard", "--output"], x11),
+    ];
+    let mut failures = Vec::new();
+    for (program, args, usable) in tools {
+        if !usable { failures.push(format!("{}: no display", program)); continue; }
+        match read(program, args) {
+            Ok(text) => return Ok(text),
+            Err(err) => failures.push(format!("{}: {}", program, err)),
+        }
+    }
+    Err(failures.join("; ").into())
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn native(text: &str) -> Result<&'static str, Box<dyn std::error::Error>> {
+    use clipboard::{ClipboardContext, ClipboardProvider};
+    let mut context = ClipboardContext::new()?;
+    context.set_contents(text.to_string())?;
+    Ok("system clipboard")
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn native(text: &str) -> Result<&'static str, Box<dyn std::error::Error>> {
+    use std::io::IsTerminal;
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some_and(|v| !v.is_empty());
+    let x11 = env::var_os("DISPLAY").is_some_and(|v| !v.is_empty());
+    let tools: [(&'static str, &[&str], bool); 3] = [
+        ("wl-copy", &[], wayland),
+        ("xclip", &["-selection", "clipboard"], x11),
+        ("xsel", &["--clipboard", "--input"], x11),
+    ];
+    let mut failures = Vec::new();
+    for (program, args, usable) in tools {
+        if !usable { failures.push(format!("{}: no display", program)); continue; }
+        match pipe(program, args, text) {
+            Ok(()) => return Ok(program),
+            Err(err) => failures.push(format!("{}: {}", program, err)),
+        }
+    }
+    if io::stdout().is_terminal() {
+        osc52(text)?;
+        return Ok("OSC 52 (needs terminal support)");
+    }
+    failures.push("OSC 52: not a terminal".to_string());
+    Err(failures.join("; ").into())
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn osc52(text: &str) -> io::Result<()> {
+    let mut out = io::stdout();
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    out.flush()
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            encoded.push(if i <= chunk.len() { ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char } else { '=' });
+        }
+    }
+    encoded
+}
+
+fn is_wsl() -> bool {
+    cfg!(target_os = "linux") && (env::var_os("WSL_DISTRO_NAME").is_some() || fs::read_to_string("/proc/sys/kernel/osrelease").is_ok_and(|r| r.to_lowercase().contains("microsoft")))
+}
+
+fn read(program: &str, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let output = Command::new(program).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().map_err(|e| if e.kind() == io::ErrorKind::NotFound { "not installed".to_string() } else { e.to_string() })?;
+    if !output.status.success() { return Err(format!("{} failed", program).into()); }
+    Ok(String::from_utf8(output.stdout).map_err(|_| "the clipboard does not hold text")?)
+}
+
+fn pipe(program: &str, args: &[&str], text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().map_err(|e| if e.kind() == io::ErrorKind::NotFound { "not installed".to_string() } else { e.to_string() })?;
+    if let Some(mut stdin) = child.stdin.take() { stdin.write_all(text.as_bytes())?; }
+    if !child.wait()?.success() { return Err(format!("{} failed", program).into()); }
+    Ok(())
+}
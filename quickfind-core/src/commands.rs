@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+use crate::{config::Document, shell};
+
+#[derive(Clone)]
+pub struct CustomCommand {
+    pub name: String,
+    pub run: String,
+    pub pause: bool,
+    pub key: Option<char>,
+}
+
+impl CustomCommand {
+    pub fn all_from_document(doc: &Document) -> Vec<CustomCommand> {
+        doc.section_names().into_iter().filter_map(|section| {
+            let name = section.strip_prefix("command.")?;
+            Some(CustomCommand {
+                name: name.trim_matches('"').to_string(),
+                run: doc.str(section, "run")?.to_string(),
+                pause: doc.bool(section, "pause").unwrap_or(false),
+                key: doc.str(section, "key").and_then(|k| k.chars().next()),
+            })
+        }).collect()
+    }
+
+    pub fn expand(&self, dir: &Path, selected: Option<&Path>) -> String {
+        expand(&self.run, dir, selected)
+    }
+}
+
+pub fn expand(template: &str, dir: &Path, selected: Option<&Path>) -> String {
+    let name = selected.and_then(|p| p.file_name()).map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let selected = selected.map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+    substitute(template, &[("{dir}", &shell::quote(&dir.to_string_lossy())), ("{name}", &shell::quote(&name)), ("{}", &shell::quote(&selected))])
+}
+
+pub fn format_choice(template: &str, path: &Path) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let dir = path.parent().map(|d| d.to_string_lossy().into_owned()).unwrap_or_default();
+    let size = fs::metadata(path).map(|m| m.len().to_string()).unwrap_or_default();
+    substitute(template, &[("{dir}", &dir), ("{name}", &name), ("{size}", &size), ("{path}", &path.to_string_lossy())])
+}
+
+fn substitute(template: &str, values: &[(&str, &str)]) -> String {
+    let (mut out, mut rest) = (String::with_capacity(template.len()), template);
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|(key, _)| rest.starts_with(key)) {
+            Some((key, value)) => { out.push_str(value); rest = &rest[key.len()..]; }
+            None => { out.push('{'); rest = &rest[1..]; }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_each_placeholder_once() {
+        assert_eq!(expand("ls {dir} {name} {}", Path::new("/tmp"), Some(Path::new("/tmp/a b"))), "ls '/tmp' 'a b' '/tmp/a b'");
+        assert_eq!(expand("echo {other} {", Path::new("/tmp"), None), "echo {other} {");
+    }
+
+    #[test]
+    fn substituted_values_are_not_expanded_again() {
+        let (dir, file) = (Path::new("/tmp/a{}b"), Path::new("/tmp/a{}b/x;id;y"));
+        assert_eq!(expand("ls {dir}", dir, Some(file)), "ls '/tmp/a{}b'");
+        assert_eq!(expand("cat {}", dir, Some(file)), "cat '/tmp/a{}b/x;id;y'");
+        assert_eq!(format_choice("{name}|{path}", Path::new("/nowhere/{path}{dir}")), "{path}{dir}|/nowhere/{path}{dir}");
+    }
+}
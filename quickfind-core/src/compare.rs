@@ -0,0 +1,95 @@
+use std::{collections::{BTreeMap, BTreeSet}, fs::{self, File, Metadata}, io::{self, Read}, path::{Path, PathBuf}, time::UNIX_EPOCH};
+use crate::finder::ScanLimits;
+
+const CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difference {
+    OnlyHere,
+    OnlyThere,
+    Kind,
+    Size,
+    Content,
+    Modified,
+}
+
+impl Difference {
+    pub fn label(self) -> &'static str {
+        match self {
+            Difference::OnlyHere => "only here",
+            Difference::OnlyThere => "only there",
+            Difference::Kind => "file vs dir",
+            Difference::Size => "size",
+            Difference::Content => "content",
+            Difference::Modified => "mtime only",
+        }
+    }
+}
+
+pub struct Comparison {
+    pub here: PathBuf,
+    pub other: PathBuf,
+    pub differences: Vec<(String, Difference)>,
+}
+
+pub fn compare(here: &Path, there: &Path, limits: &ScanLimits) -> io::Result<Vec<(String, Difference)>> {
+    let (ours, theirs) = (children(here, limits)?, children(there, limits)?);
+    let names: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+    let mut differences = Vec::new();
+    for name in names {
+        let difference = match (ours.get(name), theirs.get(name)) {
+            (Some(_), None) => Some(Difference::OnlyHere),
+            (None, Some(_)) => Some(Difference::OnlyThere),
+            (Some(a), Some(b)) => difference(&here.join(name), a, &there.join(name), b, limits)?,
+            (None, None) => None,
+        };
+        if let Some(difference) = difference { differences.push((name.clone(), difference)); }
+    }
+    Ok(differences)
+}
+
+fn children(dir: &Path, limits: &ScanLimits) -> io::Result<BTreeMap<String, Metadata>> {
+    let mut children = BTreeMap::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Ok(meta) = fs::metadata(entry.path()).or_else(|_| entry.metadata()) else { continue };
+        if limits.ignores(Path::new(&name)) || meta.is_dir() && limits.skips(&name) { continue }
+        children.insert(name, meta);
+    }
+    Ok(children)
+}
+
+fn difference(a: &Path, a_meta: &Metadata, b: &Path, b_meta: &Metadata, limits: &ScanLimits) -> io::Result<Option<Difference>> {
+    match (a_meta.is_dir(), b_meta.is_dir()) {
+        (true, true) => Ok(compare(a, b, limits)?.iter().any(|(_, d)| *d != Difference::Modified).then_some(Difference::Content)),
+        (false, false) if a_meta.len() != b_meta.len() => Ok(Some(Difference::Size)),
+        (false, false) if seconds(a_meta) == seconds(b_meta) => Ok(None),
+        (false, false) => Ok(Some(if same_contents(a, b)? { Difference::Modified } else { Difference::Content })),
+        _ => Ok(Some(Difference::Kind)),
+    }
+}
+
+fn seconds(meta: &Metadata) -> Option<u64> {
+    meta.modified().ok()?.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (File::open(a)?, File::open(b)?);
+    let (mut left, mut right) = (vec![0; CHUNK_BYTES], vec![0; CHUNK_BYTES]);
+    loop {
+        let read = fill(&mut a, &mut left)?;
+        if read != fill(&mut b, &mut right)? || left[..read] != right[..read] { return Ok(false); }
+        if read == 0 { return Ok(true); }
+    }
+}
+
+fn fill(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
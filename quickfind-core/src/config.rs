@@ -0,0 +1,329 @@
+use std::{env, fs, path::Path, path::PathBuf, time::Duration};
+use crate::{commands::CustomCommand, finder::ScanLimits, hooks::Hooks, listing::ListingSettings, notify::Alert, search::MatchOptions};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        if let Value::Str(s) = self { Some(s) } else { None }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        if let Value::Int(i) = self { Some(*i) } else { None }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Value::Bool(b) = self { Some(*b) } else { None }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Document {
+    sections: Vec<(String, Vec<(String, Value)>)>,
+}
+
+impl Document {
+    pub fn parse(text: &str) -> Result<Document, Box<dyn std::error::Error>> {
+        let mut doc = Document { sections: vec![(String::new(), Vec::new())] };
+        let mut lines = text.lines().enumerate();
+        while let Some((number, raw_line)) = lines.next() {
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() { continue; }
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header.strip_suffix(']').ok_or_else(|| format!("config line {}: unterminated section header", number + 1))?;
+                doc.sections.push((name.trim().to_string(), Vec::new()));
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("config line {}: expected `key = value`", number + 1))?;
+            let mut value = value.trim().to_string();
+            while value.starts_with('[') && parse_value(&value).is_none() {
+                let Some((_, more)) = lines.next() else { break };
+                value.push(' ');
+                value.push_str(strip_comment(more).trim());
+            }
+            let value = parse_value(&value).ok_or_else(|| format!("config line {}: invalid value `{}`", number + 1, value))?;
+            if let Some(section) = doc.sections.last_mut() { section.1.push((unquote_key(key.trim()), value)); }
+        }
+        Ok(doc)
+    }
+
+    pub fn load(path: &Path) -> Result<Document, Box<dyn std::error::Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Document::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Document::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections.iter().rev().filter(|(name, _)| name == section).find_map(|(_, pairs)| pairs.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v))
+    }
+
+    pub fn str(&self, section: &str, key: &str) -> Option<&str> {
+        self.get(section, key).and_then(Value::as_str)
+    }
+
+    pub fn int(&self, section: &str, key: &str) -> Option<i64> {
+        self.get(section, key).and_then(Value::as_int)
+    }
+
+    pub fn bool(&self, section: &str, key: &str) -> Option<bool> {
+        self.get(section, key).and_then(Value::as_bool)
+    }
+
+    pub fn strings(&self, section: &str, key: &str) -> Vec<String> {
+        match self.get(section, key) {
+            Some(Value::List(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            Some(Value::Str(s)) => vec![s.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn section_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in &self.sections { if !names.contains(&name.as_str()) { names.push(name); } }
+        names
+    }
+}
+
+#[derive(Clone)]
+pub struct Panels {
+    pub list_height: u16,
+    pub path_width: u16,
+    pub path: bool,
+    pub help: bool,
+    pub zen: bool,
+}
+
+impl Panels {
+    fn from_document(doc: &Document) -> Panels {
+        Panels {
+            list_height: doc.int("layout", "list_height").unwrap_or(85).clamp(20, 95) as u16,
+            path_width: doc.int("layout", "path_width").unwrap_or(70).clamp(10, 90) as u16,
+            path: doc.bool("layout", "path_panel").unwrap_or(true),
+            help: doc.bool("layout", "help_panel").unwrap_or(true),
+            zen: doc.bool("layout", "zen").unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ConfirmPolicy {
+    pub delete: bool,
+    pub overwrite: bool,
+    pub permanent_delete: bool,
+    pub bulk_threshold: usize,
+}
+
+impl ConfirmPolicy {
+    fn from_document(doc: &Document) -> ConfirmPolicy {
+        ConfirmPolicy {
+            delete: doc.bool("confirm", "delete").unwrap_or(true),
+            overwrite: doc.bool("confirm", "overwrite").unwrap_or(true),
+            permanent_delete: doc.bool("confirm", "permanent_delete").unwrap_or(true),
+            bulk_threshold: doc.int("confirm", "bulk_threshold").unwrap_or(50).max(0) as usize,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub path: PathBuf,
+    pub theme: String,
+    pub language: String,
+    pub accessible: bool,
+    pub status_format: Option<String>,
+    pub panels: Panels,
+    pub hooks: Hooks,
+    pub commands: Vec<CustomCommand>,
+    pub confirm: ConfirmPolicy,
+    pub trash: bool,
+    pub shred: bool,
+    pub shred_passes: usize,
+    pub ftp_active: bool,
+    pub exit_action: String,
+    pub finder: Option<String>,
+    pub search: MatchOptions,
+    pub scan: ScanLimits,
+    pub foreach_jobs: usize,
+    pub listing: ListingSettings,
+    pub ignore: Vec<String>,
+    pub preview_bytes: u64,
+    pub thumbnails: bool,
+    pub graphics: String,
+    pub notify_desktop: bool,
+    pub notify_after: Duration,
+    pub alert: Alert,
+}
+
+impl Config {
+    pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        let path = config_path();
+        Ok(Config::from_document(&Document::load(&path)?, path))
+    }
+
+    pub fn from_document(doc: &Document, path: PathBuf) -> Config {
+        Config {
+            theme: doc.str("ui", "theme").unwrap_or("dark").to_string(),
+            language: doc.str("ui", "language").unwrap_or("auto").to_string(),
+            accessible: doc.bool("ui", "accessible").unwrap_or(false),
+            status_format: doc.str("ui", "status").filter(|format| !format.trim().is_empty()).map(str::to_string),
+            panels: Panels::from_document(doc),
+            hooks: Hooks::from_document(doc),
+            commands: CustomCommand::all_from_document(doc),
+            confirm: ConfirmPolicy::from_document(doc),
+            trash: doc.bool("delete", "trash").unwrap_or(true),
+            shred: doc.bool("delete", "shred").unwrap_or(false),
+            shred_passes: doc.int("delete", "shred_passes").unwrap_or(1).clamp(1, 35) as usize,
+            ftp_active: doc.bool("ftp", "active").unwrap_or(false),
+            exit_action: doc.str("exit", "action").unwrap_or("auto").to_string(),
+            finder: doc.str("search", "finder").map(str::to_string),
+            search: MatchOptions::from_document(doc),
+            scan: ScanLimits::from_document(doc),
+            foreach_jobs: doc.int("foreach", "jobs").unwrap_or(1).clamp(1, 64) as usize,
+            listing: ListingSettings::from_document(doc),
+            ignore: doc.strings("listing", "ignore"),
+            preview_bytes: doc.int("preview", "max_bytes").unwrap_or(1 << 20).max(4096) as u64,
+            thumbnails: doc.bool("preview", "thumbnails").unwrap_or(true),
+            graphics: doc.str("preview", "graphics").unwrap_or("auto").to_string(),
+            notify_desktop: doc.bool("notify", "desktop").unwrap_or(true),
+            notify_after: Duration::from_secs(doc.int("notify", "after").unwrap_or(10).max(0) as u64),
+            alert: doc.str("notify", "bell").and_then(Alert::parse).unwrap_or_default(),
+            path,
+        }
+    }
+
+    pub fn persist(&self, section: &str, key: &str, value: &Value) -> Result<(), Box<dyn std::error::Error>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(parent) = self.path.parent() { fs::create_dir_all(parent)?; }
+        fs::write(&self.path, set_in_text(&text, section, key, value))?;
+        Ok(())
+    }
+}
+
+pub fn config_dir() -> PathBuf {
+    if let Ok(path) = env::var("QUICKFIND_CONFIG") && let Some(parent) = Path::new(&path).parent() { return parent.to_path_buf(); }
+    dirs_next::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("quickfind")
+}
+
+pub fn config_path() -> PathBuf {
+    env::var("QUICKFIND_CONFIG").map(PathBuf::from).unwrap_or_else(|_| config_dir().join("config.toml"))
+}
+
+pub fn render_value(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")),
+        Value::Int(i) => i.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::List(l) => format!("[{}]", l.iter().map(render_value).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn set_in_text(text: &str, section: &str, key: &str, value: &Value) -> String {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let assignment = format!("{} = {}", key, render_value(value));
+    let mut current = String::new();
+    let mut section_end = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = strip_comment(line).trim();
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) { current = header.trim().to_string(); continue; }
+        if current != section || trimmed.is_empty() { continue; }
+        section_end = Some(i);
+        if trimmed.split_once('=').is_some_and(|(k, _)| unquote_key(k.trim()) == key) {
+            lines[i] = assignment;
+            return lines.join("\n") + "\n";
+        }
+    }
+    let header_index = lines.iter().position(|l| strip_comment(l).trim().strip_prefix('[').and_then(|h| h.strip_suffix(']')).is_some_and(|h| h.trim() == section));
+    match (section_end, header_index) {
+        (Some(i), _) => lines.insert(i + 1, assignment),
+        (None, Some(i)) => lines.insert(i + 1, assignment),
+        (None, None) if section.is_empty() => lines.insert(0, assignment),
+        (None, None) => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) { lines.push(String::new()); }
+            lines.push(format!("[{}]", section));
+            lines.push(assignment);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (in_string, c) {
+            (Some('"'), '\\') if !escaped => { escaped = true; continue; }
+            (Some(q), c) if c == q && !escaped => in_string = None,
+            (None, '"') | (None, '\'') => in_string = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn unquote_key(key: &str) -> String {
+    match parse_value(key) {
+        Some(Value::Str(s)) => s,
+        _ => key.to_string(),
+    }
+}
+
+fn parse_value(raw: &str) -> Option<Value> {
+    let (value, rest) = parse_partial(raw)?;
+    if rest.trim().is_empty() { Some(value) } else { None }
+}
+
+fn parse_partial(raw: &str) -> Option<(Value, &str)> {
+    let raw = raw.trim_start();
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((Value::Str(out), &rest[i + 1..])),
+                '\\' => match chars.next()?.1 {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    other => out.push(other),
+                },
+                c => out.push(c),
+            }
+        }
+        return None;
+    }
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+        return Some((Value::Str(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(mut rest) = raw.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') { return Some((Value::List(items), after)); }
+            let (item, after) = parse_partial(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') { rest = after; }
+        }
+    }
+    let end = raw.find(|c: char| c == ',' || c == ']' || c.is_whitespace()).unwrap_or(raw.len());
+    let (token, rest) = raw.split_at(end);
+    match token {
+        "true" => Some((Value::Bool(true), rest)),
+        "false" => Some((Value::Bool(false), rest)),
+        _ => token.replace('_', "").parse().ok().map(|i| (Value::Int(i), rest)),
+    }
+}
@@ -0,0 +1,88 @@
+use std::{path::PathBuf, process::Command};
+
+pub struct Device {
+    pub path: String,
+    pub label: String,
+    pub size: String,
+    pub fstype: String,
+    pub mountpoint: Option<PathBuf>,
+}
+
+pub fn removable() -> Result<Vec<Device>, Box<dyn std::error::Error>> {
+    if !cfg!(target_os = "linux") { return Err("removable media needs Linux with udisks2".into()); }
+    let output = Command::new("lsblk").args(["-P", "-n", "-o", "PATH,RM,HOTPLUG,TYPE,FSTYPE,LABEL,SIZE,MOUNTPOINT"]).output()?;
+    if !output.status.success() { return Err(format!("lsblk: {}", String::from_utf8_lossy(&output.stderr).trim()).into()); }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().filter_map(|line| {
+        let fields = parse_pairs(line);
+        let field = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_default();
+        let removable = field("RM") == "1" || field("HOTPLUG") == "1";
+        let mountable = matches!(field("TYPE").as_str(), "part" | "disk" | "crypt") && !field("FSTYPE").is_empty() && field("FSTYPE") != "crypto_LUKS";
+        (removable && mountable).then(|| Device {
+            path: field("PATH"),
+            label: field("LABEL"),
+            size: field("SIZE"),
+            fstype: field("FSTYPE"),
+            mountpoint: Some(field("MOUNTPOINT")).filter(|m| !m.is_empty()).map(PathBuf::from),
+        })
+    }).collect())
+}
+
+pub fn mount(device: &Device) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    udisksctl("mount", device)?;
+    removable()?.into_iter().find(|d| d.path == device.path).and_then(|d| d.mountpoint).ok_or_else(|| format!("{} was mounted but its mount point is unknown", device.path).into())
+}
+
+pub fn unmount(device: &Device) -> Result<(), Box<dyn std::error::Error>> {
+    udisksctl("unmount", device)
+}
+
+fn udisksctl(verb: &str, device: &Device) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("udisksctl").args([verb, "--no-user-interaction", "-b", &device.path]).output().map_err(|e| format!("udisksctl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("udisksctl: {}", stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("failed")).into());
+    }
+    Ok(())
+}
+
+fn parse_pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = line;
+    while let Some((key, after)) = rest.split_once("=\"") {
+        let Some((value, tail)) = after.split_once('"') else { break };
+        pairs.push((key.trim().to_string(), unescape(value)));
+        rest = tail;
+    }
+    pairs
+}
+
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail.strip_prefix(b"x").and_then(|h| h.get(..2)).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (byte, escaped) {
+            (b'\\', Some(decoded)) => { bytes.push(decoded); rest = &tail[3..]; }
+            _ => { bytes.push(byte); rest = tail; }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+pub fn drive_roots() -> Vec<PathBuf> {
+    ('A'..='Z').map(|letter| PathBuf::from(format!("{}:\\", letter))).filter(|root| root.exists()).collect()
+}
+
+#[cfg(unix)]
+pub fn free_space(path: &std::path::Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 { return None; }
+    u64::try_from(stat.f_bavail as u128 * stat.f_frsize as u128).ok()
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_: &std::path::Path) -> Option<u64> {
+    None
+}
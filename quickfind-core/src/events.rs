@@ -0,0 +1,74 @@
+use std::{collections::VecDeque, io, time::Duration};
+#[cfg(unix)]
+use std::{fs::File, os::fd::{AsFd, BorrowedFd}};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crate::wake;
+
+#[cfg(not(unix))]
+const FALLBACK_TICK: Duration = Duration::from_millis(50);
+
+pub trait EventSource {
+    fn next_event(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+}
+
+pub struct CrosstermEvents {
+    #[cfg(unix)]
+    tty: Option<File>,
+}
+
+impl CrosstermEvents {
+    pub fn new() -> io::Result<CrosstermEvents> {
+        wake::init()?;
+        Ok(CrosstermEvents { #[cfg(unix)] tty: File::open("/dev/tty").ok() })
+    }
+}
+
+impl EventSource for CrosstermEvents {
+    #[cfg(unix)]
+    fn next_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if !timeout.is_zero() && !event::poll(Duration::ZERO)? {
+            let input = self.tty.as_ref().map_or_else(|| unsafe { BorrowedFd::borrow_raw(0) }, |tty| tty.as_fd());
+            wake::wait(input, timeout)?;
+        }
+        if event::poll(Duration::ZERO)? { event::read().map(Some) } else { Ok(None) }
+    }
+
+    #[cfg(not(unix))]
+    fn next_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if event::poll(timeout.min(FALLBACK_TICK))? { event::read().map(Some) } else { Ok(None) }
+    }
+}
+
+#[derive(Default)]
+pub struct ScriptedEvents {
+    queue: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        ScriptedEvents { queue: events.into_iter().collect() }
+    }
+
+    pub fn keys(codes: impl IntoIterator<Item = KeyCode>) -> Self {
+        Self::new(codes.into_iter().map(|code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE))))
+    }
+
+    pub fn text(text: &str) -> Self {
+        Self::keys(text.chars().map(KeyCode::Char))
+    }
+
+    pub fn push(&mut self, event: Event) -> &mut Self {
+        self.queue.push_back(event);
+        self
+    }
+
+    pub fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> &mut Self {
+        self.push(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self, _timeout: Duration) -> io::Result<Option<Event>> {
+        Ok(self.queue.pop_front())
+    }
+}
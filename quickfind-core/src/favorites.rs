@@ -0,0 +1,37 @@
+use std::{collections::BTreeSet, fs, io, path::{Path, PathBuf}};
+use crate::config;
+
+#[derive(Default)]
+pub struct Favorites {
+    path: Option<PathBuf>,
+    entries: BTreeSet<PathBuf>,
+}
+
+impl Favorites {
+    pub fn load() -> Favorites {
+        let path = config::config_dir().join("favorites");
+        let entries = fs::read_to_string(&path).unwrap_or_default().lines().filter(|line| !line.is_empty()).map(PathBuf::from).collect();
+        Favorites { path: Some(path), entries }
+    }
+
+    pub fn contains(&self, file: &Path) -> bool {
+        self.entries.contains(file)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().map(PathBuf::as_path)
+    }
+
+    pub fn toggle(&mut self, file: &Path) -> io::Result<bool> {
+        if file.to_string_lossy().contains('\n') { return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot star a path containing a newline")); }
+        let starred = if self.entries.remove(file) { false } else { self.entries.insert(file.to_path_buf()) };
+        self.save()?;
+        Ok(starred)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        fs::write(path, self.entries.iter().map(|file| format!("{}\n", file.display())).collect::<String>())
+    }
+}
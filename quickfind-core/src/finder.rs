@@ -0,0 +1,173 @@
+use std::{collections::HashMap, env, fs, io::{self, Write}, path::{Path, PathBuf}, process::Stdio, sync::{Condvar, Mutex, PoisonError, atomic::{AtomicU64, AtomicUsize, Ordering}}, thread};
+use crate::{config::Document, glob, shell};
+
+const FINDERS: [&str; 2] = ["fzf", "sk"];
+const MAX_THREADS: usize = 8;
+const SKIPPED_DIRS: [&str; 11] = [".git", ".hg", ".svn", ".bzr", "_darcs", "node_modules", "target", "__pycache__", ".venv", ".tox", ".gradle"];
+
+pub fn detect(configured: Option<&str>) -> Option<String> {
+    configured.map(str::to_string).or_else(|| FINDERS.iter().find(|f| on_path(f)).map(|f| f.to_string()))
+}
+
+pub fn on_path(program: &str) -> bool {
+    let Some(paths) = env::var_os("PATH") else { return false };
+    let name = if cfg!(windows) { format!("{}.exe", program) } else { program.to_string() };
+    env::split_paths(&paths).any(|dir| dir.join(&name).is_file())
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ScanLimits {
+    pub max_depth: Option<usize>,
+    pub ignore: Vec<String>,
+    pub skip_dirs: Vec<String>,
+    pub threads: usize,
+}
+
+impl ScanLimits {
+    pub fn from_document(doc: &Document) -> ScanLimits {
+        let skip_dirs = if doc.get("scan", "skip_dirs").is_some() { doc.strings("scan", "skip_dirs") } else { SKIPPED_DIRS.iter().map(|d| d.to_string()).collect() };
+        let threads = doc.int("scan", "threads").filter(|t| *t > 0).map_or(0, |t| t as usize);
+        ScanLimits { max_depth: doc.int("scan", "max_depth").filter(|d| *d > 0).map(|d| d as usize), ignore: doc.strings("scan", "ignore"), skip_dirs, threads }
+    }
+
+    pub fn workers(&self) -> usize {
+        if self.threads > 0 { self.threads } else { thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_THREADS) }
+    }
+
+    pub fn skips(&self, name: &str) -> bool {
+        self.skip_dirs.iter().any(|dir| dir == name)
+    }
+
+    pub fn ignores(&self, relative: &Path) -> bool {
+        let name = relative.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        let relative = if cfg!(windows) { relative.to_string_lossy().replace('\\', "/") } else { relative.to_string_lossy().into_owned() };
+        self.ignore.iter().any(|pattern| match pattern.trim_matches('/') {
+            anchored if pattern.contains('/') => glob::matches(anchored, &relative),
+            pattern => glob::matches(pattern, &name),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Prefetch {
+    pending: Vec<PathBuf>,
+    listed: HashMap<PathBuf, Vec<(PathBuf, bool)>>,
+    done: bool,
+}
+
+pub fn walk(root: &Path, limits: &ScanLimits, visit: &mut dyn FnMut(&Path, bool) -> io::Result<()>) -> io::Result<()> {
+    let shared = (Mutex::new(Prefetch { pending: vec![root.to_path_buf()], ..Prefetch::default() }), Condvar::new());
+    thread::scope(|scope| {
+        for _ in 1..limits.workers() { scope.spawn(|| prefetch(root, limits, &shared)); }
+        let result = walk_in_order(root, limits, visit, &shared);
+        shared.0.lock().unwrap_or_else(PoisonError::into_inner).done = true;
+        shared.1.notify_all();
+        result
+    })
+}
+
+fn walk_in_order(root: &Path, limits: &ScanLimits, visit: &mut dyn FnMut(&Path, bool) -> io::Result<()>, shared: &(Mutex<Prefetch>, Condvar)) -> io::Result<()> {
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = stack.pop() {
+        if limits.max_depth.is_some_and(|max| depth >= max) { continue }
+        let mut subdirs = Vec::new();
+        for (path, is_dir) in listing(root, &dir, limits, shared) {
+            visit(path.strip_prefix(root).unwrap_or(&path), is_dir)?;
+            if is_dir { subdirs.push((path, depth + 1)); }
+        }
+        let deeper = limits.max_depth.is_none_or(|max| depth + 1 < max);
+        if deeper && !subdirs.is_empty() {
+            shared.0.lock().unwrap_or_else(PoisonError::into_inner).pending.extend(subdirs.iter().rev().map(|(path, _)| path.clone()));
+            shared.1.notify_all();
+        }
+        stack.extend(subdirs.into_iter().rev());
+    }
+    Ok(())
+}
+
+fn listing(root: &Path, dir: &Path, limits: &ScanLimits, (lock, ready): &(Mutex<Prefetch>, Condvar)) -> Vec<(PathBuf, bool)> {
+    let mut state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+    loop {
+        if let Some(children) = state.listed.remove(dir) { return children; }
+        if let Some(at) = state.pending.iter().rposition(|pending| pending == dir) {
+            state.pending.remove(at);
+            drop(state);
+            return children(root, dir, limits);
+        }
+        state = ready.wait(state).unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+fn prefetch(root: &Path, limits: &ScanLimits, (lock, ready): &(Mutex<Prefetch>, Condvar)) {
+    let mut state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+    while !state.done {
+        let Some(dir) = state.pending.pop() else { state = ready.wait(state).unwrap_or_else(PoisonError::into_inner); continue };
+        drop(state);
+        let children = children(root, &dir, limits);
+        state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        state.listed.insert(dir, children);
+        ready.notify_all();
+    }
+}
+
+fn children(root: &Path, dir: &Path, limits: &ScanLimits) -> Vec<(PathBuf, bool)> {
+    let Ok(read) = fs::read_dir(dir) else { return Vec::new() };
+    let mut children: Vec<_> = read.flatten().filter(|e| !e.file_name().to_string_lossy().starts_with('.')).collect();
+    children.sort_by_key(|e| e.file_name());
+    children.into_iter().filter_map(|entry| {
+        let path = entry.path();
+        if limits.ignores(path.strip_prefix(root).unwrap_or(&path)) { return None }
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        (!is_dir || !limits.skips(&entry.file_name().to_string_lossy())).then_some((path, is_dir))
+    }).collect()
+}
+
+#[derive(Default)]
+struct Tally {
+    pending: Vec<PathBuf>,
+    busy: usize,
+}
+
+pub fn disk_usage(path: &Path, cap: usize, limits: &ScanLimits) -> Option<u64> {
+    let meta = fs::symlink_metadata(path).ok()?;
+    if !meta.is_dir() { return Some(meta.len()); }
+    let (total, seen) = (AtomicU64::new(0), AtomicUsize::new(0));
+    let shared = (Mutex::new(Tally { pending: vec![path.to_path_buf()], busy: 0 }), Condvar::new());
+    thread::scope(|scope| for _ in 0..limits.workers() { scope.spawn(|| tally(cap, limits, &shared, &total, &seen)); });
+    (seen.load(Ordering::Relaxed) < cap).then(|| total.load(Ordering::Relaxed))
+}
+
+fn tally(cap: usize, limits: &ScanLimits, (lock, ready): &(Mutex<Tally>, Condvar), total: &AtomicU64, seen: &AtomicUsize) {
+    let mut state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+    while seen.load(Ordering::Relaxed) < cap {
+        let Some(dir) = state.pending.pop() else {
+            if state.busy == 0 { break }
+            state = ready.wait(state).unwrap_or_else(PoisonError::into_inner);
+            continue
+        };
+        state.busy += 1;
+        drop(state);
+        let mut subdirs = Vec::new();
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            if seen.fetch_add(1, Ordering::Relaxed) + 1 >= cap { break }
+            let Ok(meta) = entry.metadata() else { continue };
+            if !meta.is_dir() { total.fetch_add(meta.len(), Ordering::Relaxed); } else if !limits.skips(&entry.file_name().to_string_lossy()) { subdirs.push(entry.path()); }
+        }
+        state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        state.pending.extend(subdirs);
+        state.busy -= 1;
+        ready.notify_all();
+    }
+    ready.notify_all();
+}
+
+pub fn pick(finder: &str, root: &Path, limits: &ScanLimits) -> io::Result<Option<PathBuf>> {
+    let mut child = shell::command(finder).current_dir(root).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let (walk_root, limits) = (root.to_path_buf(), limits.clone());
+    let feeder = thread::spawn(move || walk(&walk_root, &limits, &mut |path, is_dir| writeln!(stdin, "{}{}", path.display(), if is_dir { "/" } else { "" })));
+    let output = child.wait_with_output()?;
+    let _ = feeder.join();
+    let choice = String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or("").trim_end_matches('/').to_string();
+    Ok((output.status.success() && !choice.is_empty()).then(|| root.join(choice)))
+}
@@ -0,0 +1,56 @@
+use std::{path::{Path, PathBuf}, process::Stdio, sync::{Mutex, atomic::{AtomicUsize, Ordering}}, thread};
+use crate::{commands, shell};
+
+#[derive(Clone)]
+pub struct Outcome {
+    pub path: PathBuf,
+    pub code: Result<Option<i32>, String>,
+    pub output: String,
+}
+
+impl Outcome {
+    pub fn success(&self) -> bool {
+        matches!(self.code, Ok(Some(0)))
+    }
+}
+
+pub fn parse_jobs(input: &str, default: usize) -> (usize, &str) {
+    let input = input.trim_start();
+    let Some(rest) = input.strip_prefix("-j") else { return (default, input) };
+    let rest = rest.trim_start();
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    match rest[..digits].parse::<usize>() {
+        Ok(jobs) if jobs > 0 => (jobs, rest[digits..].trim_start()),
+        _ => (default, input),
+    }
+}
+
+pub fn run(template: &str, dir: &Path, paths: &[PathBuf], jobs: usize) -> Vec<Outcome> {
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::with_capacity(paths.len()));
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, paths.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(index) else { break };
+                let outcome = run_one(template, dir, path);
+                if let Ok(mut outcomes) = outcomes.lock() { outcomes.push((index, outcome)); }
+            });
+        }
+    });
+    let mut outcomes = outcomes.into_inner().unwrap_or_default();
+    outcomes.sort_by_key(|(index, _)| *index);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+pub fn run_one(template: &str, dir: &Path, path: &Path) -> Outcome {
+    let script = commands::expand(template, dir, Some(path));
+    match shell::command(&script).current_dir(dir).stdin(Stdio::null()).output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            Outcome { path: path.to_path_buf(), code: Ok(output.status.code()), output: text }
+        }
+        Err(e) => Outcome { path: path.to_path_buf(), code: Err(e.to_string()), output: String::new() },
+    }
+}
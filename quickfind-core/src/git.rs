@@ -0,0 +1,90 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, process::{Command, Stdio}};
+use crate::finder;
+
+pub struct Repo {
+    pub branch: String,
+    pub ahead: usize,
+    pub behind: usize,
+    pub dirty: bool,
+    pub untracked: bool,
+}
+
+pub fn toplevel(dir: &Path) -> Option<PathBuf> {
+    if !finder::on_path("git") { return None; }
+    let output = Command::new("git").arg("-C").arg(dir).args(["rev-parse", "--show-toplevel"]).stderr(Stdio::null()).output().ok()?;
+    let root = String::from_utf8_lossy(&output.stdout).trim_end_matches(['\n', '\r']).to_string();
+    (output.status.success() && !root.is_empty()).then(|| PathBuf::from(root))
+}
+
+pub fn entry_status(dir: &Path) -> HashMap<String, String> {
+    let mut statuses = HashMap::new();
+    let Some(root) = toplevel(dir) else { return statuses };
+    let Ok(output) = Command::new("git").arg("-C").arg(dir).args(["status", "--porcelain=v1", "-z", "--", "."]).stderr(Stdio::null()).output() else { return statuses };
+    let prefix = dir.canonicalize().ok().and_then(|dir| Some(dir.strip_prefix(root.canonicalize().ok()?).ok()?.to_path_buf())).unwrap_or_default();
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut records = text.split('\0');
+    while let Some(record) = records.next() {
+        if record.len() < 4 { continue; }
+        let (code, path) = (&record[..2], &record[3..]);
+        if code.contains(['R', 'C']) { records.next(); }
+        let Ok(relative) = Path::new(path).strip_prefix(&prefix) else { continue };
+        let Some(name) = relative.components().next() else { continue };
+        let name = name.as_os_str().to_string_lossy().into_owned();
+        statuses.entry(name).and_modify(|existing: &mut String| if existing != code { *existing = " M".to_string() }).or_insert_with(|| code.to_string());
+    }
+    statuses
+}
+
+pub fn repo_state(dir: &Path) -> Option<Repo> {
+    if !finder::on_path("git") { return None; }
+    let output = Command::new("git").arg("-C").arg(dir).args(["status", "--porcelain=v2", "--branch", "-z"]).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() { return None; }
+    let mut repo = Repo { branch: String::new(), ahead: 0, behind: 0, dirty: false, untracked: false };
+    let mut oid = "";
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut records = text.split('\0');
+    while let Some(record) = records.next() {
+        match record.split_once(' ') {
+            Some(("#", header)) => match header.split_once(' ') {
+                Some(("branch.head", head)) => repo.branch = head.to_string(),
+                Some(("branch.oid", id)) => oid = id,
+                Some(("branch.ab", counts)) => for count in counts.split(' ') {
+                    if let Some(ahead) = count.strip_prefix('+') { repo.ahead = ahead.parse().unwrap_or(0); }
+                    if let Some(behind) = count.strip_prefix('-') { repo.behind = behind.parse().unwrap_or(0); }
+                },
+                _ => {}
+            },
+            Some(("2", _)) => { repo.dirty = true; records.next(); }
+            Some(("1" | "u", _)) => repo.dirty = true,
+            Some(("?", _)) => repo.untracked = true,
+            _ => {}
+        }
+    }
+    if repo.branch == "(detached)" { repo.branch = format!("(detached {})", oid.get(..7).unwrap_or(oid)); }
+    Some(repo)
+}
+
+pub fn stage(dir: &Path, paths: &[PathBuf], stage: bool) -> Result<(), String> {
+    let args: &[&str] = if stage { &["add", "--"] } else { &["restore", "--staged", "--"] };
+    let output = Command::new("git").arg("-C").arg(dir).args(args).args(paths).stdin(Stdio::null()).output().map_err(|e| format!("git: {}", e))?;
+    if output.status.success() { return Ok(()); }
+    let message = String::from_utf8_lossy(&output.stderr);
+    Err(message.lines().find(|line| !line.trim().is_empty()).unwrap_or("git failed").trim().to_string())
+}
+
+pub fn diff(dir: &Path, path: &Path, staged: bool) -> Result<Vec<String>, String> {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    let run = |args: &[&str]| Command::new("git").arg("-C").arg(dir).args(["-c", "core.quotepath=off"]).args(args).arg(relative).stdin(Stdio::null()).output().map_err(|e| format!("git: {}", e));
+    let mut output = run(if staged { &["diff", "--no-color", "--no-ext-diff", "--cached", "--"] } else { &["diff", "--no-color", "--no-ext-diff", "--"] })?;
+    if !staged && output.status.success() && output.stdout.is_empty() && path.is_file() && !tracked(dir, path) {
+        output = run(&["diff", "--no-color", "--no-ext-diff", "--no-index", "--", if cfg!(windows) { "NUL" } else { "/dev/null" }])?;
+    }
+    if !output.status.success() && output.status.code() != Some(1) {
+        return Err(String::from_utf8_lossy(&output.stderr).lines().find(|line| !line.trim().is_empty()).unwrap_or("git diff failed").trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(|line| line.replace('\t', "    ")).collect())
+}
+
+fn tracked(dir: &Path, path: &Path) -> bool {
+    Command::new("git").arg("-C").arg(dir).args(["ls-files", "--error-unmatch", "--"]).arg(path).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok_and(|s| s.success())
+}
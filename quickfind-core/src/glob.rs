@@ -0,0 +1,44 @@
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => { backtrack = Some((p, t)); p += 1; continue }
+            Some('?') if text[t] != '/' => { p += 1; t += 1; continue }
+            Some('[') => match class(&pattern[p..], text[t]) {
+                Some((true, len)) => { p += len; t += 1; continue }
+                None if text[t] == '[' => { p += 1; t += 1; continue }
+                _ => {}
+            },
+            Some('\\') if pattern.get(p + 1) == Some(&text[t]) => { p += 2; t += 1; continue }
+            Some(&c) if c == text[t] && c != '[' && c != '\\' => { p += 1; t += 1; continue }
+            _ => {}
+        }
+        match backtrack {
+            Some((star, from)) if text[from] != '/' => { backtrack = Some((star, from + 1)); p = star + 1; t = from + 1; }
+            _ => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some('!' | '^'));
+    if negated { i += 1; }
+    let mut found = false;
+    let mut first = true;
+    loop {
+        let &lo = pattern.get(i)?;
+        if lo == ']' && !first { return Some((found != negated, i + 1)); }
+        first = false;
+        if pattern.get(i + 1) == Some(&'-') && let Some(&hi) = pattern.get(i + 2) && hi != ']' {
+            found |= lo <= c && c <= hi;
+            i += 3;
+        } else {
+            found |= lo == c;
+            i += 1;
+        }
+    }
+}
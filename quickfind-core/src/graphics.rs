@@ -0,0 +1,93 @@
+use std::{env, fs, io::{self, Read}, path::{Path, PathBuf}, process::{Command, Stdio}};
+use crate::{clipboard::base64, finder};
+
+const IMAGE_ID: u32 = 4151;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Protocol {
+    Kitty,
+    Iterm,
+    Sixel,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Placement {
+    pub image: PathBuf,
+    pub x: u16,
+    pub y: u16,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+pub fn detect(setting: &str) -> Option<Protocol> {
+    let protocol = match setting {
+        "kitty" => Some(Protocol::Kitty),
+        "iterm" => Some(Protocol::Iterm),
+        "sixel" => Some(Protocol::Sixel),
+        "auto" => sniff(),
+        _ => None,
+    };
+    if protocol == Some(Protocol::Sixel) && !finder::on_path("chafa") { return None; }
+    if protocol.is_some() && in_tmux() { let _ = tmux(&["set", "-p", "allow-passthrough", "on"]); }
+    protocol
+}
+
+fn sniff() -> Option<Protocol> {
+    let outer = if in_tmux() { tmux(&["display-message", "-p", "#{client_termname} #{client_termtype}"]).unwrap_or_default() } else { String::new() };
+    let terminal = format!("{} {} {}", env::var("TERM").unwrap_or_default(), env::var("TERM_PROGRAM").unwrap_or_default(), outer).to_lowercase();
+    if env::var_os("KITTY_WINDOW_ID").is_some() || ["kitty", "ghostty", "wezterm"].iter().any(|t| terminal.contains(t)) { Some(Protocol::Kitty) }
+    else if terminal.contains("iterm") { Some(Protocol::Iterm) }
+    else if ["foot", "mlterm", "contour", "sixel"].iter().any(|t| terminal.contains(t)) { Some(Protocol::Sixel) }
+    else { None }
+}
+
+pub fn accepts(protocol: Protocol, image: &Path) -> bool {
+    if protocol != Protocol::Kitty { return true; }
+    let mut magic = [0u8; 8];
+    fs::File::open(image).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && &magic == b"\x89PNG\r\n\x1a\n"
+}
+
+pub fn draw(protocol: Protocol, placement: &Placement) -> io::Result<String> {
+    let Placement { image, columns, rows, .. } = placement;
+    match protocol {
+        Protocol::Kitty => {
+            let encoded = base64(&fs::read(image)?);
+            let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+            Ok(chunks.iter().enumerate().map(|(i, chunk)| {
+                let more = if i + 1 < chunks.len() { 1 } else { 0 };
+                let keys = if i == 0 { format!("a=T,f=100,i={},q=2,C=1,c={},r={},m={}", IMAGE_ID, columns, rows, more) } else { format!("m={}", more) };
+                passthrough(&format!("\x1b_G{};{}\x1b\\", keys, String::from_utf8_lossy(chunk)))
+            }).collect())
+        }
+        Protocol::Iterm => {
+            let data = fs::read(image)?;
+            Ok(passthrough(&format!("\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1;doNotMoveCursor=1:{}\x07", data.len(), columns, rows, base64(&data))))
+        }
+        Protocol::Sixel => {
+            let output = Command::new("chafa").args(["--format", "sixels", "--animate", "off", "--polite", "on", "--size"]).arg(format!("{}x{}", columns, rows)).arg(image)
+                .env_remove("TMUX").stdin(Stdio::null()).stderr(Stdio::null()).output()?;
+            if !output.status.success() { return Err(io::Error::other("chafa could not draw the image")); }
+            Ok(passthrough(&String::from_utf8_lossy(&output.stdout)))
+        }
+    }
+}
+
+pub fn clear(protocol: Protocol) -> String {
+    match protocol {
+        Protocol::Kitty => passthrough(&format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", IMAGE_ID)),
+        Protocol::Iterm | Protocol::Sixel => String::new(),
+    }
+}
+
+pub fn in_tmux() -> bool {
+    env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+}
+
+fn passthrough(sequence: &str) -> String {
+    if in_tmux() { format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")) } else { sequence.to_string() }
+}
+
+fn tmux(args: &[&str]) -> Option<String> {
+    let output = Command::new("tmux").args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,37 @@
+use std::{fs, io, path::PathBuf};
+use crate::config;
+
+const MAX_PER_KIND: usize = 100;
+
+#[derive(Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<(String, String)>,
+}
+
+impl History {
+    pub fn load() -> History {
+        let path = config::config_dir().join("history");
+        let entries = fs::read_to_string(&path).unwrap_or_default().lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(kind, entry)| (kind.to_string(), entry.to_string()))
+            .collect();
+        History { path: Some(path), entries }
+    }
+
+    pub fn entries(&self, kind: &str) -> Vec<&str> {
+        self.entries.iter().filter(|(k, _)| k == kind).map(|(_, e)| e.as_str()).collect()
+    }
+
+    pub fn push(&mut self, kind: &str, entry: &str) -> io::Result<()> {
+        if entry.is_empty() || entry.contains('\n') { return Ok(()); }
+        self.entries.retain(|(k, e)| !(k == kind && e == entry));
+        self.entries.push((kind.to_string(), entry.to_string()));
+        let excess = self.entries(kind).len().saturating_sub(MAX_PER_KIND);
+        let mut dropped = 0;
+        self.entries.retain(|(k, _)| { let keep = k != kind || dropped >= excess; if !keep { dropped += 1; } keep });
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        fs::write(path, self.entries.iter().map(|(k, e)| format!("{}\t{}\n", k, e)).collect::<String>())
+    }
+}
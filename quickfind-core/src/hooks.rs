@@ -0,0 +1,36 @@
+use std::{path::Path, process::{Command, Stdio}, thread};
+use crate::{config::Document, shell};
+
+#[derive(Clone, Default)]
+pub struct Hooks {
+    pub on_dir_enter: Option<String>,
+    pub on_file_open: Option<String>,
+    pub on_exit: Option<String>,
+    pub on_selection_change: Option<String>,
+}
+
+impl Hooks {
+    pub fn from_document(doc: &Document) -> Hooks {
+        let hook = |key: &str| doc.str("hooks", key).map(str::to_string);
+        Hooks { on_dir_enter: hook("on_dir_enter"), on_file_open: hook("on_file_open"), on_exit: hook("on_exit"), on_selection_change: hook("on_selection_change") }
+    }
+}
+
+pub fn fire(hook: Option<&String>, event: &str, dir: &Path, selected: Option<&Path>) {
+    let Some(script) = hook else { return };
+    let spawned = prepare(script, event, dir, selected).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+    if let Ok(mut child) = spawned { thread::spawn(move || child.wait()); }
+}
+
+pub fn fire_and_wait(hook: Option<&String>, event: &str, dir: &Path, selected: Option<&Path>) -> std::io::Result<()> {
+    let Some(script) = hook else { return Ok(()) };
+    prepare(script, event, dir, selected).status()?;
+    Ok(())
+}
+
+fn prepare(script: &str, event: &str, dir: &Path, selected: Option<&Path>) -> Command {
+    let mut command = shell::command(script);
+    if dir.is_dir() { command.current_dir(dir); }
+    command.env("QF_EVENT", event).env("QF_DIR", dir).env("QF_SELECTED", selected.map(|p| p.as_os_str()).unwrap_or_default());
+    command
+}
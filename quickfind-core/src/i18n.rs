@@ -0,0 +1,81 @@
+use std::{collections::HashMap, env, fmt::Display, fs, path::PathBuf, sync::OnceLock};
+use crate::config;
+
+static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+pub fn init(setting: &str) -> Result<Option<PathBuf>, String> {
+    let Some(language) = language(setting) else { return Ok(None) };
+    let dir = config::config_dir().join("locales");
+    let candidates = [language.clone(), language.split('_').next().unwrap_or_default().to_string()];
+    let Some(path) = candidates.iter().map(|name| dir.join(format!("{}.po", name))).find(|path| path.is_file()) else { return Ok(None) };
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let _ = CATALOG.set(parse_po(&text));
+    Ok(Some(path))
+}
+
+fn language(setting: &str) -> Option<String> {
+    let raw = match setting {
+        "auto" => ["LC_ALL", "LC_MESSAGES", "LANG"].iter().find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))?,
+        other => other.to_string(),
+    };
+    let language = raw.split(['.', '@']).next()?.to_string();
+    (!matches!(language.as_str(), "" | "C" | "POSIX" | "en" | "en_US")).then_some(language)
+}
+
+pub fn tr(text: &'static str) -> &'static str {
+    CATALOG.get().and_then(|catalog| catalog.get(text)).map_or(text, String::as_str)
+}
+
+pub fn translate(text: &str) -> String {
+    CATALOG.get().and_then(|catalog| catalog.get(text)).map_or(text, String::as_str).to_string()
+}
+
+pub fn trf(text: &'static str, args: &[&dyn Display]) -> String {
+    let mut pieces = tr(text).split("{}");
+    let mut filled = pieces.next().unwrap_or_default().to_string();
+    for (i, piece) in pieces.enumerate() {
+        if let Some(arg) = args.get(i) { filled.push_str(&arg.to_string()); }
+        filled.push_str(piece);
+    }
+    filled
+}
+
+pub fn parse_po(text: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    let (mut id, mut translation, mut in_id) = (String::new(), String::new(), true);
+    let mut flush = |id: &mut String, translation: &mut String| {
+        if !id.is_empty() && !translation.is_empty() { messages.insert(std::mem::take(id), std::mem::take(translation)); }
+        id.clear();
+        translation.clear();
+    };
+    for line in text.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            flush(&mut id, &mut translation);
+            (id, in_id) = (unquote(rest), true);
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            (translation, in_id) = (unquote(rest), false);
+        } else if line.starts_with('"') {
+            if in_id { id.push_str(&unquote(line)); } else { translation.push_str(&unquote(line)); }
+        } else {
+            flush(&mut id, &mut translation);
+        }
+    }
+    flush(&mut id, &mut translation);
+    messages
+}
+
+fn unquote(text: &str) -> String {
+    let inner = text.trim().strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or_default();
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' { unquoted.push(c); continue }
+        match chars.next() {
+            Some('n') => unquoted.push('\n'),
+            Some('t') => unquoted.push('\t'),
+            Some(other) => unquoted.push(other),
+            None => {}
+        }
+    }
+    unquoted
+}
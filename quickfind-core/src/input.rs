@@ -0,0 +1,702 @@
+use std::{path::Path, time::Duration};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crate::{actions::{self, Action}, compare::Difference, devices, events::EventSource, finder, hooks, i18n, perms, preview::Jump, state::{AppState, Clash, ExternalTask, PopupMode, Selection, Transfer}, tags, xattr};
+
+pub fn handle_event(app_state: &mut AppState, event: &Event) {
+    match event {
+        Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press | KeyEventKind::Repeat, .. }) => {
+            if let Err(err) = handle_input(app_state, *code, *modifiers) { app_state.status = Some(i18n::translate(&err.to_string())); }
+        }
+        Event::Resize(..) => app_state.clamp_selection(),
+        Event::FocusGained | Event::FocusLost => app_state.focus_changed(*event == Event::FocusGained),
+        _ => {}
+    }
+}
+
+pub fn replay(app_state: &mut AppState, events: &mut impl EventSource) -> std::io::Result<()> {
+    while !app_state.break_now && let Some(event) = events.next_event(Duration::ZERO)? { handle_event(app_state, &event); }
+    Ok(())
+}
+
+pub fn handle_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
+    app_state.status = None;
+    if app_state.popup_mode != PopupMode::None { handle_popup_input(app_state, code, modifiers)?; } else { handle_main_input(app_state, code, modifiers)?; }
+    Ok(())
+}
+
+fn handle_main_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
+    let count = std::mem::take(&mut app_state.count);
+    match code {
+        KeyCode::Char(digit @ '0'..='9') if (digit != '0' || count > 0) && !app_state.config.commands.iter().any(|cmd| cmd.key == Some(digit)) && !app_state.plugins.iter().any(|p| p.key == Some(digit)) => {
+            app_state.count = (count * 10 + digit.to_digit(10).unwrap_or(0) as usize).min(99_999);
+            app_state.status = Some(app_state.count.to_string());
+        }
+        KeyCode::Enter if app_state.choosing => {
+            if app_state.marks.is_empty() && app_state.entries.get(app_state.selected_index).is_some_and(|name| app_state.dir_names.contains(name)) { return handle_main_input(app_state, KeyCode::Right, KeyModifiers::NONE); }
+            app_state.chosen = if app_state.marks.is_empty() { app_state.get_selected_path().into_iter().collect() } else { app_state.marks.iter().cloned().collect() };
+            app_state.break_now = !app_state.chosen.is_empty();
+        }
+        KeyCode::Enter => app_state.break_now = true,
+        KeyCode::Esc => (app_state.break_now, app_state.cancelled) = (true, true),
+        KeyCode::Up | KeyCode::Down if modifiers.contains(KeyModifiers::SHIFT) && app_state.show_preview => app_state.previewer.scroll_by(if code == KeyCode::Up { -1 } else { 1 }),
+        KeyCode::Char('d') | KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) && app_state.show_preview => { let page = app_state.previewer.half_page(); app_state.previewer.scroll_by(if code == KeyCode::Char('u') { -page } else { page }); }
+        KeyCode::Tab if app_state.show_preview && app_state.results.as_ref().is_none_or(|r| !r.visible) => app_state.popup_mode = PopupMode::Preview,
+        KeyCode::Left | KeyCode::Right if modifiers.contains(KeyModifiers::SHIFT) && app_state.show_preview => app_state.previewer.scroll_columns(code == KeyCode::Right),
+        KeyCode::Right => {
+            if let Some(path_candidate) = app_state.get_selected_path() {
+                if app_state.entries.get(app_state.selected_index).is_some_and(|name| app_state.dir_names.contains(name)) {
+                    app_state.focus_dir = path_candidate;
+                    app_state.refresh_entries()?;
+                    app_state.selected_index = 0;
+                    app_state.list_state.select(Some(0));
+                } else if app_state.fs.is_local() {
+                    hooks::fire(app_state.config.hooks.on_file_open.as_ref(), "file_open", &app_state.focus_dir, Some(&path_candidate));
+                }
+            }
+        }
+        KeyCode::Left if cfg!(windows) && app_state.fs.is_local() && app_state.focus_dir.parent().is_none() => {
+            app_state.drives = devices::drive_roots();
+            app_state.popup_index = app_state.drives.iter().position(|d| *d == app_state.focus_dir).unwrap_or(0);
+            app_state.popup_mode = PopupMode::Drives;
+        }
+        KeyCode::Left => {
+            app_state.focus_dir.pop();
+            app_state.refresh_entries()?;
+            app_state.selected_index = 0;
+            app_state.list_state.select(Some(0));
+        }
+        KeyCode::Up => {
+            app_state.selected_index = app_state.selected_index.saturating_sub(count.max(1));
+            app_state.list_state.select(Some(app_state.selected_index));
+        }
+        KeyCode::Down => {
+            app_state.selected_index = (app_state.selected_index + count.max(1)).min(app_state.entries.len().saturating_sub(1));
+            app_state.list_state.select(Some(app_state.selected_index));
+        }
+        KeyCode::Char('a') if modifiers.contains(KeyModifiers::CONTROL) => app_state.select(Selection::All),
+        KeyCode::Char('n') if modifiers.contains(KeyModifiers::CONTROL) => app_state.select(Selection::None),
+        KeyCode::Char('*') => app_state.select(Selection::Invert),
+        KeyCode::Char('n') if !modifiers.contains(KeyModifiers::SHIFT) => {
+            app_state.popup_mode = PopupMode::CreateFile;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('N') | KeyCode::Char('n') if modifiers.contains(KeyModifiers::SHIFT) => {
+            app_state.popup_mode = PopupMode::CreateDir;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('d') | KeyCode::Char('D') => {
+            if let Some(name) = app_state.entries.get(app_state.selected_index).cloned() { app_state.request(Action::Delete(name))?; }
+        }
+        KeyCode::Char('X') => {
+            if !app_state.config.shred {
+                app_state.status = Some(i18n::tr("Secure wipe is disabled; set shred = true under [delete] in config").to_string());
+            } else if let Some(name) = app_state.entries.get(app_state.selected_index).cloned() {
+                app_state.request(Action::Shred(name))?;
+            }
+        }
+        KeyCode::Char('T') => app_state.cycle_theme()?,
+        KeyCode::Char('g') if modifiers.contains(KeyModifiers::CONTROL) && !app_state.fs.is_local() => {
+            if let Some(name) = app_state.entries.get(app_state.selected_index).cloned() { app_state.apply(Action::Download(name))?; }
+        }
+        KeyCode::Char('p') if modifiers.contains(KeyModifiers::CONTROL) && !app_state.fs.is_local() => {
+            app_state.popup_mode = PopupMode::Upload;
+            app_state.input_buffer = format!("{}/", app_state.local_dir.display());
+        }
+        KeyCode::Char('f') if !modifiers.contains(KeyModifiers::CONTROL) => {
+            if !app_state.fs.is_local() { return Err(i18n::tr("find only works on local directories").into()); }
+            app_state.popup_mode = PopupMode::Find;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('g') if !modifiers.contains(KeyModifiers::CONTROL) => {
+            if !app_state.fs.is_local() { return Err(i18n::tr("content search only works on local directories").into()); }
+            app_state.popup_mode = PopupMode::Grep;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('F') | KeyCode::Tab => {
+            let Some(results) = app_state.results.as_mut() else { return Err(i18n::tr("no search results yet (f to find, g to search contents)").into()) };
+            results.visible = true;
+            app_state.popup_mode = PopupMode::Results;
+        }
+        KeyCode::Char(']') | KeyCode::Char('[') => {
+            let Some(results) = app_state.results.as_mut() else { return Err(i18n::tr("no search results yet (f to find, g to search contents)").into()) };
+            results.step(code == KeyCode::Char(']'));
+            app_state.jump_to_hit()?;
+        }
+        KeyCode::Backspace if app_state.results.is_some() => app_state.jump_back()?,
+        KeyCode::Char('f') if modifiers.contains(KeyModifiers::CONTROL) => {
+            if !app_state.fs.is_local() { return Err(i18n::tr("fuzzy finding only works on local directories").into()); }
+            let finder = finder::detect(app_state.config.finder.as_deref()).ok_or("no fuzzy finder found (install fzf or sk, or set finder under [search])")?;
+            app_state.pending_task = Some(ExternalTask::Finder(finder));
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            if let Some(current_name) = app_state.entries.get(app_state.selected_index) {
+                app_state.popup_mode = PopupMode::Rename;
+                app_state.input_buffer = current_name.clone();
+            }
+        }
+        KeyCode::Char('M') => {
+            app_state.devices = devices::removable()?;
+            app_state.popup_mode = PopupMode::Devices;
+            app_state.popup_index = 0;
+        }
+        KeyCode::Char('/') => app_state.popup_mode = PopupMode::Filter,
+        KeyCode::Char('.') => app_state.toggle_hidden()?,
+        KeyCode::Char('i') => app_state.toggle_ignored()?,
+        KeyCode::Char('L') => app_state.toggle_long_listing()?,
+        KeyCode::Char('H') => app_state.toggle_groups()?,
+        KeyCode::Char('o') => app_state.cycle_sort(false)?,
+        KeyCode::Char('O') => app_state.cycle_sort(true)?,
+        KeyCode::Char('p') if !modifiers.contains(KeyModifiers::CONTROL) => app_state.show_properties()?,
+        KeyCode::Char('P') => app_state.toggle_preview(),
+        KeyCode::Char('Z') => app_state.toggle_zen(),
+        KeyCode::Char('v') if modifiers.contains(KeyModifiers::CONTROL) => {
+            app_state.popup_mode = PopupMode::Paste;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('y') if !app_state.entries.is_empty() => {
+            app_state.popup_mode = PopupMode::Yank;
+            app_state.status = Some(i18n::tr("Copy: p path | n name | d directory | c contents").to_string());
+        }
+        KeyCode::Char('?') => app_state.toggle_help()?,
+        KeyCode::Char('v') => app_state.open_pager()?,
+        KeyCode::Char('x') if app_state.show_preview => app_state.previewer.toggle_hex(),
+        KeyCode::Char('z') if app_state.show_preview => app_state.previewer.unfolded = !app_state.previewer.unfolded,
+        KeyCode::Char(' ') => app_state.toggle_mark(),
+        KeyCode::Char('c') => app_state.paste_marks(Transfer::Copy)?,
+        KeyCode::Char('m') => app_state.paste_marks(Transfer::Move)?,
+        KeyCode::Char('l') => app_state.paste_marks(Transfer::Link)?,
+        KeyCode::Char('G') if !app_state.marks.is_empty() => {
+            app_state.popup_mode = PopupMode::Group;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('G') => return Err(i18n::tr("nothing is marked (Space marks the selected entry)").into()),
+        KeyCode::Char('I') => app_state.inspect_marks(),
+        KeyCode::Char('s') => app_state.toggle_star()?,
+        KeyCode::Char('A') => app_state.stage(true)?,
+        KeyCode::Char('U') => app_state.stage(false)?,
+        KeyCode::Char('=') => app_state.show_diff(false)?,
+        KeyCode::Char('W') => app_state.toggle_dry_run(),
+        KeyCode::Char('J') => {
+            app_state.popup_index = 0;
+            app_state.popup_mode = PopupMode::Jobs;
+        }
+        KeyCode::Char('E') => {
+            if !app_state.fs.is_local() { return Err(i18n::tr("tree export only works on local directories").into()); }
+            app_state.input_buffer = "tree.txt".to_string();
+            app_state.popup_mode = PopupMode::Tree;
+        }
+        KeyCode::Char('C') => {
+            if !app_state.fs.is_local() { return Err(i18n::tr("comparing only works on local directories").into()); }
+            app_state.input_buffer = app_state.comparison.as_ref().map(|c| c.other.display().to_string()).unwrap_or_default();
+            app_state.popup_mode = PopupMode::Compare;
+        }
+        KeyCode::Char('S') => {
+            app_state.popup_index = 0;
+            app_state.popup_mode = PopupMode::Favorites;
+        }
+        KeyCode::Char('t') if !modifiers.contains(KeyModifiers::CONTROL) => {
+            if !app_state.fs.is_local() { return Err(i18n::tr("tags only work on local files").into()); }
+            let Some(path) = app_state.get_selected_path() else { return Ok(()) };
+            app_state.input_buffer = app_state.tags.get(&path).join(" ");
+            app_state.popup_mode = PopupMode::Tag;
+        }
+        KeyCode::Char('!') => {
+            if app_state.marks.is_empty() { return Err(i18n::tr("nothing is marked (Space marks the selected entry)").into()); }
+            if !app_state.fs.is_local() { return Err(i18n::tr("for-each commands only work on local directories").into()); }
+            app_state.popup_mode = PopupMode::ForEach;
+            app_state.input_buffer.clear();
+        }
+        KeyCode::Char('V') if !app_state.entries.is_empty() => {
+            app_state.visual_anchor = app_state.selected_index;
+            app_state.popup_mode = PopupMode::Visual;
+        }
+        KeyCode::Char(':') => {
+            app_state.popup_mode = PopupMode::Palette;
+            app_state.input_buffer.clear();
+            app_state.popup_index = 0;
+        }
+        KeyCode::Char(c) => {
+            if let Some(index) = app_state.config.commands.iter().position(|cmd| cmd.key == Some(c)) {
+                app_state.pending_task = Some(ExternalTask::Command(index));
+            } else if let Some(index) = app_state.plugins.iter().position(|p| p.key == Some(c)) {
+                app_state.pending_task = Some(ExternalTask::Plugin(index));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_popup_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
+    if app_state.popup_mode == PopupMode::Palette {
+        match code {
+            KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+            KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(app_state.palette_items().len().saturating_sub(1)),
+            KeyCode::Enter => {
+                app_state.pending_task = app_state.palette_items().into_iter().nth(app_state.popup_index).map(|(_, _, task)| task);
+                app_state.popup_mode = PopupMode::None;
+                app_state.input_buffer.clear();
+            }
+            KeyCode::Backspace => { app_state.input_buffer.pop(); app_state.popup_index = 0; }
+            KeyCode::Char(c) => { app_state.input_buffer.push(c); app_state.popup_index = 0; }
+            KeyCode::Esc => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); }
+            _ => {}
+        }
+        return Ok(());
+    }
+    if app_state.popup_mode == PopupMode::Devices { return handle_devices_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Drives { return handle_drives_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Visual {
+        match code {
+            KeyCode::Up | KeyCode::Down => handle_main_input(app_state, code, modifiers)?,
+            KeyCode::Enter | KeyCode::Char('V') | KeyCode::Char(' ') => app_state.commit_visual(),
+            KeyCode::Esc => app_state.popup_mode = PopupMode::None,
+            _ => {}
+        }
+        return Ok(());
+    }
+    if app_state.popup_mode == PopupMode::Marks { return handle_marks_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Favorites { return handle_favorites_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Properties { return handle_properties_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Preview { return handle_preview_input(app_state, code, modifiers); }
+    if app_state.popup_mode == PopupMode::Diff { return handle_diff_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Comparison { return handle_comparison_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Sync { return handle_sync_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Jobs { return handle_jobs_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Yank {
+        app_state.popup_mode = PopupMode::None;
+        match code {
+            KeyCode::Char(part @ ('p' | 'n' | 'd')) => app_state.yank(part),
+            KeyCode::Char('c') => app_state.yank_contents()?,
+            _ => {}
+        }
+        return Ok(());
+    }
+    if app_state.popup_mode == PopupMode::Conflict {
+        match code {
+            KeyCode::Char('s') => app_state.resolve_conflict(Clash::Skip)?,
+            KeyCode::Char('b') => app_state.resolve_conflict(Clash::KeepBoth)?,
+            KeyCode::Char('g') if app_state.pending_transfer == Some(Transfer::Move) => app_state.resolve_conflict(Clash::Merge)?,
+            _ => { (app_state.popup_mode, app_state.pending_transfer) = (PopupMode::None, None); app_state.status = Some(i18n::tr("Paste cancelled").to_string()); }
+        }
+        return Ok(());
+    }
+    if app_state.popup_mode == PopupMode::Report {
+        match code {
+            KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+            KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(app_state.report.len().saturating_sub(1)),
+            KeyCode::Char('r') => app_state.retry_failed(app_state.report_job)?,
+            KeyCode::Enter | KeyCode::Esc => app_state.popup_mode = PopupMode::None,
+            _ => {}
+        }
+        return Ok(());
+    }
+    if app_state.popup_mode == PopupMode::Results { return handle_results_input(app_state, code); }
+    if app_state.popup_mode == PopupMode::Filter { return handle_filter_input(app_state, code, modifiers); }
+    if matches!(app_state.popup_mode, PopupMode::Find | PopupMode::Grep) && toggle_match_option(app_state, code, modifiers) { return Ok(()); }
+    match code {
+        KeyCode::Esc => { app_state.history_cursor = None; app_state.popup_mode = match app_state.popup_mode { PopupMode::Xattr | PopupMode::Chmod => PopupMode::Properties, PopupMode::PreviewSearch => PopupMode::Preview, _ => PopupMode::None }; app_state.input_buffer.clear(); }
+        KeyCode::Enter => { app_state.remember(); execute_popup_action(app_state)?; }
+        KeyCode::Up | KeyCode::Down => app_state.recall(code == KeyCode::Up)?,
+        KeyCode::Backspace => { app_state.input_buffer.pop(); app_state.history_cursor = None; }
+        KeyCode::Char(c) => { app_state.input_buffer.push(c); app_state.history_cursor = None; }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_devices_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    match code {
+        KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(app_state.devices.len().saturating_sub(1)),
+        KeyCode::Enter => {
+            let Some(device) = app_state.devices.get(app_state.popup_index) else { return Ok(()) };
+            let mountpoint = match &device.mountpoint {
+                Some(mountpoint) => mountpoint.clone(),
+                None => devices::mount(device)?,
+            };
+            app_state.popup_mode = PopupMode::None;
+            app_state.open(&mountpoint.to_string_lossy())?;
+            app_state.refresh_entries()?;
+        }
+        KeyCode::Char('u') => {
+            let Some(device) = app_state.devices.get(app_state.popup_index) else { return Ok(()) };
+            let Some(mountpoint) = device.mountpoint.clone() else { return Ok(()) };
+            devices::unmount(device)?;
+            app_state.status = Some(i18n::trf("Unmounted {}", &[&device.path]));
+            if app_state.fs.is_local() && app_state.focus_dir.starts_with(&mountpoint) {
+                app_state.focus_dir = mountpoint.parent().map(Path::to_path_buf).unwrap_or(mountpoint);
+                app_state.refresh_entries()?;
+            }
+            app_state.devices = devices::removable()?;
+        }
+        KeyCode::Esc => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_diff_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(diff) = app_state.diff.as_mut() else { app_state.popup_mode = PopupMode::None; return Ok(()) };
+    match code {
+        KeyCode::Up => diff.scroll = diff.scroll.saturating_sub(1),
+        KeyCode::Down => diff.scroll += 1,
+        KeyCode::PageUp => diff.scroll = diff.scroll.saturating_sub(20),
+        KeyCode::PageDown | KeyCode::Char(' ') => diff.scroll += 20,
+        KeyCode::Home => diff.scroll = 0,
+        KeyCode::End => diff.scroll = usize::MAX,
+        KeyCode::Char('s') => { let staged = !diff.staged; app_state.show_diff(staged)?; }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('=') => { app_state.diff = None; app_state.popup_mode = PopupMode::None; }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_preview_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
+    let previewer = &mut app_state.previewer;
+    let page = previewer.half_page();
+    match code {
+        KeyCode::Up => previewer.scroll_by(-1),
+        KeyCode::Down => previewer.scroll_by(1),
+        KeyCode::PageUp => previewer.scroll_by(-2 * page),
+        KeyCode::PageDown => previewer.scroll_by(2 * page),
+        KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => previewer.scroll_by(-page),
+        KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => previewer.scroll_by(page),
+        KeyCode::Home => previewer.scroll = 0,
+        KeyCode::End => previewer.scroll = usize::MAX,
+        KeyCode::Left | KeyCode::Right => previewer.scroll_columns(code == KeyCode::Right),
+        KeyCode::Char('z') => previewer.unfolded = !previewer.unfolded,
+        KeyCode::Char('x') => previewer.toggle_hex(),
+        KeyCode::Char('n') if !previewer.query.is_empty() => previewer.jump = Some(Jump::Next),
+        KeyCode::Char('N') if !previewer.query.is_empty() => previewer.jump = Some(Jump::Previous),
+        KeyCode::Char('v') | KeyCode::Enter => app_state.open_pager()?,
+        KeyCode::Char('/') => {
+            app_state.input_buffer = previewer.query.clone();
+            app_state.popup_mode = PopupMode::PreviewSearch;
+        }
+        KeyCode::Esc | KeyCode::Tab => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_properties_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(properties) = app_state.properties.as_mut() else { app_state.popup_mode = PopupMode::None; return Ok(()) };
+    if code != KeyCode::Char('d') { properties.delete_armed = false; }
+    let items = properties.streams.len() + properties.xattrs.len();
+    match code {
+        KeyCode::Up => { app_state.popup_index = app_state.popup_index.saturating_sub(1); properties.preview = None; }
+        KeyCode::Down => { app_state.popup_index = (app_state.popup_index + 1).min(items.saturating_sub(1)); properties.preview = None; }
+        KeyCode::Char('v') => app_state.view_stream()?,
+        KeyCode::Char('d') => app_state.delete_property_item()?,
+        KeyCode::Char('m') => {
+            if !perms::SUPPORTED || !app_state.fs.is_local() { return Err(i18n::tr("permission modes can only be changed on local Unix files").into()); }
+            app_state.input_buffer = properties.meta.as_ref().and_then(|meta| meta.mode).map(|mode| format!("{:o}", mode & 0o7777)).unwrap_or_default();
+            app_state.popup_mode = PopupMode::Chmod;
+        }
+        KeyCode::Char('a') => {
+            if !xattr::SUPPORTED || !app_state.fs.is_local() { return Err(i18n::tr("extended attributes are only available on local Linux and macOS files").into()); }
+            app_state.input_buffer.clear();
+            app_state.popup_mode = PopupMode::Xattr;
+        }
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('p') => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_marks_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    match code {
+        KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(app_state.mark_sizes.len().saturating_sub(1)),
+        KeyCode::Char(' ') | KeyCode::Char('x') | KeyCode::Delete => app_state.unmark_inspected(),
+        KeyCode::Enter => {
+            let Some((path, _)) = app_state.mark_sizes.get(app_state.popup_index).cloned() else { return Ok(()) };
+            app_state.popup_mode = PopupMode::None;
+            app_state.reveal(&path)?;
+        }
+        KeyCode::Esc | KeyCode::Char('I') => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_comparison_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(comparison) = &app_state.comparison else { app_state.popup_mode = PopupMode::None; return Ok(()) };
+    match code {
+        KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(comparison.differences.len().saturating_sub(1)),
+        KeyCode::Enter => {
+            let Some((name, difference)) = comparison.differences.get(app_state.popup_index) else { return Ok(()) };
+            let path = if *difference == Difference::OnlyThere { comparison.other.join(name) } else { comparison.here.join(name) };
+            app_state.popup_mode = PopupMode::None;
+            app_state.reveal(&path)?;
+        }
+        KeyCode::Char('s') => app_state.plan_sync(false)?,
+        KeyCode::Esc | KeyCode::Char('C') => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_jobs_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let selected = app_state.jobs.list.get(app_state.popup_index).map(|job| job.id);
+    match (code, selected) {
+        (KeyCode::Up, _) => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        (KeyCode::Down, _) => app_state.popup_index = (app_state.popup_index + 1).min(app_state.jobs.list.len().saturating_sub(1)),
+        (KeyCode::Char('p') | KeyCode::Char(' '), Some(id)) => app_state.jobs.toggle_pause(id),
+        (KeyCode::Char('x') | KeyCode::Delete, Some(id)) => app_state.jobs.cancel(id),
+        (KeyCode::Char('+') | KeyCode::Char('-'), Some(_)) => app_state.popup_index = app_state.jobs.shift(app_state.popup_index, code == KeyCode::Char('+')),
+        (KeyCode::Char('r'), Some(id)) => app_state.retry_failed(Some(id))?,
+        (KeyCode::Enter, Some(id)) => app_state.view_job(id),
+        (KeyCode::Char('c'), _) => { app_state.jobs.clear_finished(); app_state.popup_index = 0; }
+        (KeyCode::Esc | KeyCode::Char('J'), _) => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_sync_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(plan) = &app_state.sync_plan else { app_state.popup_mode = PopupMode::None; return Ok(()) };
+    match code {
+        KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(plan.steps.len().saturating_sub(1)),
+        KeyCode::Char('d') => { let delete = !plan.delete; app_state.plan_sync(delete)?; }
+        KeyCode::Char('y') | KeyCode::Enter if !plan.steps.is_empty() => app_state.run_sync()?,
+        KeyCode::Esc => { app_state.sync_plan = None; app_state.popup_index = 0; app_state.popup_mode = PopupMode::Comparison; }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_favorites_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let count = app_state.favorites.iter().count();
+    let selected = app_state.favorites.iter().nth(app_state.popup_index).map(Path::to_path_buf);
+    match code {
+        KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(count.saturating_sub(1)),
+        KeyCode::Char(' ') | KeyCode::Char('x') | KeyCode::Delete => {
+            let Some(path) = selected else { return Ok(()) };
+            app_state.favorites.toggle(&path)?;
+            app_state.popup_index = app_state.popup_index.min(count.saturating_sub(2));
+        }
+        KeyCode::Enter => {
+            let Some(path) = selected else { return Ok(()) };
+            if !app_state.fs.is_local() { let local_dir = app_state.local_dir.clone(); app_state.open(&local_dir.to_string_lossy())?; }
+            app_state.popup_mode = PopupMode::None;
+            app_state.reveal(&path)?;
+        }
+        KeyCode::Esc | KeyCode::Char('S') => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_drives_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    match code {
+        KeyCode::Up => app_state.popup_index = app_state.popup_index.saturating_sub(1),
+        KeyCode::Down => app_state.popup_index = (app_state.popup_index + 1).min(app_state.drives.len().saturating_sub(1)),
+        KeyCode::Enter => {
+            let Some(drive) = app_state.drives.get(app_state.popup_index).cloned() else { return Ok(()) };
+            app_state.popup_mode = PopupMode::None;
+            app_state.apply(Action::Cd(drive.to_string_lossy().into_owned()))?;
+        }
+        KeyCode::Esc | KeyCode::Left => app_state.popup_mode = PopupMode::None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn toggle_match_option(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> bool {
+    if !modifiers.contains(KeyModifiers::CONTROL) { return false; }
+    match code {
+        KeyCode::Char('e') => app_state.config.search.cycle_case(),
+        KeyCode::Char('r') => app_state.config.search.regex = !app_state.config.search.regex,
+        _ => return false,
+    }
+    true
+}
+
+fn handle_filter_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
+    if toggle_match_option(app_state, code, modifiers) { app_state.refilter(); return Ok(()); }
+    match code {
+        KeyCode::Char(c) => { app_state.filter.push(c); app_state.history_cursor = None; }
+        KeyCode::Backspace => { app_state.filter.pop(); app_state.history_cursor = None; }
+        KeyCode::Up | KeyCode::Down if app_state.choosing => return handle_main_input(app_state, code, KeyModifiers::NONE),
+        KeyCode::Up | KeyCode::Down => return app_state.recall(code == KeyCode::Up),
+        KeyCode::Enter if app_state.choosing => { app_state.popup_mode = PopupMode::None; return handle_main_input(app_state, code, KeyModifiers::NONE); }
+        KeyCode::Enter => { app_state.remember(); app_state.popup_mode = PopupMode::None; return Ok(()); }
+        KeyCode::Esc => { app_state.history_cursor = None; app_state.popup_mode = PopupMode::None; app_state.filter.clear(); }
+        _ => return Ok(()),
+    }
+    app_state.selected_index = 0;
+    app_state.refilter();
+    Ok(())
+}
+
+fn handle_results_input(app_state: &mut AppState, code: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(results) = app_state.results.as_mut() else { app_state.popup_mode = PopupMode::None; return Ok(()) };
+    match code {
+        KeyCode::Up | KeyCode::Down => results.step(code == KeyCode::Down),
+        KeyCode::Enter => {
+            app_state.popup_mode = PopupMode::None;
+            app_state.jump_to_hit()?;
+        }
+        KeyCode::Tab => app_state.popup_mode = PopupMode::None,
+        KeyCode::Esc => { results.visible = false; app_state.popup_mode = PopupMode::None; }
+        KeyCode::Backspace => { app_state.popup_mode = PopupMode::None; app_state.jump_back()?; }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn execute_popup_action(app_state: &mut AppState) -> Result<(), Box<dyn std::error::Error>> {
+    let input = app_state.input_buffer.clone();
+    let selected = app_state.entries.get(app_state.selected_index).cloned();
+    let confirmed = matches!(input.to_lowercase().as_str(), "y" | "yes");
+    let action = match (&app_state.popup_mode, selected) {
+        (PopupMode::CreateFile, _) if !input.trim().is_empty() => Some(Action::Touch(input)),
+        (PopupMode::CreateDir, _) if !input.trim().is_empty() => Some(Action::Mkdir(input)),
+        (PopupMode::Paste, _) if !input.trim().is_empty() => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); app_state.pending_task = Some(ExternalTask::Paste(input)); return Ok(()); }
+        (PopupMode::Upload, _) if !input.trim().is_empty() => Some(Action::Upload(input)),
+        (PopupMode::Delete | PopupMode::Overwrite, _) if confirmed => app_state.confirm_action.take(),
+        (PopupMode::Shred, _) if input == "shred" => app_state.confirm_action.take(),
+        (PopupMode::Find, _) if !input.trim().is_empty() => { app_state.input_buffer.clear(); return app_state.find(&input); }
+        (PopupMode::Grep, _) if !input.is_empty() => { app_state.input_buffer.clear(); return app_state.grep(&input); }
+        (PopupMode::ForEach, _) if !input.trim().is_empty() => { app_state.input_buffer.clear(); return app_state.for_each(&input); }
+        (PopupMode::Compare, _) if !input.trim().is_empty() => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); return app_state.compare(&input); }
+        (PopupMode::Tree, _) if !input.trim().is_empty() => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); return app_state.export_tree(&actions::split_args(&input)?); }
+        (PopupMode::PreviewSearch, _) => {
+            app_state.previewer.jump = (!input.is_empty()).then_some(Jump::First);
+            (app_state.previewer.query, app_state.popup_mode) = (input, PopupMode::Preview);
+            app_state.input_buffer.clear();
+            return Ok(());
+        }
+        (PopupMode::Xattr, _) => { app_state.input_buffer.clear(); return app_state.set_xattr(&input); }
+        (PopupMode::Group, _) if !input.trim().is_empty() => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); return app_state.group_marks(&input); }
+        (PopupMode::Chmod, _) if !input.trim().is_empty() => { app_state.input_buffer.clear(); return app_state.chmod(&input); }
+        (PopupMode::Tag, Some(name)) => Some(Action::Tag(name, tags::parse(&input))),
+        (PopupMode::Rename, Some(name)) if !input.trim().is_empty() => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); return app_state.request(Action::Rename(name, input)); }
+        _ => None,
+    };
+    app_state.popup_mode = PopupMode::None;
+    app_state.input_buffer.clear();
+    app_state.confirm_action = None;
+    match action {
+        Some(action) => app_state.apply(action),
+        None => app_state.refresh_entries(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{events::ScriptedEvents, state::tests::{app, exists}};
+
+    fn press(app_state: &mut AppState, codes: impl IntoIterator<Item = KeyCode>) {
+        replay(app_state, &mut ScriptedEvents::keys(codes)).unwrap();
+    }
+
+    fn typed(text: &str) -> Vec<KeyCode> {
+        text.chars().map(KeyCode::Char).chain([KeyCode::Enter]).collect()
+    }
+
+    #[test]
+    fn arrows_move_the_selection() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt", "/work/c.txt"]);
+        press(&mut app_state, [KeyCode::Down, KeyCode::Down, KeyCode::Down, KeyCode::Up]);
+        assert_eq!(app_state.selected_index, 1);
+        assert!(!app_state.break_now);
+    }
+
+    #[test]
+    fn space_marks_and_moves_on() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt", "/work/c.txt"]);
+        press(&mut app_state, [KeyCode::Char(' '), KeyCode::Down, KeyCode::Char(' ')]);
+        assert!(app_state.is_marked("a.txt") && app_state.is_marked("c.txt") && !app_state.is_marked("b.txt"));
+        assert_eq!(app_state.selected_index, 2);
+    }
+
+    #[test]
+    fn n_prompts_for_a_new_file() {
+        let mut app_state = app(&[]);
+        press(&mut app_state, [KeyCode::Char('n')]);
+        assert_eq!(app_state.popup_mode, PopupMode::CreateFile);
+        press(&mut app_state, typed("notes.md"));
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert!(exists(&app_state, "/work/notes.md"));
+        assert_eq!(app_state.entries, ["notes.md"]);
+    }
+
+    #[test]
+    fn escaping_a_popup_keeps_running() {
+        let mut app_state = app(&[]);
+        press(&mut app_state, [KeyCode::Char('n'), KeyCode::Char('x'), KeyCode::Esc]);
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert!(app_state.input_buffer.is_empty() && !app_state.break_now);
+    }
+
+    #[test]
+    fn delete_waits_for_confirmation() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt"]);
+        press(&mut app_state, [KeyCode::Char('d')]);
+        assert_eq!(app_state.popup_mode, PopupMode::Delete);
+        press(&mut app_state, [KeyCode::Esc]);
+        assert!(exists(&app_state, "/work/a.txt"));
+        press(&mut app_state, [KeyCode::Char('d')].into_iter().chain(typed("y")));
+        assert_eq!(app_state.entries, ["b.txt"]);
+    }
+
+    #[test]
+    fn enter_picks_and_esc_cancels() {
+        let mut app_state = app(&["/work/a.txt"]);
+        press(&mut app_state, [KeyCode::Enter, KeyCode::Down]);
+        assert!(app_state.break_now && !app_state.cancelled);
+        let mut app_state = app(&["/work/a.txt"]);
+        press(&mut app_state, [KeyCode::Esc]);
+        assert!(app_state.break_now && app_state.cancelled);
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_exit() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt"]);
+        press(&mut app_state, [KeyCode::Esc, KeyCode::Down]);
+        assert_eq!(app_state.selected_index, 0);
+    }
+
+    #[test]
+    fn group_needs_marks() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt"]);
+        press(&mut app_state, [KeyCode::Char('G')]);
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert!(app_state.status.is_some());
+        press(&mut app_state, [KeyCode::Char(' '), KeyCode::Char(' '), KeyCode::Char('G')]);
+        assert_eq!(app_state.popup_mode, PopupMode::Group);
+        press(&mut app_state, typed("pair"));
+        assert_eq!(app_state.entries, ["pair"]);
+        assert!(exists(&app_state, "/work/pair/a.txt") && exists(&app_state, "/work/pair/b.txt"));
+    }
+
+    #[test]
+    fn conflict_keys_resolve_clashing_pastes() {
+        let mut app_state = app(&["/src/a.txt", "/work/a.txt"]);
+        app_state.marks.insert("/src/a.txt".into());
+        press(&mut app_state, [KeyCode::Char('c')]);
+        assert_eq!(app_state.popup_mode, PopupMode::Conflict);
+        press(&mut app_state, [KeyCode::Char('g')]);
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert!(app_state.pending_transfer.is_none());
+        press(&mut app_state, [KeyCode::Char('c'), KeyCode::Char('b')]);
+        assert!(exists(&app_state, "/work/a (1).txt"));
+    }
+
+    #[test]
+    fn moving_folders_can_merge() {
+        let mut app_state = app(&["/src/photos/x.jpg", "/work/photos/y.jpg"]);
+        app_state.marks.insert("/src/photos".into());
+        press(&mut app_state, [KeyCode::Char('m'), KeyCode::Char('g')]);
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert!(exists(&app_state, "/work/photos/x.jpg") && exists(&app_state, "/work/photos/y.jpg"));
+        assert!(!exists(&app_state, "/src/photos"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{env, fs, io, path::{Path, PathBuf}, sync::mpsc::{Receiver, Sender}};
+
+pub const ENV_VAR: &str = "QF_SOCKET";
+
+pub struct Request {
+    pub line: String,
+    reply: Sender<String>,
+}
+
+impl Request {
+    pub fn reply(self, result: Result<(), String>) {
+        let _ = self.reply.send(match result { Ok(()) => "ok".to_string(), Err(e) => format!("error: {}", e.replace('\n', " ")) });
+    }
+}
+
+pub struct ControlSocket {
+    pub path: PathBuf,
+    receiver: Receiver<Request>,
+}
+
+impl ControlSocket {
+    pub fn poll(&self) -> Vec<Request> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn default_path() -> PathBuf {
+    match dirs_next::runtime_dir() {
+        Some(dir) => dir.join("quickfind.sock"),
+        None => env::temp_dir().join(format!("quickfind-{}.sock", user_id())),
+    }
+}
+
+pub fn client_path() -> PathBuf {
+    env::var_os(ENV_VAR).map(PathBuf::from).unwrap_or_else(default_path)
+}
+
+#[cfg(unix)]
+pub fn listen(path: &Path) -> io::Result<ControlSocket> {
+    use std::{io::{BufRead, BufReader, Write}, os::unix::{fs::{FileTypeExt, PermissionsExt}, net::{UnixListener, UnixStream}}, sync::mpsc, thread};
+    use crate::wake;
+    if UnixStream::connect(path).is_ok() { return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("another QuickFind is already listening on {}", path.display()))); }
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path.display()))),
+        Err(_) => {}
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    let (sender, receiver) = wake::channel::<Request>();
+    thread::spawn(move || for stream in listener.incoming().flatten() {
+        let sender = sender.clone();
+        thread::spawn(move || -> io::Result<()> {
+            let mut writer = stream.try_clone()?;
+            for line in BufReader::new(stream).lines() {
+                let line = line?;
+                if line.trim().is_empty() { continue }
+                let (reply, answer) = mpsc::channel();
+                if sender.send(Request { line, reply }).is_err() { break }
+                writeln!(writer, "{}", answer.recv().unwrap_or_else(|_| "error: QuickFind is shutting down".to_string()))?;
+            }
+            Ok(())
+        });
+    });
+    Ok(ControlSocket { path: path.to_path_buf(), receiver })
+}
+
+#[cfg(not(unix))]
+pub fn listen(_: &Path) -> io::Result<ControlSocket> {
+    Err(io::Error::other("the control socket is only supported on Unix"))
+}
+
+#[cfg(unix)]
+pub fn send(path: &Path, commands: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::{io::{BufRead, BufReader, Write}, os::unix::net::UnixStream};
+    let mut stream = UnixStream::connect(path).map_err(|e| format!("cannot reach QuickFind at {}: {}", path.display(), e))?;
+    let mut replies = BufReader::new(stream.try_clone()?);
+    for (number, command) in commands.iter().enumerate() {
+        writeln!(stream, "{}", command)?;
+        let mut reply = String::new();
+        if replies.read_line(&mut reply)? == 0 { return Err("QuickFind closed the connection".into()); }
+        if let Some(e) = reply.trim_end().strip_prefix("error: ") { return Err(format!("command {} (`{}`): {}", number + 1, command, e).into()); }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn send(_: &Path, _: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    Err("the control socket is only supported on Unix".into())
+}
+
+#[cfg(unix)]
+fn user_id() -> u32 {
+    unsafe { libc::getuid() }
+}
+
+#[cfg(not(unix))]
+fn user_id() -> u32 {
+    0
+}
@@ -0,0 +1,224 @@
+use std::{path::PathBuf, sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}, mpsc::{Receiver, TryRecvError}}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+use crate::{foreach::{self, Outcome}, sync::{self, Plan}, vfs::Filesystem, wake};
+
+pub enum Work {
+    ForEach { template: String, dir: PathBuf, paths: Vec<PathBuf>, parallel: usize },
+    Sync(Plan),
+    Transfer { remote: Arc<dyn Filesystem + Send + Sync>, upload: bool, paths: Vec<PathBuf>, target: PathBuf },
+}
+
+impl Work {
+    pub fn len(&self) -> usize {
+        match self {
+            Work::ForEach { paths, .. } => paths.len(),
+            Work::Sync(plan) => plan.steps.len(),
+            Work::Transfer { paths, .. } => paths.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn parallel(&self) -> usize {
+        match self {
+            Work::ForEach { parallel, .. } => *parallel,
+            Work::Sync(_) | Work::Transfer { .. } => 1,
+        }
+    }
+
+    fn run(&self, index: usize, cancel: &AtomicBool) -> Outcome {
+        match self {
+            Work::ForEach { template, dir, paths, .. } => foreach::run_one(template, dir, &paths[index]),
+            Work::Sync(plan) => sync::run_step(plan, &plan.steps[index]),
+            Work::Transfer { remote, upload, paths, target } => {
+                let result = if *upload { remote.upload(&paths[index], target, cancel) } else { remote.download(&paths[index], target, cancel) };
+                Outcome { path: paths[index].clone(), code: result.map(|_| Some(0)).map_err(|e| e.to_string()), output: String::new() }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Queued,
+    Running,
+    Paused,
+    Cancelled,
+    Finished,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Queued => "queued",
+            Status::Running => "running",
+            Status::Paused => "paused",
+            Status::Cancelled => "cancelled",
+            Status::Finished => "done",
+        }
+    }
+
+    pub fn is_active(self) -> bool {
+        matches!(self, Status::Queued | Status::Running | Status::Paused)
+    }
+}
+
+#[derive(Default)]
+struct Control {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+pub struct Job {
+    pub id: usize,
+    pub title: String,
+    pub work: Arc<Work>,
+    pub outcomes: Vec<Option<Outcome>>,
+    pub status: Status,
+    pub elapsed: Duration,
+    started: Option<Instant>,
+    pending: Vec<usize>,
+    control: Arc<Control>,
+    receiver: Option<Receiver<(usize, Outcome)>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Job {
+    pub fn done(&self) -> usize {
+        self.outcomes.iter().flatten().count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().flatten().filter(|o| !o.success()).count()
+    }
+
+    pub fn report(&self) -> Vec<Outcome> {
+        self.outcomes.iter().flatten().cloned().collect()
+    }
+
+    fn start(&mut self) {
+        let (sender, receiver) = wake::channel();
+        let (work, control, pending) = (self.work.clone(), self.control.clone(), std::mem::take(&mut self.pending));
+        self.handle = Some(thread::spawn(move || {
+            let next = AtomicUsize::new(0);
+            thread::scope(|scope| {
+                for _ in 0..work.parallel().clamp(1, pending.len().max(1)) {
+                    scope.spawn(|| loop {
+                        while control.paused.load(Ordering::Relaxed) && !control.cancelled.load(Ordering::Relaxed) { thread::sleep(Duration::from_millis(50)); }
+                        if control.cancelled.load(Ordering::Relaxed) { break }
+                        let Some(&index) = pending.get(next.fetch_add(1, Ordering::Relaxed)) else { break };
+                        if sender.send((index, work.run(index, &control.cancelled))).is_err() { break }
+                    });
+                }
+            });
+        }));
+        (self.receiver, self.status, self.started) = (Some(receiver), Status::Running, Some(Instant::now()));
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    pub list: Vec<Job>,
+    next_id: usize,
+}
+
+impl Jobs {
+    pub fn push(&mut self, title: String, work: Work) -> usize {
+        self.next_id += 1;
+        let pending = (0..work.len()).collect();
+        let outcomes = vec![None; work.len()];
+        self.list.push(Job { id: self.next_id, title, work: Arc::new(work), outcomes, status: Status::Queued, elapsed: Duration::ZERO, started: None, pending, control: Arc::default(), receiver: None, handle: None });
+        self.schedule();
+        self.next_id
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.list.iter().find(|job| job.id == id)
+    }
+
+    pub fn active(&self) -> usize {
+        self.list.iter().filter(|job| job.status.is_active()).count()
+    }
+
+    pub fn poll(&mut self) -> (bool, Vec<usize>) {
+        let (mut changed, mut finished) = (false, Vec::new());
+        for job in &mut self.list {
+            let Some(receiver) = &job.receiver else { continue };
+            loop {
+                match receiver.try_recv() {
+                    Ok((index, outcome)) => { job.outcomes[index] = Some(outcome); changed = true; }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        if let Some(handle) = job.handle.take() { let _ = handle.join(); }
+                        job.status = if job.control.cancelled.load(Ordering::Relaxed) { Status::Cancelled } else { Status::Finished };
+                        job.receiver = None;
+                        job.elapsed += job.started.take().map_or(Duration::ZERO, |started| started.elapsed());
+                        changed = true;
+                        finished.push(job.id);
+                        break;
+                    }
+                }
+            }
+        }
+        if self.schedule() { changed = true; }
+        (changed, finished)
+    }
+
+    fn schedule(&mut self) -> bool {
+        if self.list.iter().any(|job| job.status == Status::Running) { return false; }
+        let Some(job) = self.list.iter_mut().find(|job| job.status == Status::Queued) else { return false };
+        job.start();
+        true
+    }
+
+    pub fn toggle_pause(&mut self, id: usize) {
+        let Some(job) = self.list.iter_mut().find(|job| job.id == id) else { return };
+        match job.status {
+            Status::Running => job.status = Status::Paused,
+            Status::Paused => job.status = Status::Running,
+            _ => return,
+        }
+        job.control.paused.store(job.status == Status::Paused, Ordering::Relaxed);
+        self.schedule();
+    }
+
+    pub fn cancel(&mut self, id: usize) {
+        let Some(job) = self.list.iter_mut().find(|job| job.id == id) else { return };
+        match job.status {
+            Status::Queued => job.status = Status::Cancelled,
+            Status::Running | Status::Paused => job.control.cancelled.store(true, Ordering::Relaxed),
+            _ => {}
+        }
+    }
+
+    pub fn retry(&mut self, id: usize) -> Result<usize, String> {
+        let Some(job) = self.list.iter_mut().find(|job| job.id == id) else { return Err("that job is gone".into()) };
+        if job.status.is_active() { return Err("the job is still running".into()); }
+        job.pending = job.outcomes.iter().enumerate().filter(|(_, o)| o.as_ref().is_none_or(|o| !o.success())).map(|(i, _)| i).collect();
+        if job.pending.is_empty() { return Err("nothing failed".into()); }
+        (job.status, job.control) = (Status::Queued, Arc::default());
+        let count = job.pending.len();
+        self.schedule();
+        Ok(count)
+    }
+
+    pub fn shift(&mut self, index: usize, up: bool) -> usize {
+        let other = if up { index.checked_sub(1) } else { Some(index + 1).filter(|i| *i < self.list.len()) };
+        let Some(other) = other else { return index };
+        self.list.swap(index, other);
+        other
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.list.retain(|job| job.status.is_active());
+    }
+
+    pub fn finish(&mut self) {
+        for job in &mut self.list {
+            if job.status == Status::Queued { job.status = Status::Cancelled; }
+            job.control.paused.store(false, Ordering::Relaxed);
+            if let Some(handle) = job.handle.take() { let _ = handle.join(); }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { chars: text.char_indices().peekable(), text };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.chars.next() {
+            None => Ok(value),
+            Some((at, _)) => Err(format!("trailing characters at {}", at)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Json::String(s) = self { Some(s) } else { None }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        if let Json::Number(n) = self { Some(*n) } else { None }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        if let Json::Array(items) = self { items } else { &[] }
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    text: &'a str,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((at, c)) => Err(format!("expected '{}' at {}, found '{}'", expected, at, c)),
+            None => Err(format!("expected '{}', found end of input", expected)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        let Some(&(at, c)) = self.chars.peek() else { return Err("unexpected end of input".to_string()) };
+        match c {
+            '{' => {
+                self.chars.next();
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if(|(_, c)| *c == '}').is_some() { return Ok(Json::Object(fields)); }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    if self.chars.next_if(|(_, c)| *c == ',').is_none() { self.expect('}')?; return Ok(Json::Object(fields)); }
+                }
+            }
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if(|(_, c)| *c == ']').is_some() { return Ok(Json::Array(items)); }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    if self.chars.next_if(|(_, c)| *c == ',').is_none() { self.expect(']')?; return Ok(Json::Array(items)); }
+                }
+            }
+            '"' => self.string().map(Json::String),
+            't' => self.keyword("true", Json::Bool(true)),
+            'f' => self.keyword("false", Json::Bool(false)),
+            'n' => self.keyword("null", Json::Null),
+            '-' | '0'..='9' => {
+                let mut end = at;
+                while let Some((i, c)) = self.chars.next_if(|(_, c)| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')) { end = i + c.len_utf8(); }
+                self.text[at..end].parse().map(Json::Number).map_err(|_| format!("invalid number at {}", at))
+            }
+            c => Err(format!("unexpected '{}' at {}", c, at)),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() { self.expect(expected)?; }
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next().ok_or("unterminated string")?.1 {
+                '"' => return Ok(out),
+                '\\' => match self.chars.next().ok_or("unterminated escape")?.1 {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'u' => {
+                        let high = self.hex4()?;
+                        let code = if (0xD800..0xDC00).contains(&high) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            0x10000 + ((high - 0xD800) << 10) + self.hex4()?.checked_sub(0xDC00).filter(|low| *low < 0x400).ok_or("invalid surrogate pair")?
+                        } else { high };
+                        out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = (0..4).filter_map(|_| self.chars.next().map(|(_, c)| c)).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("invalid unicode escape '{}'", digits))
+    }
+}
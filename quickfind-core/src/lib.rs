@@ -0,0 +1,55 @@
+pub mod actions;
+pub mod archive;
+pub mod clipboard;
+pub mod commands;
+pub mod compare;
+pub mod config;
+pub mod devices;
+pub mod events;
+pub mod favorites;
+pub mod finder;
+pub mod foreach;
+pub mod ftp;
+pub mod git;
+pub mod glob;
+pub mod graphics;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod input;
+pub mod ipc;
+pub mod jobs;
+pub mod json;
+pub mod listing;
+pub mod media;
+pub mod notify;
+pub mod perms;
+pub mod plugins;
+pub mod picture;
+pub mod pretty;
+pub mod preview;
+pub mod regex;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod scratch;
+pub mod script;
+pub mod search;
+pub mod sftp;
+pub mod shell;
+pub mod state;
+pub mod statusline;
+pub mod streams;
+pub mod sync;
+pub mod tags;
+pub mod theme;
+pub mod thumbnail;
+pub mod timefmt;
+pub mod tree;
+pub mod trash;
+pub mod ui;
+pub mod users;
+pub mod vfs;
+pub mod wake;
+pub mod watch;
+pub mod wipe;
+pub mod xattr;
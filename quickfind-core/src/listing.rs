@@ -0,0 +1,374 @@
+use std::{cmp::{Ordering, Reverse}, collections::HashMap, iter::Peekable, fs::{self, DirEntry}, io, mem, path::{Path, PathBuf}, str::Chars, sync::mpsc::{Receiver, TryRecvError}, thread, time::{Duration, Instant, SystemTime}};
+use crate::{config::Document, media::{self, Kind}, timefmt::TimeStyle, vfs::{Filesystem, Meta}, wake};
+
+pub const DIR_FILE: &str = ".quickfind.toml";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Modified,
+    Size,
+    Extension,
+}
+
+impl SortKey {
+    pub fn parse(name: &str) -> Option<SortKey> {
+        match name {
+            "name" => Some(SortKey::Name),
+            "mtime" | "modified" => Some(SortKey::Modified),
+            "size" => Some(SortKey::Size),
+            "ext" | "extension" => Some(SortKey::Extension),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> SortKey {
+        match self {
+            SortKey::Name => SortKey::Modified,
+            SortKey::Modified => SortKey::Size,
+            SortKey::Size => SortKey::Extension,
+            SortKey::Extension => SortKey::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Modified => "mtime",
+            SortKey::Size => "size",
+            SortKey::Extension => "ext",
+        }
+    }
+
+    pub fn direction(self, reverse: bool) -> &'static str {
+        match (self, reverse) {
+            (SortKey::Name | SortKey::Extension, false) => "A→Z",
+            (SortKey::Name | SortKey::Extension, true) => "Z→A",
+            (SortKey::Modified, false) => "newest first",
+            (SortKey::Modified, true) => "oldest first",
+            (SortKey::Size, false) => "largest first",
+            (SortKey::Size, true) => "smallest first",
+        }
+    }
+}
+
+const DOCUMENTS: [&str; 19] = ["pdf", "doc", "docx", "odt", "rtf", "txt", "md", "rst", "tex", "epub", "xls", "xlsx", "ods", "csv", "tsv", "ppt", "pptx", "odp", "pages"];
+const CODE: [&str; 38] = ["rs", "py", "js", "mjs", "ts", "jsx", "tsx", "c", "h", "cc", "cpp", "hpp", "go", "java", "kt", "rb", "php", "sh", "bash", "zsh", "fish", "swift", "cs", "lua", "pl", "r", "sql", "html", "css", "scss", "vue", "json", "yaml", "yml", "toml", "xml", "ini", "mk"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    Directories,
+    Images,
+    Documents,
+    Code,
+    Other,
+}
+
+impl Group {
+    pub fn of(name: &str, is_dir: bool) -> Group {
+        if is_dir { return Group::Directories; }
+        let path = Path::new(name);
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if media::kind(path) == Some(Kind::Image) { Group::Images }
+        else if DOCUMENTS.contains(&extension.as_str()) { Group::Documents }
+        else if CODE.contains(&extension.as_str()) || matches!(name, "Makefile" | "Dockerfile" | "Justfile") { Group::Code }
+        else { Group::Other }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Group::Directories => "Directories",
+            Group::Images => "Images",
+            Group::Documents => "Documents",
+            Group::Code => "Code",
+            Group::Other => "Other",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    Name,
+    Size,
+    Modified,
+    Perms,
+    Owner,
+    Type,
+    Git,
+}
+
+impl Column {
+    pub const LONG: [Column; 5] = [Column::Perms, Column::Owner, Column::Size, Column::Modified, Column::Name];
+    pub const ALL: [Column; 7] = [Column::Name, Column::Size, Column::Modified, Column::Perms, Column::Owner, Column::Type, Column::Git];
+
+    pub fn parse(name: &str) -> Option<Column> {
+        match name {
+            "name" => Some(Column::Name),
+            "size" => Some(Column::Size),
+            "mtime" | "modified" => Some(Column::Modified),
+            "perms" | "permissions" => Some(Column::Perms),
+            "owner" => Some(Column::Owner),
+            "type" => Some(Column::Type),
+            "git" => Some(Column::Git),
+            _ => None,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Size => "size",
+            Column::Modified => "mtime",
+            Column::Perms => "perms",
+            Column::Owner => "owner",
+            Column::Type => "type",
+            Column::Git => "git",
+        }
+    }
+
+    pub fn default_width(&self) -> usize {
+        match self {
+            Column::Name => 16,
+            Column::Size => 9,
+            Column::Modified => 16,
+            Column::Perms => 10,
+            Column::Owner => 15,
+            Column::Type => 4,
+            Column::Git => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingSettings {
+    pub sort: SortKey,
+    pub reverse: bool,
+    pub natural: bool,
+    pub ignore_case: bool,
+    pub group: bool,
+    pub hidden: bool,
+    pub dir_counts: bool,
+    pub number: bool,
+    pub relative_number: bool,
+    pub columns: Vec<Column>,
+    pub widths: HashMap<Column, usize>,
+    pub time: TimeStyle,
+}
+
+impl Default for ListingSettings {
+    fn default() -> Self {
+        ListingSettings { sort: SortKey::Name, reverse: false, natural: true, ignore_case: false, group: false, hidden: true, dir_counts: false, number: false, relative_number: false, columns: vec![Column::Name], widths: HashMap::new(), time: TimeStyle::Absolute }
+    }
+}
+
+impl ListingSettings {
+    pub fn from_document(doc: &Document) -> ListingSettings {
+        ListingSettings::default().overridden(doc)
+    }
+
+    pub fn overridden(&self, doc: &Document) -> ListingSettings {
+        ListingSettings {
+            sort: doc.str("listing", "sort").and_then(SortKey::parse).unwrap_or(self.sort),
+            reverse: doc.bool("listing", "reverse").unwrap_or(self.reverse),
+            natural: doc.bool("listing", "natural").unwrap_or(self.natural),
+            ignore_case: doc.bool("listing", "ignore_case").unwrap_or(self.ignore_case),
+            group: doc.bool("listing", "group").unwrap_or(self.group),
+            hidden: doc.bool("listing", "hidden").unwrap_or(self.hidden),
+            dir_counts: doc.bool("listing", "dir_counts").unwrap_or(self.dir_counts),
+            number: doc.bool("listing", "number").unwrap_or(self.number),
+            relative_number: doc.bool("listing", "relative_number").unwrap_or(self.relative_number),
+            columns: match doc.get("listing", "columns") {
+                Some(_) => {
+                    let mut columns: Vec<Column> = doc.strings("listing", "columns").iter().filter_map(|c| Column::parse(c)).collect();
+                    if !columns.contains(&Column::Name) { columns.insert(0, Column::Name); }
+                    columns
+                }
+                None => self.columns.clone(),
+            },
+            widths: {
+                let mut widths = self.widths.clone();
+                for column in Column::ALL { if let Some(width) = doc.int("listing.widths", column.key()).filter(|w| *w > 0) { widths.insert(column, width as usize); } }
+                widths
+            },
+            time: TimeStyle::from_document(doc).unwrap_or(self.time),
+        }
+    }
+
+    pub fn line_number(&self, index: usize, selected: usize) -> Option<usize> {
+        match (self.number, self.relative_number) {
+            (false, false) => None,
+            (true, _) if index == selected => Some(index + 1),
+            (false, true) if index == selected => Some(0),
+            (_, true) => Some(index.abs_diff(selected)),
+            (true, false) => Some(index + 1),
+        }
+    }
+
+    pub fn for_dir(&self, fs: &dyn Filesystem, dir: &Path) -> Result<ListingSettings, String> {
+        if !fs.is_local() { return Ok(self.clone()); }
+        let path = dir.join(DIR_FILE);
+        let Ok(text) = fs::read_to_string(&path) else { return Ok(self.clone()) };
+        let doc = Document::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(self.overridden(&doc))
+    }
+
+    pub fn needs_meta(&self) -> bool {
+        self.sort != SortKey::Name && self.sort != SortKey::Extension || self.columns.iter().any(|c| *c != Column::Name)
+    }
+
+    pub fn width(&self, column: Column) -> usize {
+        self.widths.get(&column).copied().unwrap_or_else(|| column.default_width())
+    }
+
+    pub fn fit(&self, available: usize) -> Vec<Column> {
+        let mut columns = self.columns.clone();
+        let used = |columns: &[Column]| columns.iter().map(|c| self.width(*c) + 2).sum::<usize>();
+        while columns.len() > 1 && used(&columns) > available + 2 {
+            let Some(drop) = columns.iter().rposition(|c| *c != Column::Name) else { break };
+            columns.remove(drop);
+        }
+        columns
+    }
+
+    pub fn compare_names(&self, a: &str, b: &str) -> Ordering {
+        match (self.natural, self.ignore_case) {
+            (true, fold) => natural_cmp(a, b, fold),
+            (false, true) => a.chars().map(lower).cmp(b.chars().map(lower)).then_with(|| a.cmp(b)),
+            (false, false) => a.cmp(b),
+        }
+    }
+
+    pub fn sort(&self, listing: &mut [(String, bool)], meta: &HashMap<String, Meta>) {
+        listing.sort_unstable_by(|(a, _), (b, _)| self.compare_names(a, b));
+        match self.sort {
+            SortKey::Name => {}
+            SortKey::Modified => listing.sort_by_key(|(name, _)| Reverse(meta.get(name).and_then(|m| m.modified))),
+            SortKey::Size => listing.sort_by_key(|(name, _)| Reverse(meta.get(name).map(|m| m.size))),
+            SortKey::Extension => listing.sort_by_cached_key(|(name, is_dir)| (!is_dir, Path::new(name).extension().map(|e| e.to_string_lossy().to_lowercase()))),
+        }
+        if self.reverse { listing.reverse(); }
+        if self.group { listing.sort_by_key(|(name, is_dir)| Group::of(name, *is_dir)); }
+    }
+}
+
+pub fn natural_cmp(a: &str, b: &str, fold: bool) -> Ordering {
+    let (mut left, mut right) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (digits(&mut left), digits(&mut right));
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let order = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if order != Ordering::Equal { return order; }
+            }
+            (Some(x), Some(y)) => {
+                let (x, y) = if fold { (lower(x), lower(y)) } else { (x, y) };
+                if x != y { return x.cmp(&y); }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn digits(chars: &mut Peekable<Chars>) -> String {
+    let mut run = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) { run.push(c); }
+    run
+}
+
+#[derive(Default)]
+pub struct DirCounts {
+    cache: HashMap<PathBuf, (Option<SystemTime>, usize)>,
+    receiver: Option<Receiver<(PathBuf, Option<SystemTime>, usize)>>,
+}
+
+impl DirCounts {
+    pub fn get(&self, dir: &Path) -> Option<usize> {
+        self.cache.get(dir).map(|(_, count)| *count)
+    }
+
+    pub fn request(&mut self, dirs: impl Iterator<Item = PathBuf>) {
+        let stale: Vec<(PathBuf, Option<SystemTime>)> = dirs
+            .map(|dir| { let modified = fs::metadata(&dir).and_then(|m| m.modified()).ok(); (dir, modified) })
+            .filter(|(dir, modified)| self.cache.get(dir).is_none_or(|(cached, _)| cached != modified || modified.is_none()))
+            .collect();
+        if stale.is_empty() { self.receiver = None; return; }
+        let (sender, receiver) = wake::channel();
+        self.receiver = Some(receiver);
+        thread::spawn(move || for (dir, modified) in stale {
+            let Ok(read) = fs::read_dir(&dir) else { continue };
+            if sender.send((dir, modified, read.count())).is_err() { break }
+        });
+    }
+
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else { return false };
+        let mut changed = false;
+        loop {
+            match receiver.try_recv() {
+                Ok((dir, modified, count)) => { self.cache.insert(dir, (modified, count)); changed = true; }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => { self.receiver = None; break }
+            }
+        }
+        changed
+    }
+}
+
+const READ_BUDGET: Duration = Duration::from_millis(100);
+const BATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+pub type Entries = Vec<(String, bool)>;
+
+pub struct DirStream {
+    receiver: Receiver<Entries>,
+}
+
+impl DirStream {
+    pub fn poll(&mut self) -> (Entries, bool) {
+        let mut entries = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(batch) => entries.extend(batch),
+                Err(TryRecvError::Empty) => return (entries, false),
+                Err(TryRecvError::Disconnected) => return (entries, true),
+            }
+        }
+    }
+}
+
+pub fn read_dir(dir: &Path) -> io::Result<(Entries, Option<DirStream>)> {
+    let mut read = fs::read_dir(dir)?;
+    let (mut entries, started) = (Vec::new(), Instant::now());
+    while started.elapsed() < READ_BUDGET {
+        match read.next() {
+            Some(entry) => entries.extend(entry.ok().map(|e| named(&e))),
+            None => return Ok((entries, None)),
+        }
+    }
+    let (sender, receiver) = wake::channel();
+    thread::spawn(move || {
+        let (mut batch, mut sent) = (Vec::new(), Instant::now());
+        for entry in read.flatten() {
+            batch.push(named(&entry));
+            if sent.elapsed() < BATCH_INTERVAL { continue }
+            if sender.send(mem::take(&mut batch)).is_err() { return }
+            sent = Instant::now();
+        }
+        let _ = sender.send(batch);
+    });
+    Ok((entries, Some(DirStream { receiver })))
+}
+
+fn named(entry: &DirEntry) -> (String, bool) {
+    (entry.file_name().to_string_lossy().into_owned(), entry.path().is_dir())
+}
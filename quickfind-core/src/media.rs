@@ -0,0 +1,195 @@
+use std::{fs::File, io::{self, Read}, path::Path, process::Command};
+use crate::{finder, json::Json};
+
+const IMAGE: [&str; 9] = ["png", "jpg", "jpeg", "gif", "bmp", "webp", "tif", "tiff", "heic"];
+const AUDIO: [&str; 8] = ["mp3", "wav", "flac", "ogg", "opus", "m4a", "aac", "wma"];
+const VIDEO: [&str; 8] = ["mp4", "mkv", "webm", "mov", "avi", "m4v", "wmv", "flv"];
+const HEADER_BYTES: u64 = 256 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Image,
+    Audio,
+    Video,
+}
+
+pub fn kind(path: &Path) -> Option<Kind> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    if IMAGE.contains(&extension.as_str()) { Some(Kind::Image) }
+    else if AUDIO.contains(&extension.as_str()) { Some(Kind::Audio) }
+    else if VIDEO.contains(&extension.as_str()) { Some(Kind::Video) }
+    else { None }
+}
+
+pub fn metadata(path: &Path, kind: Kind) -> io::Result<Vec<(String, String)>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(HEADER_BYTES).read_to_end(&mut head)?;
+    let builtin = png(&head).or_else(|| gif(&head)).or_else(|| bmp(&head)).or_else(|| jpeg(&head)).or_else(|| wav(&head));
+    if let Some(fields) = &builtin && (kind == Kind::Image || fields.iter().any(|(label, _)| label == "Duration")) { return Ok(fields.clone()); }
+    if finder::on_path("ffprobe") && let Some(fields) = ffprobe(path) { return Ok(fields); }
+    Ok(builtin.unwrap_or_else(|| vec![("Details".to_string(), "install ffprobe to read this format".to_string())]))
+}
+
+fn png(data: &[u8]) -> Option<Vec<(String, String)>> {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") || &data.get(12..16)? != b"IHDR" { return None; }
+    let colour = match data.get(25)? { 0 => "grayscale", 2 => "RGB", 3 => "indexed", 4 => "grayscale + alpha", 6 => "RGBA", _ => "unknown" };
+    Some(vec![
+        ("Format".to_string(), "PNG".to_string()),
+        dimensions(be32(data, 16)?, be32(data, 20)?),
+        ("Colour".to_string(), format!("{}, {}-bit", colour, data.get(24)?)),
+    ])
+}
+
+fn gif(data: &[u8]) -> Option<Vec<(String, String)>> {
+    if !data.starts_with(b"GIF8") { return None; }
+    Some(vec![("Format".to_string(), "GIF".to_string()), dimensions(le16(data, 6)? as u32, le16(data, 8)? as u32)])
+}
+
+fn bmp(data: &[u8]) -> Option<Vec<(String, String)>> {
+    if !data.starts_with(b"BM") || data.len() < 30 { return None; }
+    let (width, height) = (le32(data, 18)? as i32, le32(data, 22)? as i32);
+    Some(vec![("Format".to_string(), "BMP".to_string()), dimensions(width.unsigned_abs(), height.unsigned_abs()), ("Colour".to_string(), format!("{}-bit", le16(data, 28)?))])
+}
+
+fn jpeg(data: &[u8]) -> Option<Vec<(String, String)>> {
+    if !data.starts_with(&[0xFF, 0xD8]) { return None; }
+    let mut fields = vec![("Format".to_string(), "JPEG".to_string())];
+    let mut exif = Vec::new();
+    let mut at = 2;
+    while at + 4 <= data.len() && data[at] == 0xFF {
+        let marker = data[at + 1];
+        let length = be16(data, at + 2)? as usize;
+        let segment = data.get(at + 4..at + 2 + length).unwrap_or(&[]);
+        match marker {
+            0xE1 if segment.starts_with(b"Exif\0\0") => exif = tiff(&segment[6..]),
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                fields.push(dimensions(be16(segment, 3)? as u32, be16(segment, 1)? as u32));
+                break;
+            }
+            0xD9 | 0xDA => break,
+            _ => {}
+        }
+        at += 2 + length;
+    }
+    fields.extend(exif);
+    Some(fields)
+}
+
+fn tiff(data: &[u8]) -> Vec<(String, String)> {
+    let little = data.starts_with(b"II");
+    let u16_at = |at: usize| if little { le16(data, at) } else { be16(data, at) };
+    let u32_at = |at: usize| if little { le32(data, at) } else { be32(data, at) };
+    let ascii = |count: u32, value_at: usize| {
+        let start = if count > 4 { u32_at(value_at)? as usize } else { value_at };
+        let text = data.get(start..start + count as usize)?;
+        Some(String::from_utf8_lossy(text).trim_end_matches('\0').trim().to_string())
+    };
+    let entries = |ifd: usize| -> Vec<(u16, u32, usize)> {
+        let Some(count) = u16_at(ifd) else { return Vec::new() };
+        (0..count as usize).filter_map(|i| { let at = ifd + 2 + i * 12; Some((u16_at(at)?, u32_at(at + 4)?, at + 8)) }).collect()
+    };
+    let (mut make, mut model, mut taken) = (None, None, None);
+    let mut exposure = Vec::new();
+    let ifd0 = u32_at(4).unwrap_or(0) as usize;
+    for (tag, count, value_at) in entries(ifd0) {
+        match tag {
+            0x010F => make = ascii(count, value_at),
+            0x0110 => model = ascii(count, value_at),
+            0x0132 if taken.is_none() => taken = ascii(count, value_at),
+            0x8769 => for (tag, count, value_at) in u32_at(value_at).map(|ifd| entries(ifd as usize)).unwrap_or_default() {
+                let rational = || { let at = u32_at(value_at)? as usize; Some((u32_at(at)?, u32_at(at + 4)?)) };
+                match tag {
+                    0x9003 => taken = ascii(count, value_at).or(taken.take()),
+                    0x829A => if let Some((n, d)) = rational() && n > 0 && d > 0 { exposure.push(if n < d { format!("1/{}s", d / n) } else { format!("{}s", n as f64 / d as f64) }); },
+                    0x829D => if let Some((n, d)) = rational() && d > 0 { exposure.push(format!("f/{:.1}", n as f64 / d as f64)); },
+                    0x8827 => if let Some(iso) = u16_at(value_at) { exposure.push(format!("ISO {}", iso)); },
+                    _ => {}
+                }
+            },
+            _ => {}
+        }
+    }
+    let camera = match (make, model) {
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    };
+    let mut fields = Vec::new();
+    if let Some(camera) = camera.filter(|c| !c.is_empty()) { fields.push(("Camera".to_string(), camera)); }
+    if !exposure.is_empty() { fields.push(("Exposure".to_string(), exposure.join("  "))); }
+    if let Some(taken) = taken.filter(|t| !t.is_empty()) { fields.push(("Taken".to_string(), taken)); }
+    fields
+}
+
+fn wav(data: &[u8]) -> Option<Vec<(String, String)>> {
+    if !data.starts_with(b"RIFF") || data.get(8..12)? != b"WAVE" { return None; }
+    let (mut format, mut at) = (None, 12);
+    while at + 8 <= data.len() {
+        let (id, size) = (&data[at..at + 4], le32(data, at + 4)? as usize);
+        if id == b"fmt " { format = Some((le16(data, at + 10)?, le32(data, at + 12)?, le32(data, at + 16)?, le16(data, at + 22)?)); }
+        if id == b"data" && let Some((channels, rate, byte_rate, bits)) = format {
+            let mut fields = vec![("Format".to_string(), "WAV".to_string())];
+            if byte_rate > 0 { fields.push(("Duration".to_string(), duration(size as f64 / byte_rate as f64))); }
+            fields.push(("Audio".to_string(), format!("PCM {} Hz, {}, {}-bit", rate, channel_name(channels as u64), bits)));
+            fields.push(("Bitrate".to_string(), bitrate(byte_rate as f64 * 8.0)));
+            return Some(fields);
+        }
+        at += 8 + size + size % 2;
+    }
+    None
+}
+
+fn ffprobe(path: &Path) -> Option<Vec<(String, String)>> {
+    let output = Command::new("ffprobe").args(["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"]).arg(path).output().ok()?;
+    if !output.status.success() { return None; }
+    let probe = Json::parse(&String::from_utf8_lossy(&output.stdout)).ok()?;
+    let format = probe.get("format")?;
+    let number = |value: Option<&Json>| value.and_then(|v| v.as_str().and_then(|s| s.parse().ok()).or(v.as_f64()));
+    let mut fields = Vec::new();
+    if let Some(name) = format.get("format_long_name").and_then(Json::as_str) { fields.push(("Format".to_string(), name.to_string())); }
+    if let Some(seconds) = number(format.get("duration")) { fields.push(("Duration".to_string(), duration(seconds))); }
+    for stream in probe.get("streams").map(Json::as_array).unwrap_or_default() {
+        let codec = stream.get("codec_name").and_then(Json::as_str).unwrap_or("?");
+        match stream.get("codec_type").and_then(Json::as_str) {
+            Some("video") => {
+                let size = match (number(stream.get("width")), number(stream.get("height"))) { (Some(w), Some(h)) => format!(" {}x{}", w, h), _ => String::new() };
+                let rate = stream.get("avg_frame_rate").and_then(Json::as_str).and_then(|r| r.split_once('/')).and_then(|(n, d)| Some(n.parse::<f64>().ok()? / d.parse::<f64>().ok().filter(|d| *d > 0.0)?));
+                fields.push(("Video".to_string(), format!("{}{}{}", codec, size, rate.map(|r| format!(" @ {:.2} fps", r)).unwrap_or_default())));
+            }
+            Some("audio") => {
+                let rate = number(stream.get("sample_rate")).map(|r| format!(" {} Hz", r)).unwrap_or_default();
+                let channels = number(stream.get("channels")).map(|c| format!(", {}", channel_name(c as u64))).unwrap_or_default();
+                fields.push(("Audio".to_string(), format!("{}{}{}", codec, rate, channels)));
+            }
+            _ => {}
+        }
+    }
+    if let Some(bits) = number(format.get("bit_rate")) { fields.push(("Bitrate".to_string(), bitrate(bits))); }
+    let tags = format.get("tags");
+    for (label, key) in [("Title", "title"), ("Artist", "artist"), ("Album", "album")] {
+        if let Some(value) = tags.and_then(|t| t.get(key)).and_then(Json::as_str) { fields.push((label.to_string(), value.to_string())); }
+    }
+    Some(fields)
+}
+
+fn dimensions(width: u32, height: u32) -> (String, String) {
+    ("Dimensions".to_string(), format!("{} x {}", width, height))
+}
+
+fn duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    if total >= 3600 { format!("{}:{:02}:{:02}", total / 3600, total / 60 % 60, total % 60) } else { format!("{}:{:02}", total / 60, total % 60) }
+}
+
+fn bitrate(bits: f64) -> String {
+    if bits >= 1_000_000.0 { format!("{:.1} Mb/s", bits / 1_000_000.0) } else { format!("{:.0} kb/s", bits / 1000.0) }
+}
+
+fn channel_name(channels: u64) -> String {
+    match channels { 1 => "mono".to_string(), 2 => "stereo".to_string(), n => format!("{} channels", n) }
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u16> { Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?)) }
+fn be32(data: &[u8], at: usize) -> Option<u32> { Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?)) }
+fn le16(data: &[u8], at: usize) -> Option<u16> { Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?)) }
+fn le32(data: &[u8], at: usize) -> Option<u32> { Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?)) }
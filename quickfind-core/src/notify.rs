@@ -0,0 +1,51 @@
+use std::{io::{self, IsTerminal, Write}, process::{Command, Stdio}, thread};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Alert {
+    #[default]
+    Off,
+    Bell,
+    Flash,
+}
+
+impl Alert {
+    pub fn parse(name: &str) -> Option<Alert> {
+        match name {
+            "off" | "none" => Some(Alert::Off),
+            "bell" => Some(Alert::Bell),
+            "flash" => Some(Alert::Flash),
+            _ => None,
+        }
+    }
+}
+
+pub fn desktop(title: &str, body: &str) -> Result<&'static str, String> {
+    if let Some((program, args)) = native(title, body) && let Ok(mut child) = Command::new(program).args(&args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+        thread::spawn(move || child.wait());
+        return Ok(program);
+    }
+    if !io::stdout().is_terminal() { return Err("no notifier available".to_string()); }
+    osc9(title, body).map(|_| "terminal").map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn native(title: &str, body: &str) -> Option<(&'static str, Vec<String>)> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    Some(("osascript", vec!["-e".to_string(), format!("display notification {} with title {}", quote(body), quote(title))]))
+}
+
+#[cfg(windows)]
+fn native(_: &str, _: &str) -> Option<(&'static str, Vec<String>)> {
+    None
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn native(title: &str, body: &str) -> Option<(&'static str, Vec<String>)> {
+    Some(("notify-send", vec!["--app-name=QuickFind".to_string(), title.to_string(), body.to_string()]))
+}
+
+fn osc9(title: &str, body: &str) -> io::Result<()> {
+    let mut out = io::stdout();
+    write!(out, "\x1b]9;{}: {}\x07", title, body.replace(|c: char| c.is_control(), " "))?;
+    out.flush()
+}
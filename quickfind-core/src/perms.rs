@@ -0,0 +1,86 @@
+use std::{fs, io, path::{Path, PathBuf}};
+use crate::{foreach::Outcome, i18n::{tr, trf}};
+
+pub const SUPPORTED: bool = cfg!(unix);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ModeSpec {
+    pub dirs: u32,
+    pub files: u32,
+    pub recursive: bool,
+}
+
+impl ModeSpec {
+    pub fn parse(input: &str) -> Result<ModeSpec, String> {
+        let (mut modes, mut recursive) = (None, false);
+        for word in input.split_whitespace() {
+            match word {
+                "-R" | "-r" | "--recursive" => recursive = true,
+                _ if modes.is_some() => return Err(tr("give one mode, or a directory/file pair such as 755/644").to_string()),
+                _ => {
+                    let (dirs, files) = word.split_once('/').unwrap_or((word, word));
+                    modes = Some((octal(dirs)?, octal(files)?));
+                }
+            }
+        }
+        let (dirs, files) = modes.ok_or(tr("no mode given (e.g. 644, 755/644 or -R 755/644)"))?;
+        Ok(ModeSpec { dirs, files, recursive })
+    }
+
+    pub fn label(&self) -> String {
+        let modes = if self.dirs == self.files { format!("{:o}", self.files) } else { format!("{:o}/{:o}", self.dirs, self.files) };
+        if self.recursive { format!("-R {}", modes) } else { modes }
+    }
+}
+
+fn octal(text: &str) -> Result<u32, String> {
+    u32::from_str_radix(text, 8).ok().filter(|mode| !text.is_empty() && *mode <= 0o7777).ok_or_else(|| trf("`{}` is not an octal mode such as 644", &[&text]))
+}
+
+pub fn apply(spec: &ModeSpec, paths: &[PathBuf]) -> Vec<Outcome> {
+    let mut report = Vec::new();
+    for path in paths {
+        let (mut changed, mut failures) = (0, Vec::new());
+        let code = visit(spec, path, &mut changed, &mut failures).map(|_| Some(0)).map_err(|e| e.to_string());
+        let output = match (spec.recursive, changed) {
+            (_, 0) if code.is_ok() => tr("skipped, symlinks keep their target's mode").to_string(),
+            (true, 1) => tr("set on 1 entry").to_string(),
+            (true, _) => trf("set on {} entries", &[&changed]),
+            (false, _) => trf("set to {}", &[&format!("{:o}", if path.is_dir() { spec.dirs } else { spec.files })]),
+        };
+        report.push(Outcome { path: path.clone(), code, output });
+        report.extend(failures);
+    }
+    report
+}
+
+fn visit(spec: &ModeSpec, path: &Path, changed: &mut usize, failures: &mut Vec<Outcome>) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_symlink() { return Ok(()); }
+    if !meta.is_dir() || !spec.recursive {
+        set_mode(path, if meta.is_dir() { spec.dirs } else { spec.files })?;
+        *changed += 1;
+        return Ok(());
+    }
+    let (children, set_first) = match fs::read_dir(path) {
+        Ok(read) => (read, false),
+        Err(_) => { set_mode(path, spec.dirs)?; (fs::read_dir(path)?, true) }
+    };
+    for child in children.flatten().map(|entry| entry.path()) {
+        if let Err(e) = visit(spec, &child, changed, failures) { failures.push(Outcome { path: child, code: Err(e.to_string()), output: String::new() }); }
+    }
+    if !set_first { set_mode(path, spec.dirs)?; }
+    *changed += 1;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_: &Path, _: u32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, tr("permission modes are only supported on Unix")))
+}
@@ -0,0 +1,276 @@
+use std::{fs, path::Path, process::{Command, Stdio}};
+use tui::style::Color;
+use crate::finder;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub symbol: char,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+pub type Art = Vec<Vec<Cell>>;
+
+pub fn render(path: &Path, width: usize, height: usize) -> Result<Art, String> {
+    if width == 0 || height == 0 { return Ok(Vec::new()); }
+    let (size, columns, rows) = (format!("{}x{}", width, height), width.to_string(), height.to_string());
+    let tools: [(&str, Vec<&std::ffi::OsStr>); 2] = [
+        ("chafa", vec!["--format".as_ref(), "symbols".as_ref(), "--size".as_ref(), size.as_ref(), "--animate".as_ref(), "off".as_ref(), "--polite".as_ref(), "on".as_ref(), "--colors".as_ref(), "full".as_ref(), path.as_ref()]),
+        ("viu", vec!["-b".as_ref(), "-w".as_ref(), columns.as_ref(), "-h".as_ref(), rows.as_ref(), path.as_ref()]),
+    ];
+    for (program, args) in tools.iter().filter(|(program, _)| finder::on_path(program)) {
+        let Ok(output) = Command::new(program).args(args).stdin(Stdio::null()).stderr(Stdio::null()).env("TERM", "xterm-256color").env_remove("TMUX").output() else { continue };
+        let art = ansi(&String::from_utf8_lossy(&output.stdout));
+        if output.status.success() && !art.is_empty() { return Ok(art); }
+    }
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") { return Err("install chafa or viu to draw this image".to_string()); }
+    let (image_width, image_height, pixels) = png(&data)?;
+    Ok(blocks(image_width, image_height, &pixels, width, height))
+}
+
+fn ansi(text: &str) -> Art {
+    let (mut rows, mut row) = (Vec::new(), Vec::new());
+    let (mut fg, mut bg, mut inverse) = (None, None, false);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' if chars.peek() == Some(&'[') => {
+                chars.next();
+                let mut sequence = String::new();
+                while let Some(&next) = chars.peek() {
+                    chars.next();
+                    if next.is_ascii_alphabetic() || next == '~' { sequence.push(next); break; }
+                    sequence.push(next);
+                }
+                let Some(params) = sequence.strip_suffix('m') else { continue };
+                let codes: Vec<u16> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+                let mut i = 0;
+                while i < codes.len() {
+                    match codes[i] {
+                        0 => (fg, bg, inverse) = (None, None, false),
+                        7 => inverse = true,
+                        27 => inverse = false,
+                        39 => fg = None,
+                        49 => bg = None,
+                        code @ (30..=37 | 90..=97) => fg = Some(Color::Indexed((code % 10 + if code >= 90 { 8 } else { 0 }) as u8)),
+                        code @ (40..=47 | 100..=107) => bg = Some(Color::Indexed((code % 10 + if code >= 100 { 8 } else { 0 }) as u8)),
+                        code @ (38 | 48) => {
+                            let color = match codes.get(i + 1) {
+                                Some(5) => { i += 2; codes.get(i).map(|n| Color::Indexed(*n as u8)) }
+                                Some(2) => { i += 4; codes.get(i - 2..=i).map(|rgb| Color::Rgb(rgb[0] as u8, rgb[1] as u8, rgb[2] as u8)) }
+                                _ => None,
+                            };
+                            if code == 38 { fg = color; } else { bg = color; }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            '\x1b' => { chars.next(); }
+            '\n' => rows.push(std::mem::take(&mut row)),
+            c if c.is_control() => {}
+            symbol => row.push(if inverse { Cell { symbol, fg: bg, bg: fg } } else { Cell { symbol, fg, bg } }),
+        }
+    }
+    if !row.is_empty() { rows.push(row); }
+    while rows.last().is_some_and(|row: &Vec<Cell>| row.iter().all(|cell| cell.symbol == ' ' && cell.bg.is_none())) { rows.pop(); }
+    rows
+}
+
+fn blocks(image_width: usize, image_height: usize, pixels: &[[u8; 4]], width: usize, height: usize) -> Art {
+    let scale = (width as f64 / image_width as f64).min(2.0 * height as f64 / image_height as f64).min(1.0);
+    let (columns, lines) = (((image_width as f64 * scale) as usize).max(1), ((image_height as f64 * scale) as usize).max(1));
+    let sample = |x: usize, y: usize| -> Option<Color> {
+        if y >= lines { return None; }
+        let (x0, x1) = (x * image_width / columns, ((x + 1) * image_width / columns).max(x * image_width / columns + 1));
+        let (y0, y1) = (y * image_height / lines, ((y + 1) * image_height / lines).max(y * image_height / lines + 1));
+        let mut sum = [0u64; 4];
+        for row in y0..y1 {
+            for pixel in &pixels[row * image_width + x0..row * image_width + x1] {
+                let alpha = pixel[3] as u64;
+                for (channel, value) in sum.iter_mut().zip(pixel).take(3) { *channel += *value as u64 * alpha; }
+                sum[3] += alpha;
+            }
+        }
+        let count = ((x1 - x0) * (y1 - y0)) as u64;
+        (sum[3] * 2 >= count * 255).then(|| Color::Rgb((sum[0] / sum[3]) as u8, (sum[1] / sum[3]) as u8, (sum[2] / sum[3]) as u8))
+    };
+    (0..lines.div_ceil(2)).map(|line| (0..columns).map(|x| match (sample(x, line * 2), sample(x, line * 2 + 1)) {
+        (None, None) => Cell { symbol: ' ', fg: None, bg: None },
+        (None, Some(bottom)) => Cell { symbol: '▄', fg: Some(bottom), bg: None },
+        (top, bottom) => Cell { symbol: '▀', fg: top, bg: bottom },
+    }).collect()).collect()
+}
+
+fn png(data: &[u8]) -> Result<(usize, usize, Vec<[u8; 4]>), String> {
+    let (mut header, mut palette, mut transparency, mut compressed) = (None, Vec::new(), Vec::new(), Vec::new());
+    let mut at = 8;
+    while at + 12 <= data.len() {
+        let length = u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]) as usize;
+        let chunk = data.get(at + 8..at + 8 + length).ok_or("truncated PNG")?;
+        match &data[at + 4..at + 8] {
+            b"IHDR" if length >= 13 => header = Some(chunk.to_vec()),
+            b"PLTE" => palette = chunk.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"tRNS" => transparency = chunk.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+        at += 12 + length;
+    }
+    let header = header.ok_or("missing PNG header")?;
+    let (width, height) = (u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize, u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize);
+    let (depth, colour, interlace) = (header[8] as usize, header[9], header[12]);
+    if interlace != 0 { return Err("interlaced PNGs need chafa or viu".to_string()); }
+    let channels = match colour { 0 | 3 => 1, 4 => 2, 2 => 3, 6 => 4, _ => return Err("unsupported PNG colour type".to_string()) };
+    if width == 0 || height == 0 || width * height > 64 << 20 { return Err("unsupported PNG size".to_string()); }
+    let (stride, bpp) = ((width * channels * depth).div_ceil(8), (channels * depth).div_ceil(8));
+    let raw = inflate(compressed.get(2..).ok_or("truncated PNG data")?)?;
+    if raw.len() < (stride + 1) * height { return Err("truncated PNG data".to_string()); }
+    let mut rows = vec![0u8; stride * height];
+    for y in 0..height {
+        let (filter, line) = (raw[y * (stride + 1)], &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)]);
+        let (done, current) = rows.split_at_mut(y * stride);
+        let previous = if y > 0 { &done[(y - 1) * stride..] } else { &[][..] };
+        for x in 0..stride {
+            let (a, b) = (if x >= bpp { current[x - bpp] } else { 0 }, previous.get(x).copied().unwrap_or(0));
+            let c = if x >= bpp { previous.get(x - bpp).copied().unwrap_or(0) } else { 0 };
+            current[x] = line[x].wrapping_add(match filter {
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => { let p = a as i16 + b as i16 - c as i16; let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs()); if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c } }
+                _ => 0,
+            });
+        }
+    }
+    let max = (1u32 << depth.min(8)) - 1;
+    let pixels = (0..width * height).map(|i| {
+        let (row, x) = (&rows[i / width * stride..(i / width + 1) * stride], i % width);
+        let sample = |channel: usize| -> u32 {
+            let index = x * channels + channel;
+            match depth { 16 => row[index * 2] as u32, 8 => row[index] as u32, _ => (row[index * depth / 8] >> (8 - depth - index * depth % 8)) as u32 & max }
+        };
+        let scale = |value: u32| if depth < 8 { (value * 255 / max) as u8 } else { value as u8 };
+        let keyed = |value: u32, at: usize| transparency.len() >= at + 2 && u16::from_be_bytes([transparency[at], transparency[at + 1]]) as u32 >> depth.saturating_sub(8) == value;
+        match colour {
+            0 => { let g = sample(0); [scale(g), scale(g), scale(g), if keyed(g, 0) { 0 } else { 255 }] }
+            2 => { let (r, g, b) = (sample(0), sample(1), sample(2)); [r as u8, g as u8, b as u8, if keyed(r, 0) && keyed(g, 2) && keyed(b, 4) { 0 } else { 255 }] }
+            3 => { let index = sample(0) as usize; let [r, g, b] = palette.get(index).copied().unwrap_or_default(); [r, g, b, transparency.get(index).copied().unwrap_or(255)] }
+            4 => { let g = scale(sample(0)); [g, g, g, sample(1) as u8] }
+            _ => [sample(0) as u8, sample(1) as u8, sample(2) as u8, sample(3) as u8],
+        }
+    }).collect();
+    Ok((width, height, pixels))
+}
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+struct Bits<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl Bits<'_> {
+    fn take(&mut self, count: u8) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.at / 8).ok_or("truncated PNG data")?;
+            value |= ((byte >> (self.at % 8)) as u32 & 1) << i;
+            self.at += 1;
+        }
+        Ok(value)
+    }
+}
+
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for length in lengths { counts[*length as usize] += 1; }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for i in 1..16 { offsets[i] = offsets[i - 1] + counts[i - 1]; }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate().filter(|(_, l)| **l > 0) {
+            symbols[offsets[*length as usize] as usize] = symbol as u16;
+            offsets[*length as usize] += 1;
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= bits.take(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count { return self.symbols.get((index + code - first) as usize).copied().ok_or_else(|| "bad PNG data".to_string()); }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("bad PNG data".to_string())
+    }
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let (mut out, mut bits) = (Vec::new(), Bits { data, at: 0 });
+    loop {
+        let last = bits.take(1)? == 1;
+        match bits.take(2)? {
+            0 => {
+                bits.at = bits.at.div_ceil(8) * 8;
+                let start = bits.at / 8;
+                let length = u16::from_le_bytes([*data.get(start).ok_or("truncated PNG data")?, *data.get(start + 1).ok_or("truncated PNG data")?]) as usize;
+                out.extend_from_slice(data.get(start + 4..start + 4 + length).ok_or("truncated PNG data")?);
+                bits.at = (start + 4 + length) * 8;
+            }
+            kind @ (1 | 2) => {
+                let (literals, distances) = if kind == 1 {
+                    let lengths: Vec<u8> = (0..288).map(|i| match i { 0..=143 => 8, 144..=255 => 9, 256..=279 => 7, _ => 8 }).collect();
+                    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+                } else {
+                    let (literal_count, distance_count, code_count) = (bits.take(5)? as usize + 257, bits.take(5)? as usize + 1, bits.take(4)? as usize + 4);
+                    let mut code_lengths = [0u8; 19];
+                    for position in CODE_ORDER.iter().take(code_count) { code_lengths[*position] = bits.take(3)? as u8; }
+                    let codes = Huffman::new(&code_lengths);
+                    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+                    while lengths.len() < literal_count + distance_count {
+                        let (value, repeat) = match codes.decode(&mut bits)? {
+                            symbol @ 0..=15 => (symbol as u8, 1),
+                            16 => (*lengths.last().ok_or("bad PNG data")?, 3 + bits.take(2)?),
+                            17 => (0, 3 + bits.take(3)?),
+                            _ => (0, 11 + bits.take(7)?),
+                        };
+                        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+                    }
+                    (Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..literal_count + distance_count]))
+                };
+                loop {
+                    let symbol = literals.decode(&mut bits)? as usize;
+                    if symbol < 256 { out.push(symbol as u8); continue; }
+                    if symbol == 256 { break; }
+                    let index = symbol - 257;
+                    let length = *LENGTH_BASE.get(index).ok_or("bad PNG data")? as usize + bits.take(LENGTH_EXTRA[index])? as usize;
+                    let code = distances.decode(&mut bits)? as usize;
+                    let distance = *DISTANCE_BASE.get(code).ok_or("bad PNG data")? as usize + bits.take(DISTANCE_EXTRA[code])? as usize;
+                    if distance > out.len() { return Err("bad PNG data".to_string()); }
+                    let start = out.len() - distance;
+                    for i in 0..length { out.push(out[start + i]); }
+                }
+            }
+            _ => return Err("bad PNG data".to_string()),
+        }
+        if last { return Ok(out); }
+    }
+}
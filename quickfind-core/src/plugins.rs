@@ -0,0 +1,67 @@
+use std::{collections::BTreeSet, ffi::OsStr, fs, path::Path, path::PathBuf, process::{Command, ExitStatus}};
+use crate::{config, scratch::ScratchDir};
+
+pub struct Plugin {
+    pub name: String,
+    pub key: Option<char>,
+    pub pause: bool,
+    pub path: PathBuf,
+}
+
+pub fn plugins_dir() -> PathBuf {
+    config::config_dir().join("plugins")
+}
+
+pub fn load() -> Vec<Plugin> {
+    let Ok(read) = fs::read_dir(plugins_dir()) else { return Vec::new() };
+    let mut plugins: Vec<Plugin> = read.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_file()).map(|path| {
+        let head = fs::read(&path).map(|bytes| String::from_utf8_lossy(&bytes[..bytes.len().min(4096)]).into_owned()).unwrap_or_default();
+        let mut plugin = Plugin { name: path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(), key: None, pause: false, path: path.clone() };
+        for line in head.lines().take(20) {
+            let Some((field, value)) = line.trim_start_matches(['#', '/', '-', ';', ' ']).split_once(':') else { continue };
+            match field.trim() {
+                "qf-name" => plugin.name = value.trim().to_string(),
+                "qf-key" => plugin.key = value.trim().chars().next(),
+                "qf-pause" => plugin.pause = value.trim() == "true",
+                _ => {}
+            }
+        }
+        plugin
+    }).collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+pub fn run(plugin: &Plugin, dir: &Path, selected: Option<&Path>, marked: &BTreeSet<PathBuf>) -> Result<(ExitStatus, Vec<String>), Box<dyn std::error::Error>> {
+    let scratch = ScratchDir::new()?;
+    let (commands_file, _) = scratch.create("commands")?;
+    let status = Command::new(&plugin.path)
+        .current_dir(dir)
+        .env("QF_DIR", dir)
+        .env("QF_SELECTED", selected.map(|p| p.as_os_str()).unwrap_or_default())
+        .env("QF_MARKED", marked.iter().map(|p| p.as_os_str()).collect::<Vec<_>>().join(OsStr::new("\n")))
+        .env("QF_COMMANDS", &commands_file)
+        .status();
+    let commands = fs::read_to_string(&commands_file).unwrap_or_default();
+    Ok((status?, commands.lines().map(str::to_string).filter(|l| !l.trim().is_empty()).collect()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{io::Write, os::unix::fs::PermissionsExt};
+
+    #[test]
+    fn plugins_get_the_marks_and_send_commands_back() {
+        let scratch = ScratchDir::new().unwrap();
+        let (path, mut file) = scratch.create("plugin").unwrap();
+        file.write_all(b"#!/bin/sh\nprintf '%s\\n' \"$QF_MARKED\" | while IFS= read -r p; do echo \"select ${p##*/}\" >> \"$QF_COMMANDS\"; done\n").unwrap();
+        drop(file);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o700)).unwrap();
+        let plugin = Plugin { name: "marks".to_string(), key: None, pause: false, path };
+        let marked = BTreeSet::from([PathBuf::from("/work/a b.png"), PathBuf::from("/work/c.png")]);
+        let (status, commands) = run(&plugin, scratch.path(), None, &marked).unwrap();
+        assert!(status.success());
+        assert_eq!(commands, ["select a b.png", "select c.png"]);
+    }
+}
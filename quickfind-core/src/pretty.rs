@@ -0,0 +1,223 @@
+use crate::json::Json;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Token {
+    Key,
+    Section,
+    String,
+    Number,
+    Literal,
+    Punct,
+    Comment,
+    Plain,
+}
+
+#[derive(Clone)]
+pub struct Line {
+    pub indent: usize,
+    pub tokens: Vec<(Token, String)>,
+}
+
+pub fn json(value: &Json) -> Vec<Line> {
+    let mut lines = Vec::new();
+    json_value(value, 0, Vec::new(), false, &mut lines);
+    lines
+}
+
+fn json_value(value: &Json, indent: usize, mut head: Vec<(Token, String)>, comma: bool, out: &mut Vec<Line>) {
+    let tail = || comma.then(|| (Token::Punct, ",".to_string()));
+    let (open, close, children): (&str, &str, Vec<(Option<&str>, &Json)>) = match value {
+        Json::Object(fields) => ("{", "}", fields.iter().map(|(k, v)| (Some(k.as_str()), v)).collect()),
+        Json::Array(items) => ("[", "]", items.iter().map(|v| (None, v)).collect()),
+        scalar => {
+            let (token, text) = match scalar {
+                Json::Null => (Token::Literal, "null".to_string()),
+                Json::Bool(b) => (Token::Literal, b.to_string()),
+                Json::Number(n) => (Token::Number, n.to_string()),
+                Json::String(s) => (Token::String, quote(s)),
+                _ => unreachable!(),
+            };
+            head.push((token, text));
+            head.extend(tail());
+            return out.push(Line { indent, tokens: head });
+        }
+    };
+    if children.is_empty() {
+        head.push((Token::Punct, format!("{}{}", open, close)));
+        head.extend(tail());
+        return out.push(Line { indent, tokens: head });
+    }
+    head.push((Token::Punct, open.to_string()));
+    out.push(Line { indent, tokens: head });
+    let last = children.len() - 1;
+    for (i, (key, child)) in children.into_iter().enumerate() {
+        let head = key.map(|k| vec![(Token::Key, quote(k)), (Token::Punct, ": ".to_string())]).unwrap_or_default();
+        json_value(child, indent + 1, head, i < last, out);
+    }
+    let mut end = vec![(Token::Punct, close.to_string())];
+    end.extend(tail());
+    out.push(Line { indent, tokens: end });
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn toml(text: &str) -> Vec<Line> {
+    let mut depth = 0;
+    text.lines().map(|raw| {
+        let line = raw.trim();
+        let (code, comment) = split_comment(line);
+        let mut tokens = Vec::new();
+        let indent = if depth > 0 && !code.starts_with(']') { 1 } else { 0 };
+        if code.starts_with('[') && depth == 0 {
+            tokens.push((Token::Section, code.trim_end().to_string()));
+        } else if depth == 0 && let Some((key, value)) = code.split_once('=') {
+            tokens.push((Token::Key, key.trim().to_string()));
+            tokens.push((Token::Punct, " = ".to_string()));
+            tokens.extend(values(value.trim()));
+        } else if !code.is_empty() {
+            tokens.extend(values(code));
+        }
+        depth = (depth + bracket_balance(code)).max(0);
+        if let Some(comment) = comment { tokens.push((Token::Comment, if code.is_empty() { comment.to_string() } else { format!("  {}", comment) })); }
+        Line { indent, tokens }
+    }).collect()
+}
+
+pub fn yaml(text: &str) -> Vec<Line> {
+    text.lines().map(|raw| {
+        let trimmed = raw.trim_start();
+        let indent = (raw.len() - trimmed.len()) / 2;
+        let (mut code, comment) = split_comment(trimmed.trim_end());
+        let mut tokens = Vec::new();
+        while let Some(rest) = code.strip_prefix("- ").or_else(|| (code == "-").then_some("")) {
+            tokens.push((Token::Punct, "- ".to_string()));
+            code = rest;
+        }
+        if matches!(code, "---" | "...") {
+            tokens.push((Token::Punct, code.to_string()));
+        } else if let Some((key, value)) = yaml_key(code) {
+            tokens.push((Token::Key, key.to_string()));
+            tokens.push((Token::Punct, ":".to_string()));
+            if !value.is_empty() { tokens.push((Token::Plain, " ".to_string())); tokens.push(scalar(value, Token::Plain)); }
+        } else if !code.is_empty() {
+            tokens.push(scalar(code, Token::Plain));
+        }
+        if let Some(comment) = comment { tokens.push((Token::Comment, if code.is_empty() { comment.to_string() } else { format!("  {}", comment) })); }
+        Line { indent, tokens }
+    }).collect()
+}
+
+fn yaml_key(code: &str) -> Option<(&str, &str)> {
+    if code.starts_with(['[', '{']) { return None; }
+    let end = match code.find(": ") {
+        Some(end) => end,
+        None if code.ends_with(':') => code.len() - 1,
+        None => return None,
+    };
+    let key = &code[..end];
+    if key.is_empty() || key.contains(" #") { return None; }
+    Some((key, code[end + 1..].trim()))
+}
+
+fn values(text: &str) -> Vec<(Token, String)> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let start = rest.trim_start();
+        if start.len() < rest.len() { tokens.push((Token::Plain, rest[..rest.len() - start.len()].to_string())); rest = start; continue; }
+        let c = rest.chars().next().unwrap_or(' ');
+        let end = match c {
+            '[' | ']' | '{' | '}' | ',' | '=' => c.len_utf8(),
+            '"' | '\'' => string_end(rest),
+            _ => rest.find(|c: char| matches!(c, ',' | ']' | '}' | '=') || c.is_whitespace()).unwrap_or(rest.len()).max(1),
+        };
+        let (piece, after) = rest.split_at(end);
+        tokens.push(if matches!(c, '[' | ']' | '{' | '}' | ',' | '=') { (Token::Punct, piece.to_string()) } else { scalar(piece, Token::Key) });
+        rest = after;
+    }
+    tokens
+}
+
+fn scalar(text: &str, bare: Token) -> (Token, String) {
+    let token = match text {
+        t if t.starts_with(['"', '\'']) => Token::String,
+        "true" | "false" | "null" | "~" | "yes" | "no" | "inf" | "nan" => Token::Literal,
+        t if t.replace('_', "").parse::<f64>().is_ok() => Token::Number,
+        t if t.len() >= 10 && t.as_bytes()[..4].iter().all(u8::is_ascii_digit) && t.as_bytes()[4] == b'-' => Token::Number,
+        _ => bare,
+    };
+    (token, text.to_string())
+}
+
+fn string_end(text: &str) -> usize {
+    let quote = text.as_bytes()[0];
+    let mut escaped = false;
+    for (i, b) in text.bytes().enumerate().skip(1) {
+        match b {
+            b'\\' if quote == b'"' && !escaped => { escaped = true; continue; }
+            b if b == quote && !escaped => return i + 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    text.len()
+}
+
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut at = 0;
+    while at < line.len() {
+        let rest = &line[at..];
+        match rest.as_bytes()[0] {
+            b'"' | b'\'' if at == 0 || line.as_bytes()[at - 1].is_ascii_whitespace() || b"[{,:=".contains(&line.as_bytes()[at - 1]) => at += string_end(rest),
+            b'#' if at == 0 || line.as_bytes()[at - 1].is_ascii_whitespace() => return (line[..at].trim_end(), Some(rest)),
+            _ => at += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    (line, None)
+}
+
+fn bracket_balance(code: &str) -> i32 {
+    let (mut balance, mut at) = (0, 0);
+    while at < code.len() {
+        let rest = &code[at..];
+        match rest.as_bytes()[0] {
+            b'"' | b'\'' => { at += string_end(rest); continue; }
+            b'[' | b'{' => balance += 1,
+            b']' | b'}' => balance -= 1,
+            _ => {}
+        }
+        at += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    balance
+}
+
+pub fn fold(lines: &[Line], threshold: usize) -> Vec<Line> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let block = lines[i + 1..].iter().take_while(|next| next.indent > line.indent || next.tokens.is_empty()).count();
+        out.push(line.clone());
+        let whole = i == 0 && block + 2 >= lines.len();
+        if block > threshold && !whole {
+            out.push(Line { indent: line.indent + 1, tokens: vec![(Token::Comment, format!("… {} lines folded", block))] });
+            i += block;
+        }
+        i += 1;
+    }
+    out
+}
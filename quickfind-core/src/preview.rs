@@ -0,0 +1,261 @@
+use std::{fs::{self, File}, io::{self, Read, Seek, SeekFrom}, path::{Path, PathBuf}, sync::mpsc::Receiver, thread};
+use crate::{archive::{self, Entry}, graphics::Placement, json::Json, media::{self, Kind}, picture::{self, Art}, pretty::{self, Line}, thumbnail, vfs::human_size, wake};
+
+const CHUNK_BYTES: u64 = 64 * 1024;
+const MAX_LINES: usize = 500;
+
+pub enum Body {
+    Text(Vec<String>),
+    Table(Vec<Vec<String>>),
+    Structured(Vec<Line>),
+    Hex(Vec<u8>),
+    Image(Art),
+    Archive(Vec<Entry>),
+    Listing(Vec<String>),
+    Binary,
+    Empty,
+    Error(String),
+}
+
+pub struct Preview {
+    pub path: PathBuf,
+    pub info: Vec<(String, String)>,
+    pub body: Body,
+    pub loaded: u64,
+    pub size: u64,
+    pub thumbnail: Option<PathBuf>,
+    pub fitted: (usize, usize),
+    tail: Vec<u8>,
+}
+
+impl Preview {
+    pub fn line_count(&self) -> usize {
+        match &self.body {
+            Body::Text(lines) => lines.len(),
+            Body::Hex(bytes) => bytes.len().div_ceil(HEX_WIDTH),
+            _ => 0,
+        }
+    }
+
+    pub fn can_grow(&self, limit: u64) -> bool {
+        matches!(self.body, Body::Text(_) | Body::Hex(_)) && self.loaded < self.size.min(limit)
+    }
+
+    fn grow(&mut self, limit: u64) -> io::Result<()> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.loaded))?;
+        let mut data = Vec::new();
+        file.take(CHUNK_BYTES.min(limit.saturating_sub(self.loaded))).read_to_end(&mut data)?;
+        self.loaded += data.len() as u64;
+        if data.is_empty() { self.size = self.loaded; }
+        let done = !self.can_grow(limit);
+        match &mut self.body {
+            Body::Hex(bytes) => bytes.extend(data),
+            Body::Text(lines) => {
+                self.tail.extend(data);
+                let split = if done { self.tail.len() } else { self.tail.iter().rposition(|b| *b == b'\n').map_or(0, |at| at + 1) };
+                let complete: Vec<u8> = self.tail.drain(..split).collect();
+                lines.extend(String::from_utf8_lossy(&complete).lines().map(|line| line.replace('\t', "    ")));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+pub const HEX_WIDTH: usize = 16;
+
+pub fn load(path: &Path, hex: bool, limit: u64, thumbnails: bool, fitted: (usize, usize)) -> Preview {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut preview = Preview { path: path.to_path_buf(), info: Vec::new(), body: Body::Empty, loaded: 0, size, thumbnail: None, fitted, tail: Vec::new() };
+    let (info, body) = match fs::metadata(path) {
+        Err(e) => (Vec::new(), Body::Error(e.to_string())),
+        Ok(meta) if meta.is_dir() => (Vec::new(), listing(path)),
+        Ok(_) if hex => (Vec::new(), Body::Hex(Vec::new())),
+        Ok(_) => match media::kind(path) {
+            Some(kind) => match media::metadata(path, kind) {
+                Ok(info) => {
+                    if thumbnails { preview.thumbnail = thumbnail::get(path, kind); }
+                    let source = preview.thumbnail.as_deref().or((kind == Kind::Image).then_some(path));
+                    let body = match source.map(|source| picture::render(source, fitted.0, fitted.1.saturating_sub(info.len() + 1))) {
+                        Some(Ok(art)) if !art.is_empty() => Body::Image(art),
+                        Some(Err(e)) => Body::Error(e),
+                        _ => Body::Empty,
+                    };
+                    (info, body)
+                }
+                Err(e) => (Vec::new(), Body::Error(e.to_string())),
+            },
+            None if archive::is_archive(path) => contents(path),
+            None => text(path, limit, &mut preview.loaded),
+        },
+    };
+    (preview.info, preview.body) = (info, body);
+    if matches!(preview.body, Body::Text(_) | Body::Hex(_)) && preview.loaded == 0 && let Err(e) = preview.grow(limit) { preview.body = Body::Error(e.to_string()); }
+    preview
+}
+
+fn listing(dir: &Path) -> Body {
+    let read = match fs::read_dir(dir) {
+        Ok(read) => read,
+        Err(e) => return Body::Error(e.to_string()),
+    };
+    let mut names: Vec<String> = read.flatten().map(|e| {
+        let name = e.file_name().to_string_lossy().into_owned();
+        if e.file_type().is_ok_and(|t| t.is_dir()) { format!("{}/", name) } else { name }
+    }).collect();
+    names.sort_unstable();
+    names.truncate(MAX_LINES);
+    if names.is_empty() { Body::Empty } else { Body::Listing(names) }
+}
+
+fn contents(path: &Path) -> (Vec<(String, String)>, Body) {
+    let listing = match archive::list(path, MAX_LINES + 1) {
+        Ok(listing) => listing,
+        Err(e) => return (Vec::new(), Body::Error(e.to_string())),
+    };
+    let mut entries = listing.entries;
+    let more = entries.len() > MAX_LINES;
+    entries.truncate(MAX_LINES);
+    let count = match listing.total {
+        Some(total) if total > entries.len() => format!("{} (showing {})", total, entries.len()),
+        None if more => format!("more than {}", MAX_LINES),
+        _ => entries.len().to_string(),
+    };
+    let mut info = vec![("Format".to_string(), listing.format.to_string()), ("Entries".to_string(), count)];
+    let size: u64 = entries.iter().map(|e| e.size).sum();
+    let compressed: Option<u64> = entries.iter().map(|e| e.compressed).sum();
+    info.push(("Unpacked".to_string(), match compressed {
+        Some(compressed) if size > 0 => format!("{} ({} packed, {}%)", human_size(size), human_size(compressed), compressed * 100 / size),
+        _ => human_size(size),
+    }));
+    (info, Body::Archive(entries))
+}
+
+fn text(path: &Path, limit: u64, loaded: &mut u64) -> (Vec<(String, String)>, Body) {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    let structured = matches!(extension.as_deref(), Some("csv" | "tsv" | "tab" | "json" | "yaml" | "yml" | "toml"));
+    let mut data = Vec::new();
+    if let Err(e) = File::open(path).and_then(|f| f.take(if structured { limit } else { CHUNK_BYTES.min(limit) }).read_to_end(&mut data)) { return (Vec::new(), Body::Error(e.to_string())); }
+    if data.is_empty() { return (Vec::new(), Body::Empty); }
+    if data.contains(&0) { return (Vec::new(), Body::Binary); }
+    if !structured { return (Vec::new(), Body::Text(Vec::new())); }
+    *loaded = data.len() as u64;
+    let text = String::from_utf8_lossy(&data);
+    let complete = fs::metadata(path).is_ok_and(|m| m.len() == *loaded);
+    match extension.as_deref() {
+        Some("csv") => (Vec::new(), Body::Table(table(&text, ','))),
+        Some("tsv" | "tab") => (Vec::new(), Body::Table(table(&text, '\t'))),
+        Some("yaml" | "yml") => (Vec::new(), Body::Structured(pretty::yaml(&text))),
+        Some("toml") => (Vec::new(), Body::Structured(pretty::toml(&text))),
+        _ if !complete => { *loaded = 0; (Vec::new(), Body::Text(Vec::new())) }
+        _ => match Json::parse(&text) {
+            Ok(value) => (Vec::new(), Body::Structured(pretty::json(&value))),
+            Err(e) => { *loaded = 0; (vec![("Invalid JSON".to_string(), e)], Body::Text(Vec::new())) }
+        },
+    }
+}
+
+fn table(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut quoted, mut chars) = (false, text.chars().peekable());
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => { chars.next(); field.push('"'); }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                if rows.len() == MAX_LINES { return rows; }
+            }
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut field)),
+            c => field.push(if c == '\n' || c == '\t' { ' ' } else { c }),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() { row.push(field); rows.push(row); }
+    rows
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Jump {
+    First,
+    Next,
+    Previous,
+}
+
+#[derive(Default)]
+pub struct Previewer {
+    pub current: Option<Preview>,
+    pub column: usize,
+    pub unfolded: bool,
+    pub scroll: usize,
+    pub width: usize,
+    pub height: usize,
+    pub query: String,
+    pub jump: Option<Jump>,
+    pub limit: u64,
+    pub hex: bool,
+    pub thumbnails: bool,
+    pub placement: Option<Placement>,
+    pending: Option<PathBuf>,
+    stale: bool,
+    receiver: Option<Receiver<Preview>>,
+}
+
+impl Previewer {
+    pub fn new(limit: u64, thumbnails: bool) -> Previewer {
+        Previewer { limit, thumbnails, ..Previewer::default() }
+    }
+
+    pub fn request(&mut self, path: &Path) {
+        let resized = self.current.as_ref().is_some_and(|p| p.fitted != (self.width, self.height) && media::kind(&p.path).is_some_and(|kind| kind != Kind::Audio));
+        if self.pending.as_deref() == Some(path) || self.current.as_ref().is_some_and(|p| p.path == path) && self.pending.is_none() && !self.stale && !resized { return; }
+        if self.current.as_ref().is_some_and(|p| p.path != path) { self.hex = false; }
+        let (sender, receiver) = wake::channel();
+        let (target, hex, limit, thumbnails, fitted) = (path.to_path_buf(), self.hex, self.limit, self.thumbnails, (self.width, self.height));
+        thread::spawn(move || { let _ = sender.send(load(&target, hex, limit, thumbnails, fitted)); });
+        (self.pending, self.receiver) = (Some(path.to_path_buf()), Some(receiver));
+    }
+
+    pub fn scroll_columns(&mut self, right: bool) {
+        let Some(Preview { body: Body::Table(rows), .. }) = &self.current else { return };
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        self.column = if right { (self.column + 1).min(columns.saturating_sub(1)) } else { self.column.saturating_sub(1) };
+    }
+
+    pub fn scroll_by(&mut self, lines: isize) {
+        self.scroll = self.scroll.saturating_add_signed(lines);
+    }
+
+    pub fn half_page(&self) -> isize {
+        (self.height / 2).max(1) as isize
+    }
+
+    pub fn toggle_hex(&mut self) {
+        self.hex = !self.hex;
+        self.stale = true;
+    }
+
+    pub fn load_more(&mut self) -> bool {
+        let (limit, wanted) = (self.limit, self.scroll.saturating_add(2 * self.height.max(1)));
+        let Some(preview) = self.current.as_mut() else { return false };
+        if !preview.can_grow(limit) || preview.line_count() > wanted { return false; }
+        if let Err(e) = preview.grow(limit) { preview.body = Body::Error(e.to_string()); }
+        true
+    }
+
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else { return false };
+        let Ok(preview) = receiver.try_recv() else { return false };
+        if self.current.as_ref().is_none_or(|current| current.path != preview.path) { (self.column, self.scroll) = (0, 0); }
+        (self.current, self.pending, self.receiver, self.stale) = (Some(preview), None, None, false);
+        true
+    }
+}
@@ -0,0 +1,332 @@
+use std::ops::Range;
+
+const MAX_PROGRAM: usize = 5_000;
+
+#[derive(Clone, Debug)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Class {
+    fn of(ranges: &[(char, char)], negated: bool) -> Class {
+        Class { ranges: ranges.to_vec(), negated }
+    }
+
+    fn contains(&self, c: char, insensitive: bool) -> bool {
+        let hit = |c: char| self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+        let found = hit(c) || (insensitive && (c.to_lowercase().any(hit) || c.to_uppercase().any(hit)));
+        found != self.negated
+    }
+}
+
+const DIGIT: [(char, char); 1] = [('0', '9')];
+const WORD: [(char, char); 4] = [('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: [(char, char); 2] = [('\t', '\r'), (' ', ' ')];
+
+#[derive(Clone, Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat(Box<Node>, u32, Option<u32>, bool),
+}
+
+#[derive(Clone, Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+}
+
+pub struct Regex {
+    program: Vec<Inst>,
+    insensitive: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, insensitive: bool) -> Result<Regex, String> {
+        let mut parser = Parser { chars: pattern.chars().collect(), at: 0 };
+        let node = parser.alternation()?;
+        if parser.at < parser.chars.len() { return Err(format!("unmatched ')' at {}", parser.at)); }
+        let mut program = Vec::new();
+        compile(&node, &mut program)?;
+        program.push(Inst::Match);
+        Ok(Regex { program, insensitive })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        self.find_at(&chars, 0).is_some()
+    }
+
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let offsets: Vec<usize> = text.char_indices().map(|(i, _)| i).chain([text.len()]).collect();
+        let chars: Vec<char> = text.chars().collect();
+        let mut ranges = Vec::new();
+        let mut from = 0;
+        while from <= chars.len() && let Some((start, end)) = self.find_at(&chars, from) {
+            if end > start { ranges.push(offsets[start]..offsets[end]); }
+            from = if end > start { end } else { end + 1 };
+        }
+        ranges
+    }
+
+    fn find_at(&self, text: &[char], from: usize) -> Option<(usize, usize)> {
+        let mut seen = vec![usize::MAX; self.program.len()];
+        let (mut current, mut next) = (Vec::new(), Vec::new());
+        let mut matched = None;
+        let mut pos = from;
+        loop {
+            if matched.is_none() { self.add(&mut current, &mut seen, 0, pos, pos, text); }
+            if current.is_empty() && matched.is_some() { break; }
+            for &(pc, start) in &current {
+                let advances = match &self.program[pc] {
+                    Inst::Match => { matched = Some((start, pos)); break; }
+                    Inst::Char(c) => text.get(pos).is_some_and(|&t| t == *c || (self.insensitive && t.to_lowercase().eq(c.to_lowercase()))),
+                    Inst::Any => text.get(pos).is_some_and(|&t| t != '\n'),
+                    Inst::Class(class) => text.get(pos).is_some_and(|&t| class.contains(t, self.insensitive)),
+                    _ => false,
+                };
+                if advances { self.add(&mut next, &mut seen, pc + 1, start, pos + 1, text); }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+            if pos >= text.len() { break; }
+            pos += 1;
+        }
+        matched
+    }
+
+    fn add(&self, list: &mut Vec<(usize, usize)>, seen: &mut [usize], pc: usize, start: usize, pos: usize, text: &[char]) {
+        if seen[pc] == pos { return; }
+        seen[pc] = pos;
+        match self.program[pc] {
+            Inst::Jmp(target) => self.add(list, seen, target, start, pos, text),
+            Inst::Split(first, second) => {
+                self.add(list, seen, first, start, pos, text);
+                self.add(list, seen, second, start, pos, text);
+            }
+            Inst::Start => if pos == 0 { self.add(list, seen, pc + 1, start, pos, text) },
+            Inst::End => if pos == text.len() { self.add(list, seen, pc + 1, start, pos, text) },
+            Inst::WordBoundary(negated) => {
+                let word = |c: Option<&char>| c.is_some_and(|&c| c.is_alphanumeric() || c == '_');
+                let boundary = word(pos.checked_sub(1).and_then(|i| text.get(i))) != word(text.get(pos));
+                if boundary != negated { self.add(list, seen, pc + 1, start, pos, text); }
+            }
+            _ => list.push((pc, start)),
+        }
+    }
+}
+
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<(), String> {
+    if program.len() > MAX_PROGRAM { return Err("pattern is too large".to_string()); }
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class(class) => program.push(Inst::Class(class.clone())),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::WordBoundary(negated) => program.push(Inst::WordBoundary(*negated)),
+        Node::Concat(nodes) => for node in nodes { compile(node, program)?; },
+        Node::Alt(options) => {
+            let mut jumps = Vec::new();
+            for (i, option) in options.iter().enumerate() {
+                if i + 1 == options.len() { compile(option, program)?; break; }
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(option, program)?;
+                jumps.push(program.len());
+                program.push(Inst::Jmp(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            let end = program.len();
+            for jump in jumps { program[jump] = Inst::Jmp(end); }
+        }
+        Node::Repeat(node, min, max, greedy) => {
+            for _ in 0..*min { compile(node, program)?; }
+            let split = |body: usize, exit: usize| if *greedy { Inst::Split(body, exit) } else { Inst::Split(exit, body) };
+            match max {
+                None => {
+                    let start = program.len();
+                    program.push(Inst::Match);
+                    compile(node, program)?;
+                    program.push(Inst::Jmp(start));
+                    program[start] = split(start + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Match);
+                        compile(node, program)?;
+                    }
+                    let end = program.len();
+                    for at in splits { program[at] = split(at + 1, end); }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) { self.at += 1; true } else { false }
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut options = vec![self.concat()?];
+        while self.eat('|') { options.push(self.concat()?); }
+        Ok(if options.len() == 1 { options.pop().unwrap() } else { Node::Alt(options) })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() && c != '|' && c != ')' {
+            let atom = self.atom()?;
+            nodes.push(self.repeat(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn repeat(&mut self, mut atom: Node) -> Result<Node, String> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => { self.at += 1; (0, None) }
+                Some('+') => { self.at += 1; (1, None) }
+                Some('?') => { self.at += 1; (0, Some(1)) }
+                Some('{') => match self.bounds() { Some(bounds) => bounds, None => return Ok(atom) },
+                _ => return Ok(atom),
+            };
+            if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) { return Err("nothing to repeat".to_string()); }
+            if max.is_some_and(|max| max < min) { return Err("invalid repetition bounds".to_string()); }
+            let greedy = !self.eat('?');
+            atom = Node::Repeat(Box::new(atom), min, max, greedy);
+        }
+    }
+
+    fn bounds(&mut self) -> Option<(u32, Option<u32>)> {
+        let close = self.at + self.chars[self.at..].iter().position(|&c| c == '}')?;
+        let inner: String = self.chars[self.at + 1..close].iter().collect();
+        let bounds = match inner.split_once(',') {
+            None => { let n = inner.parse().ok()?; (n, Some(n)) }
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+        };
+        if bounds.0 > 1000 || bounds.1.is_some_and(|max| max > 1000) { return None; }
+        self.at = close + 1;
+        Some(bounds)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("unexpected end of pattern")?;
+        self.at += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                if self.chars[self.at..].starts_with(&['?', ':']) { self.at += 2; }
+                let inner = self.alternation()?;
+                if !self.eat(')') { return Err("unclosed group".to_string()); }
+                inner
+            }
+            '[' => Node::Class(self.class()?),
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => return Err("nothing to repeat".to_string()),
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("trailing backslash")?;
+        self.at += 1;
+        Ok(match c {
+            'd' => Node::Class(Class::of(&DIGIT, false)),
+            'D' => Node::Class(Class::of(&DIGIT, true)),
+            'w' => Node::Class(Class::of(&WORD, false)),
+            'W' => Node::Class(Class::of(&WORD, true)),
+            's' => Node::Class(Class::of(&SPACE, false)),
+            'S' => Node::Class(Class::of(&SPACE, true)),
+            'b' => Node::WordBoundary(false),
+            'B' => Node::WordBoundary(true),
+            c => Node::Char(literal_escape(c)),
+        })
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or("unclosed character class")?;
+            self.at += 1;
+            if c == ']' && !first { break; }
+            first = false;
+            let lo = match c {
+                '\\' => {
+                    let e = self.peek().ok_or("trailing backslash")?;
+                    self.at += 1;
+                    match e {
+                        'd' => { ranges.extend(DIGIT); continue }
+                        'w' => { ranges.extend(WORD); continue }
+                        's' => { ranges.extend(SPACE); continue }
+                        'D' | 'W' | 'S' => return Err(format!("\\{} is not supported inside a class", e)),
+                        e => literal_escape(e),
+                    }
+                }
+                c => c,
+            };
+            if self.peek() == Some('-') && self.chars.get(self.at + 1).is_some_and(|&n| n != ']') {
+                self.at += 1;
+                let mut hi = self.peek().ok_or("unclosed character class")?;
+                self.at += 1;
+                if hi == '\\' { hi = literal_escape(self.peek().ok_or("trailing backslash")?); self.at += 1; }
+                if hi < lo { return Err(format!("invalid class range {}-{}", lo, hi)); }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        Ok(Class { ranges, negated })
+    }
+}
+
+fn literal_escape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+pub fn has_uppercase_literal(pattern: &str) -> bool {
+    let mut escaped = false;
+    pattern.chars().any(|c| {
+        let literal = !escaped && c.is_uppercase();
+        escaped = !escaped && c == '\\';
+        literal
+    })
+}
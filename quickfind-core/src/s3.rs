@@ -0,0 +1,125 @@
+use std::{io::Write, path::Component, path::Path, path::PathBuf, process::{Command, Stdio}, sync::{Arc, atomic::AtomicBool}};
+use crate::vfs::{self, Filesystem, Stat};
+
+#[derive(Clone)]
+pub struct S3Bucket {
+    pub bucket: String,
+}
+
+impl S3Bucket {
+    pub fn parse(url: &str) -> Option<(S3Bucket, String)> {
+        let rest = url.strip_prefix("s3://")?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() { return None; }
+        Some((S3Bucket { bucket: bucket.to_string() }, format!("/{}", prefix)))
+    }
+
+    fn url(&self, path: &Path, is_dir: bool) -> String {
+        let key = path.to_string_lossy().trim_start_matches('/').to_string();
+        let slash = if is_dir && !key.is_empty() { "/" } else { "" };
+        format!("s3://{}/{}{}", self.bucket, key, slash)
+    }
+
+    fn aws(&self, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.aws_until(args, input, &AtomicBool::new(false))
+    }
+
+    fn aws_until(&self, args: &[&str], input: Option<&[u8]>, cancel: &AtomicBool) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut child = Command::new("aws").args(args).stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() }).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) { stdin.write_all(input)?; }
+        let output = vfs::wait_cancellable(child, cancel)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if args.get(1) == Some(&"ls") && stderr.trim().is_empty() { return Ok(Vec::new()); }
+            return Err(format!("aws: {}", stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("failed")).into());
+        }
+        Ok(output.stdout)
+    }
+
+    fn recursive(&self, path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(self.stat(path)?.ok_or_else(|| format!("no such object: {}", self.url(path, false)))?.is_dir)
+    }
+}
+
+impl Filesystem for S3Bucket {
+    fn list(&self, dir: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+        let output = self.aws(&["s3", "ls", &self.url(dir, true)], None)?;
+        Ok(String::from_utf8_lossy(&output).lines().filter_map(|line| {
+            let line = line.trim_start();
+            if let Some(prefix) = line.strip_prefix("PRE ") { return Some((prefix.trim_end_matches('/').to_string(), true)); }
+            let name = vfs::skip_fields(line, 3)?;
+            (!name.is_empty()).then(|| (name.to_string(), false))
+        }).collect())
+    }
+
+    fn stat(&self, path: &Path) -> Result<Option<Stat>, Box<dyn std::error::Error>> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { return Ok(Some(Stat { is_dir: true, is_symlink: false })) };
+        let name = name.to_string_lossy();
+        Ok(self.list(parent)?.into_iter().find(|(entry, _)| *entry == name).map(|(_, is_dir)| Stat { is_dir, is_symlink: false }))
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.aws(&["s3", "cp", &self.url(path, false), "-"], None)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.aws(&["s3", "cp", "-", &self.url(path, false)], Some(contents)).map(|_| ())
+    }
+
+    fn mkdir(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let key = format!("{}/", path.to_string_lossy().trim_start_matches('/'));
+        self.aws(&["s3api", "put-object", "--bucket", &self.bucket, "--key", &key], None).map(|_| ())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let is_dir = self.recursive(from)?;
+        let (from, to) = (self.url(from, is_dir), self.url(to, is_dir));
+        let mut args = vec!["s3", "mv", &from, &to];
+        if is_dir { args.push("--recursive"); }
+        self.aws(&args, None).map(|_| ())
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let is_dir = self.recursive(path)?;
+        let url = self.url(path, is_dir);
+        let mut args = vec!["s3", "rm", &url];
+        if is_dir { args.push("--recursive"); }
+        self.aws(&args, None).map(|_| ())
+    }
+
+    fn resolve(&self, base: &Path, path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut resolved = PathBuf::from("/");
+        for component in base.join(path).components() {
+            match component {
+                Component::ParentDir => { resolved.pop(); }
+                Component::Normal(part) => resolved.push(part),
+                _ => {}
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn location(&self, dir: &Path) -> String { self.url(dir, true) }
+
+    fn shell_hint(&self, dir: &Path) -> String { format!("aws s3 ls '{}'", self.url(dir, true)) }
+
+    fn share(&self) -> Option<Arc<dyn Filesystem + Send + Sync>> { Some(Arc::new(self.clone())) }
+
+    fn download(&self, path: &Path, local_dir: &Path, cancel: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        let name = path.file_name().ok_or("nothing to download")?;
+        let is_dir = self.recursive(path)?;
+        let (source, target) = (self.url(path, is_dir), local_dir.join(name).to_string_lossy().into_owned());
+        let mut args = vec!["s3", "cp", "--only-show-errors", &source, &target];
+        if is_dir { args.push("--recursive"); }
+        self.aws_until(&args, None, cancel).map(|_| ())
+    }
+
+    fn upload(&self, local: &Path, dir: &Path, cancel: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        let name = local.file_name().ok_or("nothing to upload")?;
+        let is_dir = local.is_dir();
+        let (source, target) = (local.to_string_lossy().into_owned(), self.url(&dir.join(name), is_dir));
+        let mut args = vec!["s3", "cp", "--only-show-errors", &source, &target];
+        if is_dir { args.push("--recursive"); }
+        self.aws_until(&args, None, cancel).map(|_| ())
+    }
+}
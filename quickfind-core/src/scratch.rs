@@ -0,0 +1,73 @@
+use std::{collections::hash_map::RandomState, env, fs::{self, File, OpenOptions}, hash::{BuildHasher, Hasher}, io, path::{Path, PathBuf}, time::SystemTime};
+
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    pub fn new() -> io::Result<ScratchDir> {
+        for _ in 0..16 {
+            let path = env::temp_dir().join(format!("quickfind-{:016x}", random()));
+            match private_dir(&path) {
+                Ok(()) => return Ok(ScratchDir { path }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, "could not create a private temporary directory"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn create(&self, name: &str) -> io::Result<(PathBuf, File)> {
+        let path = self.path.join(name);
+        let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+        Ok((path, file))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn random() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u128(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default());
+    hasher.finish()
+}
+
+#[cfg(unix)]
+fn private_dir(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(0o700).create(path)
+}
+
+#[cfg(not(unix))]
+fn private_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scratch_dirs_are_private_and_removed() {
+        let (first, second) = (ScratchDir::new().unwrap(), ScratchDir::new().unwrap());
+        assert_ne!(first.path(), second.path());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(first.path()).unwrap().permissions().mode() & 0o777, 0o700);
+        }
+        let (path, _) = first.create("commands").unwrap();
+        assert!(first.create("commands").is_err());
+        drop(first);
+        assert!(!path.exists());
+    }
+}
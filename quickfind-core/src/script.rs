@@ -0,0 +1,53 @@
+use std::io::Write;
+use crate::{actions::{self, Action}, state::AppState, tree};
+
+pub fn run(app_state: &mut AppState, script: &str, out: &mut impl Write) -> Result<(), Box<dyn std::error::Error>> {
+    for (number, line) in split_commands(script)?.iter().enumerate() {
+        execute(app_state, line, out).map_err(|e| format!("command {} (`{}`): {}", number + 1, line, e))?;
+    }
+    Ok(())
+}
+
+fn execute(app_state: &mut AppState, line: &str, out: &mut impl Write) -> Result<(), Box<dyn std::error::Error>> {
+    match actions::split_args(line)?.as_slice() {
+        [name] if name == "list" => {
+            for entry in &app_state.entries {
+                writeln!(out, "{}{}", entry, if app_state.dir_names.contains(entry) { "/" } else { "" })?;
+            }
+        }
+        [name, ..] if name == "list" => return Err("wrong number of arguments for `list`".into()),
+        [name] if name == "pwd" => writeln!(out, "{}", app_state.fs.location(&app_state.focus_dir))?,
+        [name, args @ ..] if name == "tree" => {
+            if !app_state.fs.is_local() { return Err("tree only works on local directories".into()); }
+            let mut limits = app_state.scan_limits();
+            if tree::parse_args(args, &mut limits)?.is_none() { tree::write(&app_state.focus_dir, &limits, out)?; return Ok(()); }
+            app_state.export_tree(args)?;
+            if let Some(status) = app_state.status.take() { eprintln!("{}", status); }
+        }
+        _ => {
+            app_state.apply(Action::parse(line)?)?;
+            if let Some(status) = app_state.status.take() { eprintln!("{}", status); }
+        }
+    }
+    Ok(())
+}
+
+pub fn split_commands(script: &str) -> Result<Vec<String>, String> {
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut chars = script.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => { quote = None; current.push(c); }
+            (Some('"') | None, '\\') => { current.push(c); current.push(chars.next().ok_or("trailing backslash")?); }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => { quote = Some(c); current.push(c); }
+            (None, ';' | '\n') => commands.push(std::mem::take(&mut current)),
+            (None, c) => current.push(c),
+        }
+    }
+    if quote.is_some() { return Err("unterminated quote".to_string()); }
+    commands.push(current);
+    Ok(commands.into_iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty() && !c.starts_with('#')).collect())
+}
@@ -0,0 +1,248 @@
+use std::{fs, io::{self, BufRead, BufReader}, ops::Range, path::{Path, PathBuf}, process::{Command, Stdio}};
+use crate::{config::Document, finder::{self, ScanLimits}, i18n::tr, json::Json, regex::{self, Regex}};
+
+pub const MAX_HITS: usize = 1000;
+const MAX_GREP_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub line: Option<usize>,
+    pub text: String,
+}
+
+impl Hit {
+    fn path(path: PathBuf, is_dir: bool) -> Hit {
+        Hit { path, is_dir, line: None, text: String::new() }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CaseMode {
+    #[default]
+    Smart,
+    Insensitive,
+    Sensitive,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MatchOptions {
+    pub case: CaseMode,
+    pub regex: bool,
+    pub fuzzy: bool,
+}
+
+impl MatchOptions {
+    pub fn from_document(doc: &Document) -> MatchOptions {
+        let case = match doc.str("search", "case") {
+            Some("insensitive") => CaseMode::Insensitive,
+            Some("sensitive") => CaseMode::Sensitive,
+            _ => CaseMode::Smart,
+        };
+        MatchOptions { case, regex: doc.bool("search", "regex").unwrap_or(false), fuzzy: false }
+    }
+
+    pub fn cycle_case(&mut self) {
+        self.case = match self.case { CaseMode::Smart => CaseMode::Insensitive, CaseMode::Insensitive => CaseMode::Sensitive, CaseMode::Sensitive => CaseMode::Smart };
+    }
+
+    pub fn label(&self) -> String {
+        let case = match self.case { CaseMode::Smart => "smart case", CaseMode::Insensitive => "ignore case", CaseMode::Sensitive => "match case" };
+        format!("{}, {}", tr(case), tr(if self.regex { "regex" } else if self.fuzzy { "fuzzy" } else { "literal" }))
+    }
+
+    fn case_flag(&self) -> &'static str {
+        match self.case { CaseMode::Smart => "--smart-case", CaseMode::Insensitive => "--ignore-case", CaseMode::Sensitive => "--case-sensitive" }
+    }
+
+    fn backend_args(&self) -> Vec<&'static str> {
+        let mut args = vec![self.case_flag()];
+        if !self.regex { args.push("--fixed-strings"); }
+        args
+    }
+}
+
+pub struct Results {
+    pub query: String,
+    pub options: MatchOptions,
+    pub root: PathBuf,
+    pub hits: Vec<Hit>,
+    pub index: usize,
+    pub origin: (PathBuf, Option<String>),
+    pub visible: bool,
+}
+
+impl Results {
+    pub fn current(&self) -> Option<PathBuf> {
+        self.hits.get(self.index).map(|hit| self.root.join(&hit.path))
+    }
+
+    pub fn step(&mut self, forward: bool) {
+        self.index = if forward { (self.index + 1).min(self.hits.len().saturating_sub(1)) } else { self.index.saturating_sub(1) };
+    }
+}
+
+pub fn find(root: &Path, pattern: &str, options: MatchOptions, limits: &ScanLimits) -> io::Result<Vec<Hit>> {
+    let matcher = Matcher::new(pattern, options).map_err(io::Error::other)?;
+    match ["fd", "fdfind"].into_iter().find(|program| finder::on_path(program)) {
+        Some(program) => find_with_fd(program, root, pattern, options, limits),
+        None => find_with_walk(root, &matcher, limits),
+    }
+}
+
+fn find_with_fd(program: &str, root: &Path, pattern: &str, options: MatchOptions, limits: &ScanLimits) -> io::Result<Vec<Hit>> {
+    let mut command = Command::new(program);
+    command.args(["--color", "never", "--max-results", &MAX_HITS.to_string()]).args(options.backend_args());
+    if let Some(depth) = limits.max_depth { command.args(["--max-depth", &depth.to_string()]); }
+    for pattern in &limits.ignore { command.args(["--exclude", pattern]); }
+    for dir in &limits.skip_dirs { command.arg("--exclude").arg(format!("{}/", dir)); }
+    let output = command.args(["--", pattern]).current_dir(root).output()?;
+    if !output.status.success() { return Err(io::Error::other(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()))); }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(|line| {
+        let path = PathBuf::from(line.strip_prefix("./").unwrap_or(line).trim_end_matches('/'));
+        let is_dir = root.join(&path).is_dir();
+        Hit::path(path, is_dir)
+    }).collect())
+}
+
+fn find_with_walk(root: &Path, matcher: &Matcher, limits: &ScanLimits) -> io::Result<Vec<Hit>> {
+    let mut hits = Vec::new();
+    let result = finder::walk(root, limits, &mut |path, is_dir| {
+        if path.file_name().is_some_and(|name| matcher.is_match(&name.to_string_lossy())) { hits.push(Hit::path(path.to_path_buf(), is_dir)); }
+        if hits.len() >= MAX_HITS { Err(io::ErrorKind::Interrupted.into()) } else { Ok(()) }
+    });
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::Interrupted => Err(e),
+        _ => Ok(hits),
+    }
+}
+
+pub fn grep(root: &Path, pattern: &str, options: MatchOptions, limits: &ScanLimits) -> io::Result<Vec<Hit>> {
+    let matcher = Matcher::new(pattern, options).map_err(io::Error::other)?;
+    if finder::on_path("rg") { grep_with_rg(root, pattern, options, limits) } else { grep_with_walk(root, &matcher, limits) }
+}
+
+fn grep_with_rg(root: &Path, pattern: &str, options: MatchOptions, limits: &ScanLimits) -> io::Result<Vec<Hit>> {
+    let mut command = Command::new("rg");
+    command.arg("--json").args(options.backend_args());
+    if let Some(depth) = limits.max_depth { command.args(["--max-depth", &depth.to_string()]); }
+    for pattern in &limits.ignore { command.arg("--glob").arg(format!("!{}", pattern)); }
+    for dir in &limits.skip_dirs { command.arg("--glob").arg(format!("!{}/", dir)); }
+    let mut child = command.args(["--", pattern, "."]).current_dir(root).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    let mut hits = Vec::new();
+    for line in BufReader::new(child.stdout.take().expect("piped stdout")).lines() {
+        let Ok(event) = Json::parse(&line?) else { continue };
+        if event.get("type").and_then(Json::as_str) != Some("match") { continue }
+        let Some(data) = event.get("data") else { continue };
+        let Some(path) = data.get("path").and_then(|p| p.get("text")).and_then(Json::as_str) else { continue };
+        let text = data.get("lines").and_then(|l| l.get("text")).and_then(Json::as_str).unwrap_or("");
+        let line = data.get("line_number").and_then(Json::as_f64).map(|n| n as usize);
+        hits.push(Hit { path: PathBuf::from(path.strip_prefix("./").unwrap_or(path)), is_dir: false, line, text: text.trim_end().to_string() });
+        if hits.len() >= MAX_HITS { let _ = child.kill(); break; }
+    }
+    let status = child.wait()?;
+    if status.code() == Some(2) && hits.is_empty() { return Err(io::Error::other(format!("rg exited with {}", status))); }
+    Ok(hits)
+}
+
+fn grep_with_walk(root: &Path, matcher: &Matcher, limits: &ScanLimits) -> io::Result<Vec<Hit>> {
+    let mut hits = Vec::new();
+    let result = finder::walk(root, limits, &mut |path, is_dir| {
+        if is_dir || fs::metadata(root.join(path)).is_ok_and(|m| m.len() > MAX_GREP_BYTES) { return Ok(()); }
+        let Ok(bytes) = fs::read(root.join(path)) else { return Ok(()) };
+        if bytes[..bytes.len().min(8192)].contains(&0) { return Ok(()); }
+        for (number, text) in String::from_utf8_lossy(&bytes).lines().enumerate() {
+            if !matcher.is_match(text) { continue }
+            hits.push(Hit { path: path.to_path_buf(), is_dir: false, line: Some(number + 1), text: text.trim_end().to_string() });
+            if hits.len() >= MAX_HITS { return Err(io::ErrorKind::Interrupted.into()); }
+        }
+        Ok(())
+    });
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::Interrupted => Err(e),
+        _ => Ok(hits),
+    }
+}
+
+pub struct Matcher {
+    pattern: Pattern,
+}
+
+enum Pattern {
+    Empty,
+    Literal(Vec<char>, bool),
+    Fuzzy(Vec<char>, bool),
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, options: MatchOptions) -> Result<Matcher, String> {
+        let uppercase = if options.regex { regex::has_uppercase_literal(pattern) } else { pattern.chars().any(char::is_uppercase) };
+        let sensitive = match options.case { CaseMode::Smart => uppercase, CaseMode::Insensitive => false, CaseMode::Sensitive => true };
+        let pattern = match (pattern.is_empty(), options.regex) {
+            (true, _) => Pattern::Empty,
+            (false, true) => Pattern::Regex(Regex::new(pattern, !sensitive)?),
+            (false, false) if options.fuzzy => Pattern::Fuzzy(pattern.chars().filter(|c| !c.is_whitespace()).collect(), sensitive),
+            (false, false) => Pattern::Literal(pattern.chars().collect(), sensitive),
+        };
+        Ok(Matcher { pattern })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.pattern {
+            Pattern::Empty => true,
+            Pattern::Regex(regex) => regex.is_match(text),
+            Pattern::Fuzzy(needle, _) if needle.is_empty() => true,
+            Pattern::Literal(..) | Pattern::Fuzzy(..) => !self.ranges(text).is_empty(),
+        }
+    }
+
+    pub fn spread(&self, text: &str) -> usize {
+        let ranges = self.ranges(text);
+        match (ranges.first(), ranges.last()) {
+            (Some(first), Some(last)) => last.end - first.start,
+            _ => 0,
+        }
+    }
+
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let (needle, sensitive) = match &self.pattern {
+            Pattern::Empty => return Vec::new(),
+            Pattern::Regex(regex) => return regex.ranges(text),
+            Pattern::Literal(needle, sensitive) => (needle, *sensitive),
+            Pattern::Fuzzy(needle, sensitive) => return fuzzy_ranges(text, needle, *sensitive),
+        };
+        let same = |a: char, b: char| same_char(a, b, sensitive);
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut ranges = Vec::new();
+        let mut i = 0;
+        while i + needle.len() <= chars.len() {
+            if chars[i..i + needle.len()].iter().zip(needle).all(|(&(_, c), &n)| same(c, n)) {
+                let end = chars.get(i + needle.len()).map_or(text.len(), |&(at, _)| at);
+                ranges.push(chars[i].0..end);
+                i += needle.len();
+            } else { i += 1; }
+        }
+        ranges
+    }
+}
+
+fn same_char(a: char, b: char, sensitive: bool) -> bool {
+    a == b || (!sensitive && a.to_lowercase().eq(b.to_lowercase()))
+}
+
+fn fuzzy_ranges(text: &str, needle: &[char], sensitive: bool) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut wanted = needle.iter().peekable();
+    for (at, c) in text.char_indices() {
+        let Some(&&n) = wanted.peek() else { break };
+        if !same_char(c, n, sensitive) { continue }
+        wanted.next();
+        match ranges.last_mut() {
+            Some(last) if last.end == at => last.end = at + c.len_utf8(),
+            _ => ranges.push(at..at + c.len_utf8()),
+        }
+    }
+    if wanted.peek().is_some() { Vec::new() } else { ranges }
+}
@@ -0,0 +1,141 @@
+use std::{fs, io::Write, path::Path, path::PathBuf, process::{Command, Stdio}, sync::{Arc, atomic::AtomicBool}};
+use crate::{scratch::ScratchDir, vfs::{self, Filesystem, Stat}};
+
+#[derive(Clone)]
+pub struct SftpTarget {
+    pub destination: String,
+    pub port: Option<u16>,
+}
+
+impl SftpTarget {
+    pub fn parse(url: &str) -> Option<(SftpTarget, String)> {
+        let rest = url.strip_prefix("sftp://")?;
+        let (authority, path) = match rest.split_once('/') {
+            Some((authority, path)) if path == "~" || path.starts_with("~/") => (authority, path.trim_start_matches('~').trim_start_matches('/').to_string()),
+            Some((authority, path)) => (authority, format!("/{}", path)),
+            None => (rest, String::new()),
+        };
+        let (destination, port) = match authority.rsplit_once(':') {
+            Some((destination, port)) if port.parse::<u16>().is_ok() => (destination, port.parse().ok()),
+            _ => (authority, None),
+        };
+        if destination.is_empty() { return None; }
+        Some((SftpTarget { destination: destination.to_string(), port }, if path.is_empty() { ".".to_string() } else { path }))
+    }
+
+    fn url(&self, path: &Path) -> String {
+        let port = self.port.map(|p| format!(":{}", p)).unwrap_or_default();
+        format!("sftp://{}{}{}", self.destination, port, path.display())
+    }
+
+    fn batch(&self, script: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.batch_until(script, &AtomicBool::new(false))
+    }
+
+    fn batch_until(&self, script: &str, cancel: &AtomicBool) -> Result<String, Box<dyn std::error::Error>> {
+        let mut command = Command::new("sftp");
+        command.args(["-q", "-b", "-", "-o", "BatchMode=yes", "-o", "ControlMaster=auto", "-o", "ControlPath=~/.ssh/quickfind-%C", "-o", "ControlPersist=120"]);
+        if let Some(port) = self.port { command.arg("-P").arg(port.to_string()); }
+        let mut child = command.arg(&self.destination).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() { stdin.write_all(script.as_bytes())?; }
+        let output = vfs::wait_cancellable(child, cancel)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("sftp: {}", stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("failed")).into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn removal_script(&self, path: &Path, is_dir: bool, script: &mut String) -> Result<(), Box<dyn std::error::Error>> {
+        if is_dir {
+            for (name, child_is_dir) in self.list(path)? { self.removal_script(&path.join(name), child_is_dir, script)?; }
+            script.push_str(&format!("rmdir {}\n", quote(&path.to_string_lossy())));
+        } else {
+            script.push_str(&format!("rm {}\n", quote(&path.to_string_lossy())));
+        }
+        Ok(())
+    }
+}
+
+impl Filesystem for SftpTarget {
+    fn list(&self, dir: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+        let output = self.batch(&format!("cd {}\nls -la\n", quote(&dir.to_string_lossy())))?;
+        Ok(parse_listing(&output))
+    }
+
+    fn stat(&self, path: &Path) -> Result<Option<Stat>, Box<dyn std::error::Error>> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { return Ok(Some(Stat { is_dir: true, is_symlink: false })) };
+        let name = name.to_string_lossy();
+        Ok(self.list(parent)?.into_iter().find(|(entry, _)| *entry == name).map(|(_, is_dir)| Stat { is_dir, is_symlink: false }))
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let scratch = ScratchDir::new()?;
+        let local = scratch.path().join("read");
+        self.batch(&format!("get {} {}\n", quote(&path.to_string_lossy()), quote(&local.to_string_lossy())))?;
+        Ok(fs::read(&local)?)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let scratch = ScratchDir::new()?;
+        let (local, mut file) = scratch.create("write")?;
+        file.write_all(contents)?;
+        drop(file);
+        self.batch(&format!("put {} {}\n", quote(&local.to_string_lossy()), quote(&path.to_string_lossy()))).map(|_| ())
+    }
+
+    fn mkdir(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.batch(&format!("mkdir {}\n", quote(&path.to_string_lossy()))).map(|_| ())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.batch(&format!("rename {} {}\n", quote(&from.to_string_lossy()), quote(&to.to_string_lossy()))).map(|_| ())
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let is_dir = self.stat(path)?.ok_or_else(|| format!("no such entry: {}", path.display()))?.is_dir;
+        let mut script = String::new();
+        self.removal_script(path, is_dir, &mut script)?;
+        self.batch(&script).map(|_| ())
+    }
+
+    fn resolve(&self, base: &Path, path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let target = match path.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => PathBuf::from(".").join(rest.trim_start_matches('/')),
+            _ => base.join(path),
+        };
+        let output = self.batch(&format!("cd {}\npwd\n", quote(&target.to_string_lossy())))?;
+        output.lines().find_map(|l| l.strip_prefix("Remote working directory: ")).map(|p| PathBuf::from(p.trim())).ok_or_else(|| "could not resolve remote directory".into())
+    }
+
+    fn location(&self, dir: &Path) -> String { self.url(dir) }
+
+    fn shell_hint(&self, dir: &Path) -> String {
+        let port = self.port.map(|p| format!("-P {} ", p)).unwrap_or_default();
+        format!("sftp {}'{}:{}'", port, self.destination, dir.display())
+    }
+
+    fn share(&self) -> Option<Arc<dyn Filesystem + Send + Sync>> { Some(Arc::new(self.clone())) }
+
+    fn download(&self, path: &Path, local_dir: &Path, cancel: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        self.batch_until(&format!("lcd {}\nget -r {}\n", quote(&local_dir.to_string_lossy()), quote(&path.to_string_lossy())), cancel).map(|_| ())
+    }
+
+    fn upload(&self, local: &Path, dir: &Path, cancel: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        self.batch_until(&format!("cd {}\nput -r {}\n", quote(&dir.to_string_lossy()), quote(&local.to_string_lossy())), cancel).map(|_| ())
+    }
+}
+
+fn quote(path: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '"' | '\\' | '*' | '?' | '[' | ']') { quoted.push('\\'); }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn parse_listing(output: &str) -> Vec<(String, bool)> {
+    output.lines().filter(|l| !l.starts_with("sftp>")).filter_map(vfs::parse_long_listing).collect()
+}
@@ -0,0 +1,23 @@
+use std::{env, path::Path, process::Command};
+
+pub fn command(script: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(script);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        command
+    }
+}
+
+pub fn quote(arg: &str) -> String {
+    if cfg!(windows) { format!("\"{}\"", arg.replace('"', "\"\"")) } else { format!("'{}'", arg.replace('\'', "'\\''")) }
+}
+
+pub fn cd_command(dir: &Path) -> String {
+    let dir = dir.to_string_lossy();
+    if !cfg!(windows) { return format!("cd {}", quote(&dir)); }
+    if env::var_os("PROMPT").is_some() { format!("cd /d \"{}\"", dir) } else { format!("Set-Location -LiteralPath '{}'", dir.replace('\'', "''")) }
+}
@@ -0,0 +1,1446 @@
+use std::{env, fs::{self, File}, collections::{BTreeSet, HashMap, HashSet}, io::Write, path::Path, path::PathBuf, time::Duration};
+use tui::widgets::ListState;
+use crate::{actions::Action, compare::{self, Comparison, Difference}, config::{Config, Value}, devices::Device, favorites::Favorites, perms::{self, ModeSpec}, finder::{self, ScanLimits}, foreach::{self, Outcome}, git, glob, graphics::{self, Protocol}, history::History, i18n::{self, tr, trf}, ipc::ControlSocket, jobs::{Jobs, Status, Work}, listing::{self, Column, DirCounts, DirStream, ListingSettings, SortKey}, notify, plugins::{self, Plugin}, preview::Previewer, search::{self, Hit, Matcher, Results}, streams, sync::{self, Plan}, tags::{self, Tags}, theme::{ColorDepth, Theme}, trash, tree, vfs::{self, Filesystem, LineList, Meta, human_size}, watch::DirWatcher, wipe, xattr};
+
+const PREVIEW_BYTES: usize = 4096;
+const COPY_LIMIT: u64 = 1 << 20;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum PopupMode {
+    None,
+    CreateFile,
+    CreateDir,
+    Delete,
+    Overwrite,
+    Shred,
+    Rename,
+    Palette,
+    Upload,
+    Devices,
+    Drives,
+    Filter,
+    Find,
+    Grep,
+    Results,
+    Marks,
+    Visual,
+    ForEach,
+    Report,
+    Tag,
+    Favorites,
+    Properties,
+    Xattr,
+    Preview,
+    PreviewSearch,
+    Diff,
+    Compare,
+    Comparison,
+    Tree,
+    Sync,
+    Jobs,
+    Yank,
+    Paste,
+    Chmod,
+    Group,
+    Conflict,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Clash {
+    Skip,
+    KeepBoth,
+    Merge,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transfer {
+    Copy,
+    Move,
+    Link,
+}
+
+impl Transfer {
+    fn verb(self) -> &'static str {
+        match self {
+            Transfer::Copy => tr("copy"),
+            Transfer::Move => tr("move"),
+            Transfer::Link => tr("link"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Selection {
+    All,
+    None,
+    Invert,
+}
+
+pub struct Diff {
+    pub path: PathBuf,
+    pub staged: bool,
+    pub lines: Vec<String>,
+    pub scroll: usize,
+}
+
+pub struct Properties {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub meta: Option<Meta>,
+    pub link_target: Option<PathBuf>,
+    pub streams: Vec<(String, u64)>,
+    pub xattrs: Vec<(String, Vec<u8>)>,
+    pub preview: Option<String>,
+    pub delete_armed: bool,
+}
+
+#[derive(Clone)]
+pub enum ExternalTask {
+    Plugin(usize),
+    Command(usize),
+    Finder(String),
+    Pager(PathBuf),
+    Copy(String, String),
+    Paste(String),
+}
+
+#[derive(Default)]
+pub struct Stores {
+    pub plugins: Vec<Plugin>,
+    pub history: History,
+    pub tags: Tags,
+    pub favorites: Favorites,
+}
+
+impl Stores {
+    pub fn load() -> Stores {
+        Stores { plugins: plugins::load(), history: History::load(), tags: Tags::load(), favorites: Favorites::load() }
+    }
+}
+
+pub struct AppState {
+    pub focus_dir: PathBuf,
+    pub entries: Vec<String>,
+    pub dir_names: HashSet<String>,
+    pub selected_index: usize,
+    pub list_state: ListState,
+    pub popup_mode: PopupMode,
+    pub input_buffer: String,
+    pub break_now: bool,
+    pub cancelled: bool,
+    pub choosing: bool,
+    pub chosen: Vec<PathBuf>,
+    pub config: Config,
+    pub color_depth: ColorDepth,
+    pub theme: Theme,
+    pub plugins: Vec<Plugin>,
+    pub pending_task: Option<ExternalTask>,
+    pub status: Option<String>,
+    pub popup_index: usize,
+    pub confirm_action: Option<Action>,
+    pub confirm_message: String,
+    pub local_dir: PathBuf,
+    pub fs: Box<dyn Filesystem>,
+    pub devices: Vec<Device>,
+    pub drives: Vec<PathBuf>,
+    pub results: Option<Results>,
+    pub filter: String,
+    pub history: History,
+    pub history_cursor: Option<usize>,
+    pub marks: BTreeSet<PathBuf>,
+    pub mark_sizes: Vec<(PathBuf, Option<u64>)>,
+    pub visual_anchor: usize,
+    pub report: Vec<Outcome>,
+    pub report_title: String,
+    pub report_job: Option<usize>,
+    pub jobs: Jobs,
+    pub tags: Tags,
+    pub favorites: Favorites,
+    pub listing: ListingSettings,
+    pub show_hidden: Option<bool>,
+    pub show_ignored: bool,
+    pub long_listing: bool,
+    pub group_by_type: Option<bool>,
+    pub sort_override: Option<(SortKey, bool)>,
+    pub dir_counts: DirCounts,
+    pub watcher: DirWatcher,
+    pub stream: Option<DirStream>,
+    pub unfiltered: Vec<(String, bool)>,
+    pub entry_meta: HashMap<String, Meta>,
+    pub git_status: HashMap<String, String>,
+    pub repo: Option<git::Repo>,
+    pub properties: Option<Properties>,
+    pub diff: Option<Diff>,
+    pub comparison: Option<Comparison>,
+    pub sync_plan: Option<Plan>,
+    pub dry_run: bool,
+    pub show_preview: bool,
+    pub previewer: Previewer,
+    pub graphics: Option<Protocol>,
+    pub control: Option<ControlSocket>,
+    pub focused: Option<bool>,
+    pub toasts: Vec<String>,
+    pub alert_pending: bool,
+    pub list_offset: usize,
+    pub zen: bool,
+    pub count: usize,
+    pub pending_transfer: Option<Transfer>,
+}
+
+impl AppState {
+    pub fn new(location: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = Config::load()?;
+        let locale = i18n::init(&config.language);
+        let (fs, focus_dir) = vfs::open(location, &env::current_dir()?, &config)?;
+        let mut app_state = AppState::with_fs(config, fs, focus_dir, Stores::load())?;
+        if let Err(e) = locale { app_state.status = Some(e); }
+        Ok(app_state)
+    }
+
+    pub fn with_fs(config: Config, fs: Box<dyn Filesystem>, focus_dir: PathBuf, stores: Stores) -> Result<Self, Box<dyn std::error::Error>> {
+        let local_dir = env::current_dir()?;
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+        let color_depth = ColorDepth::detect();
+        let theme = Theme::by_name(&config.theme).unwrap_or_default().adapted(color_depth).plain(config.accessible);
+        let previewer = Previewer::new(config.preview_bytes, config.thumbnails);
+        let graphics = graphics::detect(&config.graphics);
+        let zen = config.panels.zen;
+        let mut app_state = AppState { focus_dir, entries: Vec::new(), dir_names: HashSet::new(), selected_index: 0, list_state, popup_mode: PopupMode::None, input_buffer: String::new(), break_now: false, cancelled: false, choosing: false, chosen: Vec::new(), config, color_depth, theme, plugins: stores.plugins, pending_task: None, status: None, popup_index: 0, confirm_action: None, confirm_message: String::new(), local_dir, fs, devices: Vec::new(), drives: Vec::new(), results: None, filter: String::new(), history: stores.history, history_cursor: None, marks: BTreeSet::new(), mark_sizes: Vec::new(), visual_anchor: 0, report: Vec::new(), report_title: String::new(), report_job: None, jobs: Jobs::default(), tags: stores.tags, favorites: stores.favorites, listing: ListingSettings::default(), show_hidden: None, show_ignored: false, long_listing: false, group_by_type: None, sort_override: None, dir_counts: DirCounts::default(), watcher: DirWatcher::default(), stream: None, unfiltered: Vec::new(), entry_meta: HashMap::new(), git_status: HashMap::new(), repo: None, properties: None, diff: None, comparison: None, sync_plan: None, dry_run: false, show_preview: false, previewer, graphics, control: None, focused: None, toasts: Vec::new(), alert_pending: false, list_offset: 0, zen, count: 0, pending_transfer: None };
+        app_state.refresh_entries()?;
+        Ok(app_state)
+    }
+
+    pub fn refresh_entries(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        (self.unfiltered, self.stream) = if self.fs.is_local() { listing::read_dir(&self.focus_dir)? } else { (self.fs.list(&self.focus_dir)?, None) };
+        self.previewer.invalidate();
+        self.listing = self.config.listing.for_dir(&*self.fs, &self.focus_dir).unwrap_or_else(|e| { self.status = Some(e); self.config.listing.clone() });
+        if self.long_listing { self.listing.columns = Column::LONG.to_vec(); }
+        if let Some(group) = self.group_by_type { self.listing.group = group; }
+        if let Some((sort, reverse)) = self.sort_override { (self.listing.sort, self.listing.reverse) = (sort, reverse); }
+        self.git_status = if self.fs.is_local() && self.listing.columns.contains(&Column::Git) { git::entry_status(&self.focus_dir) } else { HashMap::new() };
+        self.repo = if self.fs.is_local() { git::repo_state(&self.focus_dir) } else { None };
+        self.entry_meta.clear();
+        self.refilter();
+        Ok(())
+    }
+
+    pub fn refilter(&mut self) {
+        let mut listing = self.unfiltered.clone();
+        if !self.marks.is_empty() && self.stream.is_none() {
+            let present: HashSet<&str> = listing.iter().map(|(name, _)| name.as_str()).collect();
+            self.marks.retain(|mark| mark.parent() != Some(&self.focus_dir) || mark.file_name().is_some_and(|name| present.contains(&*name.to_string_lossy())));
+        }
+        if !self.show_hidden.unwrap_or(self.listing.hidden) {
+            let local = self.fs.is_local();
+            listing.retain(|(name, _)| !(name.starts_with('.') || local && vfs::has_hidden_attribute(&self.focus_dir.join(name))));
+        }
+        if !self.show_ignored && !self.config.ignore.is_empty() { listing.retain(|(name, _)| !self.config.ignore.iter().any(|pattern| glob::matches(pattern, name))); }
+        if self.listing.needs_meta() {
+            let (fs, dir) = (&self.fs, &self.focus_dir);
+            let missing: Vec<(String, Meta)> = listing.iter().filter(|(name, _)| !self.entry_meta.contains_key(name)).filter_map(|(name, _)| Some((name.clone(), fs.metadata(&dir.join(name))?))).collect();
+            self.entry_meta.extend(missing);
+        }
+        if !self.fs.keeps_order() { self.listing.sort(&mut listing, &self.entry_meta); }
+        let (wanted, pattern) = tags::split_query(&self.filter);
+        match Matcher::new(&pattern, self.config.search) {
+            Ok(matcher) => {
+                listing.retain(|(name, _)| matcher.is_match(name) && wanted.iter().all(|tag| self.tags.has(&self.focus_dir.join(name), tag)));
+                if self.config.search.fuzzy && !self.config.search.regex { listing.sort_by_cached_key(|(name, _)| matcher.spread(name)); }
+            }
+            Err(e) => self.status = Some(trf("Invalid filter: {}", &[&e])),
+        }
+        self.dir_names = listing.iter().filter(|(_, is_dir)| *is_dir).map(|(name, _)| name.clone()).collect();
+        self.entries = listing.into_iter().map(|(name, _)| name).collect();
+        if self.listing.dir_counts && self.fs.is_local() && self.stream.is_none() { self.dir_counts.request(self.dir_names.iter().map(|name| self.focus_dir.join(name))); }
+        self.clamp_selection();
+    }
+
+    fn poll_stream(&mut self) -> bool {
+        let Some(stream) = &mut self.stream else { return false };
+        let (entries, done) = stream.poll();
+        if entries.is_empty() && !done { return false; }
+        self.unfiltered.extend(entries);
+        if done { self.stream = None; }
+        let selected = self.entries.get(self.selected_index).cloned();
+        self.refilter();
+        self.reselect(selected);
+        true
+    }
+
+    fn reselect(&mut self, name: Option<String>) {
+        let Some(index) = name.and_then(|name| self.entries.iter().position(|entry| *entry == name)) else { return };
+        self.selected_index = index;
+        self.clamp_selection();
+    }
+
+    pub fn poll_background(&mut self) -> bool {
+        if self.show_preview && self.fs.is_local() && let Some(path) = self.get_selected_path() { self.previewer.request(&path); }
+        self.poll_stream() | self.dir_counts.poll() | self.previewer.poll() | self.previewer.load_more() | self.poll_jobs() | self.poll_control() | self.poll_watcher()
+    }
+
+    fn poll_watcher(&mut self) -> bool {
+        let local = self.fs.is_local();
+        if !self.watcher.poll(local.then_some(self.focus_dir.as_path())) || self.stream.is_some() { return false; }
+        let selected = self.entries.get(self.selected_index).cloned();
+        if let Err(e) = self.refresh_entries() { self.status = Some(e.to_string()); }
+        self.reselect(selected);
+        true
+    }
+
+    fn poll_control(&mut self) -> bool {
+        let Some(control) = &self.control else { return false };
+        let requests = control.poll();
+        if requests.is_empty() { return false; }
+        for request in requests {
+            let result = Action::parse(&request.line).map_err(Into::into).and_then(|action| self.request(action));
+            request.reply(result.map_err(|e| e.to_string()));
+        }
+        true
+    }
+
+    pub fn yank(&mut self, part: char) {
+        let paths: Vec<PathBuf> = if self.marks.is_empty() { self.get_selected_path().into_iter().collect() } else { self.marks.iter().cloned().collect() };
+        if paths.is_empty() { return; }
+        let pieces: Vec<String> = paths.iter().map(|path| match part {
+            'n' => path.file_name().map_or_else(|| path.to_string_lossy().into_owned(), |name| name.to_string_lossy().into_owned()),
+            'd' => self.fs.location(path.parent().unwrap_or(path)),
+            _ => self.fs.location(path),
+        }).collect();
+        let what = if pieces.len() == 1 { pieces[0].clone() } else { trf("{} entries", &[&pieces.len()]) };
+        self.pending_task = Some(ExternalTask::Copy(pieces.join("\n"), what));
+    }
+
+    pub fn yank_contents(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("contents can only be copied from local files").into()); }
+        let Some(path) = self.get_selected_path() else { return Ok(()) };
+        let meta = fs::metadata(&path)?;
+        if meta.is_dir() { return Err(trf("{} is a directory", &[&path.display()]).into()); }
+        if meta.len() > COPY_LIMIT { return Err(trf("{} is {}, over the {} limit for copying contents", &[&path.display(), &human_size(meta.len()), &human_size(COPY_LIMIT)]).into()); }
+        let data = fs::read(&path)?;
+        let text = String::from_utf8(data).ok().filter(|text| !text.contains('\0')).ok_or_else(|| trf("{} is a binary file; only text can be copied", &[&path.display()]))?;
+        let what = trf("the contents of {} ({})", &[&path.file_name().unwrap_or_default().to_string_lossy(), &human_size(meta.len())]);
+        self.pending_task = Some(ExternalTask::Copy(text, what));
+        Ok(())
+    }
+
+    pub fn save_pasted(&mut self, name: &str, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if text.is_empty() { return Err(tr("the clipboard is empty").into()); }
+        let path = self.focus_dir.join(name);
+        if self.fs.stat(&path)?.is_some() { return Err(trf("{} already exists", &[&name]).into()); }
+        self.fs.write(&path, text.as_bytes())?;
+        self.refresh_entries()?;
+        self.apply(Action::Select(name.to_string()))?;
+        self.status = Some(trf("Saved {} from the clipboard to {}", &[&human_size(text.len() as u64), &name]));
+        Ok(())
+    }
+
+    pub fn toggle_help(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.config.panels.help = !self.config.panels.help;
+        self.config.persist("layout", "help_panel", &Value::Bool(self.config.panels.help))
+    }
+
+    pub fn toggle_zen(&mut self) {
+        self.zen = !self.zen;
+        self.status = Some(tr(if self.zen { "Zen mode on (Z to leave)" } else { "Zen mode off" }).to_string());
+    }
+
+    pub fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+        self.status = Some(tr(if self.show_preview { "Preview on" } else { "Preview off" }).to_string());
+    }
+
+    pub fn open_pager(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("the pager only works on local files").into()); }
+        let Some(path) = self.get_selected_path() else { return Ok(()) };
+        if path.is_dir() { return Err(trf("{} is a directory", &[&path.display()]).into()); }
+        self.pending_task = Some(ExternalTask::Pager(path));
+        Ok(())
+    }
+
+    pub fn toggle_hidden(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let show = !self.show_hidden.unwrap_or(self.listing.hidden);
+        self.show_hidden = Some(show);
+        self.refresh_entries()?;
+        self.status = Some(tr(if show { "Showing hidden files" } else { "Hiding hidden files" }).to_string());
+        Ok(())
+    }
+
+    pub fn toggle_groups(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let group = !self.listing.group;
+        self.group_by_type = Some(group);
+        let selected = self.entries.get(self.selected_index).cloned();
+        self.refresh_entries()?;
+        self.reselect(selected);
+        self.status = Some(tr(if group { "Grouping entries by type" } else { "Not grouping entries" }).to_string());
+        Ok(())
+    }
+
+    pub fn cycle_sort(&mut self, reverse: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let (sort, reverse) = if reverse { (self.listing.sort, !self.listing.reverse) } else { (self.listing.sort.next(), self.listing.reverse) };
+        self.sort_override = Some((sort, reverse));
+        let selected = self.entries.get(self.selected_index).cloned();
+        self.refresh_entries()?;
+        self.reselect(selected);
+        self.status = Some(trf("Sorting by {}, {}", &[&sort.label(), &tr(sort.direction(reverse))]));
+        Ok(())
+    }
+
+    pub fn toggle_long_listing(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.long_listing = !self.long_listing;
+        self.refresh_entries()
+    }
+
+    pub fn toggle_ignored(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.config.ignore.is_empty() { return Err(tr("no ignore patterns configured (set ignore under [listing])").into()); }
+        self.show_ignored = !self.show_ignored;
+        self.refresh_entries()?;
+        self.status = Some(tr(if self.show_ignored { "Showing ignored entries" } else { "Hiding ignored entries" }).to_string());
+        Ok(())
+    }
+
+    pub fn scan_limits(&self) -> ScanLimits {
+        let mut limits = self.config.scan.clone();
+        if !self.show_ignored { limits.ignore.extend(self.config.ignore.iter().cloned()); }
+        limits
+    }
+
+    pub fn cycle_theme(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let next = self.theme.next_name();
+        self.theme = Theme::by_name(next).unwrap_or_default().adapted(self.color_depth).plain(self.config.accessible);
+        self.config.theme = next.to_string();
+        self.config.persist("ui", "theme", &Value::Str(next.to_string()))
+    }
+
+    pub fn apply(&mut self, action: Action) -> Result<(), Box<dyn std::error::Error>> {
+        match action {
+            Action::Cd(url) if url.contains("://") => self.open(&url)?,
+            Action::Cd(path) => {
+                self.focus_dir = self.fs.resolve(&self.focus_dir, &path)?;
+                self.selected_index = 0;
+            }
+            Action::Select(name) => {
+                self.refresh_entries()?;
+                self.selected_index = self.entries.iter().position(|e| *e == name).ok_or_else(|| trf("no such entry: {}", &[&name]))?;
+            }
+            Action::Reveal(path) => self.reveal(&self.focus_dir.join(vfs::expand_tilde(&path)))?,
+            Action::Refresh => {}
+            Action::Mkdir(name) => {
+                let dir_path = self.focus_dir.join(&name);
+                if self.fs.stat(&dir_path)?.is_none() { self.fs.mkdir(&dir_path)?; }
+            }
+            Action::Touch(name) => {
+                let file_path = self.focus_dir.join(&name);
+                if self.fs.stat(&file_path)?.is_none() { self.fs.write(&file_path, b"")?; }
+            }
+            Action::Rename(from, to) => {
+                let (old_path, new_path) = (self.focus_dir.join(&from), self.focus_dir.join(&to));
+                if old_path != new_path && self.fs.stat(&new_path)?.is_none() {
+                    self.fs.rename(&old_path, &new_path)?;
+                    self.retag(&old_path, Some(&new_path));
+                }
+            }
+            Action::Replace(from, to) => {
+                let (old_path, new_path) = (self.focus_dir.join(&from), self.focus_dir.join(&to));
+                if old_path != new_path {
+                    if self.fs.stat(&new_path)?.is_some() { self.fs.remove(&new_path)?; self.retag(&new_path, None); }
+                    self.fs.rename(&old_path, &new_path)?;
+                    self.retag(&old_path, Some(&new_path));
+                }
+            }
+            Action::Delete(name) if self.deletes_to_trash() => {
+                trash::trash(&self.focus_dir.join(&name))?;
+                self.retag(&self.focus_dir.join(&name), None);
+                self.status = Some(trf("Moved {} to the {}", &[&name, &trash::NAME]));
+            }
+            Action::Delete(name) => {
+                self.fs.remove(&self.focus_dir.join(&name))?;
+                self.retag(&self.focus_dir.join(&name), None);
+            }
+            Action::Shred(name) => {
+                if !self.config.shred { return Err(tr("secure wipe is disabled (set shred = true under [delete])").into()); }
+                if !self.fs.is_local() { return Err(tr("secure wipe only works on local files").into()); }
+                wipe::shred(&self.focus_dir.join(&name), self.config.shred_passes)?;
+                self.retag(&self.focus_dir.join(&name), None);
+            }
+            Action::Download(name) => {
+                if self.fs.is_local() { return Err(tr("not connected to a remote host").into()); }
+                self.transfer_in_background(trf("Download {}", &[&name]), false, self.focus_dir.join(&name), self.local_dir.clone())?;
+                self.status = Some(trf("Downloading {} to {} in the background (J shows jobs)", &[&name, &self.local_dir.display()]));
+            }
+            Action::Upload(path) => {
+                if self.fs.is_local() { return Err(tr("not connected to a remote host").into()); }
+                let local = self.local_dir.join(vfs::expand_tilde(&path));
+                if fs::symlink_metadata(&local).is_err() { return Err(trf("no such file or directory: {}", &[&local.display()]).into()); }
+                self.transfer_in_background(trf("Upload {}", &[&local.display()]), true, local.clone(), self.focus_dir.clone())?;
+                self.status = Some(trf("Uploading {} in the background (J shows jobs)", &[&local.display()]));
+            }
+            Action::Tag(name, tags) => {
+                if !self.fs.is_local() { return Err(tr("tags only work on local files").into()); }
+                let path = self.focus_dir.join(&name);
+                if self.fs.stat(&path)?.is_none() { return Err(trf("no such entry: {}", &[&name]).into()); }
+                self.tags.set(&path, tags)?;
+            }
+            Action::Message(text) => self.status = Some(text),
+        }
+        self.refresh_entries()
+    }
+
+    fn transfer_in_background(&mut self, title: String, upload: bool, path: PathBuf, target: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        let remote = self.fs.share().ok_or_else(|| tr("transfers are not supported here"))?;
+        self.jobs.push(title, Work::Transfer { remote, upload, paths: vec![path], target });
+        Ok(())
+    }
+
+    pub fn open(&mut self, location: &str) -> Result<(), Box<dyn std::error::Error>> {
+        (self.fs, self.focus_dir) = vfs::open(location, &self.local_dir, &self.config)?;
+        self.selected_index = 0;
+        self.marks.clear();
+        Ok(())
+    }
+
+    pub fn pick_lines(&mut self, lines: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        (self.fs, self.focus_dir) = (Box::new(LineList { lines }), PathBuf::new());
+        (self.show_hidden, self.show_ignored, self.choosing) = (Some(true), true, true);
+        self.config.search.fuzzy = true;
+        self.popup_mode = PopupMode::Filter;
+        self.selected_index = 0;
+        self.marks.clear();
+        self.refresh_entries()
+    }
+
+    pub fn find(&mut self, pattern: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("find only works on local directories").into()); }
+        let hits = search::find(&self.focus_dir, pattern, self.config.search, &self.scan_limits())?;
+        self.show_results(hits, pattern);
+        Ok(())
+    }
+
+    pub fn grep(&mut self, pattern: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("content search only works on local directories").into()); }
+        let hits = search::grep(&self.focus_dir, pattern, self.config.search, &self.scan_limits())?;
+        self.show_results(hits, pattern);
+        Ok(())
+    }
+
+    fn show_results(&mut self, hits: Vec<Hit>, pattern: &str) {
+        let capped = if hits.len() >= search::MAX_HITS { "+" } else { "" };
+        self.status = Some(trf(if hits.len() == 1 { "{}{} match for '{}'" } else { "{}{} matches for '{}'" }, &[&hits.len(), &capped, &pattern]));
+        let origin = (self.focus_dir.clone(), self.entries.get(self.selected_index).cloned());
+        self.results = Some(Results { query: pattern.to_string(), options: self.config.search, root: self.focus_dir.clone(), hits, index: 0, origin, visible: true });
+        self.popup_mode = PopupMode::Results;
+    }
+
+    fn history_kind(&self) -> Option<&'static str> {
+        match self.popup_mode {
+            PopupMode::Filter => Some("filter"),
+            PopupMode::Find => Some("find"),
+            PopupMode::Grep => Some("grep"),
+            PopupMode::ForEach => Some("foreach"),
+            PopupMode::Compare => Some("compare"),
+            PopupMode::Tree => Some("tree"),
+            _ => None,
+        }
+    }
+
+    pub fn remember(&mut self) {
+        self.history_cursor = None;
+        let Some(kind) = self.history_kind() else { return };
+        let entry = if kind == "filter" { self.filter.clone() } else { self.input_buffer.clone() };
+        if let Err(e) = self.history.push(kind, &entry) { self.status = Some(trf("Could not save history: {}", &[&e])); }
+    }
+
+    pub fn recall(&mut self, older: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(kind) = self.history_kind() else { return Ok(()) };
+        let entries = self.history.entries(kind);
+        let cursor = match (self.history_cursor, older) {
+            (None, true) if !entries.is_empty() => Some(entries.len() - 1),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < entries.len() => Some(i + 1),
+            _ => None,
+        };
+        let text = cursor.map(|i| entries[i].to_string()).unwrap_or_default();
+        self.history_cursor = cursor;
+        if kind == "filter" {
+            self.filter = text;
+            self.selected_index = 0;
+            self.refresh_entries()
+        } else {
+            self.input_buffer = text;
+            Ok(())
+        }
+    }
+
+    pub fn jump_to_hit(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = self.results.as_ref().and_then(Results::current) else { return Ok(()) };
+        self.reveal(&path)
+    }
+
+    pub fn jump_back(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some((dir, selected)) = self.results.as_ref().map(|r| r.origin.clone()) else { return Err(tr("no search to return from").into()) };
+        self.focus_dir = dir;
+        self.selected_index = 0;
+        match selected {
+            Some(name) => self.apply(Action::Select(name)).or_else(|_| self.refresh_entries()),
+            None => self.refresh_entries(),
+        }
+    }
+
+    pub fn reveal(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(stat) = self.fs.stat(path)? else { return Err(trf("no such file or directory: {}", &[&path.display()]).into()) };
+        if stat.is_dir {
+            self.focus_dir = path.to_path_buf();
+            self.selected_index = 0;
+            return self.refresh_entries();
+        }
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { return Err(trf("cannot reveal {}", &[&path.display()]).into()) };
+        self.focus_dir = parent.to_path_buf();
+        self.apply(Action::Select(name.to_string_lossy().into_owned()))
+    }
+
+    pub fn is_marked(&self, entry: &str) -> bool {
+        !self.marks.is_empty() && self.marks.contains(&self.focus_dir.join(entry))
+    }
+
+    pub fn toggle_mark(&mut self) {
+        let Some(path) = self.get_selected_path() else { return };
+        if !self.marks.remove(&path) { self.marks.insert(path); }
+        if self.selected_index + 1 < self.entries.len() { self.selected_index += 1; }
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    pub fn select(&mut self, selection: Selection) {
+        for entry in &self.entries {
+            let path = self.focus_dir.join(entry);
+            let mark = match selection { Selection::All => true, Selection::None => false, Selection::Invert => !self.marks.contains(&path) };
+            if mark { self.marks.insert(path); } else { self.marks.remove(&path); }
+        }
+        let scope = if self.filter.is_empty() { "" } else { " matching the filter" };
+        self.status = Some(match selection {
+            Selection::All => trf("Marked all {} entries{}", &[&self.entries.len(), &scope]),
+            Selection::None => trf("Unmarked all entries{}", &[&scope]),
+            Selection::Invert => trf("Inverted marks on {} entries{}", &[&self.entries.len(), &scope]),
+        });
+    }
+
+    pub fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        (self.popup_mode == PopupMode::Visual).then(|| self.visual_anchor.min(self.selected_index)..=self.visual_anchor.max(self.selected_index))
+    }
+
+    pub fn commit_visual(&mut self) {
+        let Some(range) = self.visual_range() else { return };
+        let entries = self.entries.get(range).unwrap_or_default();
+        self.marks.extend(entries.iter().map(|entry| self.focus_dir.join(entry)));
+        self.status = Some(trf("Marked {} entries", &[&entries.len()]));
+        self.popup_mode = PopupMode::None;
+    }
+
+    pub fn stage(&mut self, stage: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() || self.repo.is_none() { return Err(tr("staging only works inside a local git repository").into()); }
+        let paths: Vec<PathBuf> = if self.marks.is_empty() { self.get_selected_path().into_iter().collect() } else { self.marks.iter().cloned().collect() };
+        if paths.is_empty() { return Ok(()); }
+        git::stage(&self.focus_dir, &paths, stage)?;
+        let what = if paths.len() == 1 { paths[0].display().to_string() } else { trf("{} entries", &[&paths.len()]) };
+        self.status = Some(trf(if stage { "Staged {}" } else { "Unstaged {}" }, &[&what]));
+        self.refresh_entries()
+    }
+
+    pub fn for_each(&mut self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("for-each commands only work on local directories").into()); }
+        let (jobs, template) = foreach::parse_jobs(input, self.config.foreach_jobs);
+        let paths: Vec<PathBuf> = self.marks.iter().cloned().collect();
+        let count = paths.len();
+        self.jobs.push(trf("For each: {}", &[&template]), Work::ForEach { template: template.to_string(), dir: self.focus_dir.clone(), paths, parallel: jobs });
+        self.status = Some(trf("Running on {} files in the background (J shows jobs)", &[&count]));
+        self.popup_mode = PopupMode::None;
+        Ok(())
+    }
+
+    pub fn poll_jobs(&mut self) -> bool {
+        let (changed, finished) = self.jobs.poll();
+        for id in finished {
+            let Some(job) = self.jobs.get(id) else { continue };
+            let (title, work, failed, done, status, elapsed) = (job.title.clone(), job.work.clone(), job.failed(), job.done(), job.status, job.elapsed);
+            let summary = trf("{} {}: {} ok, {} failed", &[&title, &tr(status.label()), &(done - failed), &failed]);
+            if status == Status::Finished { self.announce_job(&summary, elapsed); self.alert_pending = true; }
+            self.status = Some(summary);
+            if let Work::Sync(plan) = &*work && let Err(e) = self.recompare(plan) { self.status = Some(e.to_string()); }
+            if self.popup_mode == PopupMode::None { self.view_job(id); }
+            if let Err(e) = self.refresh_entries() { self.status = Some(e.to_string()); }
+        }
+        if changed && self.popup_mode == PopupMode::Report && let Some(job) = self.report_job.and_then(|id| self.jobs.get(id)) { self.report = job.report(); }
+        changed
+    }
+
+    fn announce_job(&mut self, summary: &str, elapsed: Duration) {
+        if elapsed < self.config.notify_after || self.focused == Some(true) { return; }
+        if self.focused == Some(false) { self.toasts.push(summary.to_string()); }
+        if self.config.notify_desktop && let Err(e) = notify::desktop("QuickFind", summary) { self.toasts.push(trf("Notification failed: {}", &[&e])); }
+    }
+
+    pub fn focus_changed(&mut self, focused: bool) {
+        self.focused = Some(focused);
+        if focused && !self.toasts.is_empty() { self.status = Some(trf("While you were away: {}", &[&self.toasts.drain(..).collect::<Vec<_>>().join("; ")])); }
+    }
+
+    pub fn view_job(&mut self, id: usize) {
+        let Some(job) = self.jobs.get(id) else { return };
+        (self.report, self.report_title, self.report_job) = (job.report(), job.title.clone(), Some(id));
+        self.popup_index = 0;
+        self.popup_mode = PopupMode::Report;
+    }
+
+    pub fn toggle_star(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("favorites only work on local files").into()); }
+        let Some(path) = self.get_selected_path() else { return Ok(()) };
+        let starred = self.favorites.toggle(&path)?;
+        self.status = Some(trf(if starred { "Starred {}" } else { "Unstarred {}" }, &[&path.display()]));
+        Ok(())
+    }
+
+    pub fn show_diff(&mut self, staged: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() || self.repo.is_none() { return Err(tr("diffs only work inside a local git repository").into()); }
+        let Some(path) = self.get_selected_path() else { return Ok(()) };
+        let lines = git::diff(&self.focus_dir, &path, staged)?;
+        self.diff = Some(Diff { path, staged, lines, scroll: 0 });
+        self.popup_mode = PopupMode::Diff;
+        Ok(())
+    }
+
+    pub fn export_tree(&mut self, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("tree export only works on local directories").into()); }
+        let mut limits = self.scan_limits();
+        let Some(file) = tree::parse_args(args, &mut limits)? else { return Err(tr("name the file to write the tree to").into()) };
+        let path = self.focus_dir.join(vfs::expand_tilde(&file));
+        if path.exists() { return Err(trf("{} already exists", &[&path.display()]).into()); }
+        let mut text = Vec::new();
+        let counts = tree::write(&self.focus_dir, &limits, &mut text)?;
+        File::create_new(&path).and_then(|mut file| file.write_all(&text)).map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.status = Some(trf("Wrote a tree of {} to {}", &[&counts.summary(), &path.display()]));
+        self.refresh_entries()
+    }
+
+    pub fn compare(&mut self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.fs.is_local() { return Err(tr("comparing only works on local directories").into()); }
+        let other = self.focus_dir.join(vfs::expand_tilde(input.trim()));
+        if !other.is_dir() { return Err(trf("not a directory: {}", &[&other.display()]).into()); }
+        let other = vfs::canonicalize(&other)?;
+        if other == vfs::canonicalize(&self.focus_dir)? { return Err(tr("that is the current directory").into()); }
+        let differences = compare::compare(&self.focus_dir, &other, &self.scan_limits())?;
+        let here: Vec<PathBuf> = differences.iter().filter(|(name, d)| *d != Difference::OnlyThere && self.entries.contains(name)).map(|(name, _)| self.focus_dir.join(name)).collect();
+        self.status = Some(if differences.is_empty() { trf("No differences with {}", &[&other.display()]) } else { trf("{} differences with {}, marked {} here", &[&differences.len(), &other.display(), &here.len()]) });
+        self.marks.extend(here);
+        self.popup_index = 0;
+        self.popup_mode = if differences.is_empty() { PopupMode::None } else { PopupMode::Comparison };
+        self.comparison = Some(Comparison { here: self.focus_dir.clone(), other, differences });
+        Ok(())
+    }
+
+    pub fn plan_sync(&mut self, delete: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(comparison) = &self.comparison else { return Err(tr("compare with another directory first (C)").into()) };
+        let plan = sync::plan(&comparison.here, &comparison.other, delete, &self.scan_limits())?;
+        self.sync_plan = Some(plan);
+        self.popup_index = 0;
+        self.popup_mode = PopupMode::Sync;
+        Ok(())
+    }
+
+    pub fn run_sync(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(plan) = self.sync_plan.take() else { return Ok(()) };
+        if self.dry_run {
+            let report = plan.steps.iter().map(|step| Outcome { path: step.path.clone(), code: Ok(Some(0)), output: trf("would {} {}", &[&step.operation.label(), &plan.target.join(&step.path).display()]) }).collect();
+            self.show_dry_run(&trf("sync to {}", &[&plan.target.display()]), report);
+            return Ok(());
+        }
+        let (title, count) = (trf("Sync to {}", &[&plan.target.display()]), plan.steps.len());
+        self.jobs.push(title, Work::Sync(plan));
+        self.status = Some(trf("Syncing {} items in the background (J shows jobs)", &[&count]));
+        self.popup_mode = PopupMode::None;
+        Ok(())
+    }
+
+    fn recompare(&mut self, plan: &Plan) -> Result<(), Box<dyn std::error::Error>> {
+        let differences = compare::compare(&plan.source, &plan.target, &self.scan_limits())?;
+        self.comparison = Some(Comparison { here: plan.source.clone(), other: plan.target.clone(), differences });
+        Ok(())
+    }
+
+    pub fn retry_failed(&mut self, id: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+        if self.dry_run { return Err(tr("dry run is on (W turns it off)").into()); }
+        let Some(id) = id else { return Err(tr("this report cannot be retried").into()) };
+        let count = self.jobs.retry(id)?;
+        self.status = Some(trf("Retrying {} items in the background", &[&count]));
+        Ok(())
+    }
+
+    pub fn show_properties(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = self.get_selected_path() else { return Ok(()) };
+        let is_dir = self.fs.stat(&path)?.is_some_and(|stat| stat.is_dir);
+        let link_target = if self.fs.is_local() { fs::read_link(&path).ok() } else { None };
+        self.properties = Some(Properties { meta: self.fs.metadata(&path), path, is_dir, link_target, streams: Vec::new(), xattrs: Vec::new(), preview: None, delete_armed: false });
+        self.popup_index = 0;
+        self.popup_mode = PopupMode::Properties;
+        self.reload_properties();
+        Ok(())
+    }
+
+    fn reload_properties(&mut self) {
+        let local = self.fs.is_local();
+        let Some(properties) = self.properties.as_mut() else { return };
+        if !local { return; }
+        properties.streams = streams::list(&properties.path).unwrap_or_else(|e| { self.status = Some(trf("Could not list streams: {}", &[&e])); Vec::new() });
+        properties.xattrs = xattr::list(&properties.path).unwrap_or_else(|e| { self.status = Some(trf("Could not list extended attributes: {}", &[&e])); Vec::new() });
+        (properties.preview, properties.delete_armed) = (None, false);
+        self.popup_index = self.popup_index.min((properties.streams.len() + properties.xattrs.len()).saturating_sub(1));
+    }
+
+    pub fn view_stream(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(properties) = self.properties.as_mut() else { return Ok(()) };
+        let Some((name, _)) = properties.streams.get(self.popup_index) else { return Ok(()) };
+        let mut data = streams::read(&properties.path, name)?;
+        data.truncate(PREVIEW_BYTES);
+        properties.preview = Some(String::from_utf8_lossy(&data).into_owned());
+        Ok(())
+    }
+
+    pub fn set_xattr(&mut self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.popup_mode = PopupMode::Properties;
+        let Some(properties) = self.properties.as_ref() else { return Ok(()) };
+        let (name, value) = input.split_once('=').unwrap_or((input, ""));
+        if name.trim().is_empty() { return Err(tr("expected name=value").into()); }
+        xattr::set(&properties.path, name.trim(), value)?;
+        self.status = Some(trf("Set attribute '{}'", &[&name.trim()]));
+        self.reload_properties();
+        Ok(())
+    }
+
+    pub fn delete_property_item(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(properties) = self.properties.as_mut() else { return Ok(()) };
+        let stream = properties.streams.get(self.popup_index).map(|(name, _)| name.clone());
+        let Some(name) = stream.clone().or_else(|| properties.xattrs.get(self.popup_index - properties.streams.len()).map(|(name, _)| name.clone())) else { return Ok(()) };
+        let kind = tr(if stream.is_some() { "stream" } else { "attribute" });
+        if !properties.delete_armed {
+            properties.delete_armed = true;
+            self.status = Some(trf("Press d again to delete the {} '{}'", &[&kind, &name]));
+            return Ok(());
+        }
+        if stream.is_some() { streams::remove(&properties.path, &name)?; } else { xattr::remove(&properties.path, &name)?; }
+        self.status = Some(trf("Deleted {} '{}'", &[&kind, &name]));
+        self.reload_properties();
+        Ok(())
+    }
+
+    pub fn inspect_marks(&mut self) {
+        let (local, limits) = (self.fs.is_local(), self.scan_limits());
+        self.mark_sizes = self.marks.iter().map(|mark| (mark.clone(), if local { finder::disk_usage(mark, 200_000, &limits) } else { None })).collect();
+        self.popup_index = 0;
+        self.popup_mode = PopupMode::Marks;
+    }
+
+    pub fn unmark_inspected(&mut self) {
+        if self.popup_index >= self.mark_sizes.len() { return; }
+        let (path, _) = self.mark_sizes.remove(self.popup_index);
+        self.marks.remove(&path);
+        self.popup_index = self.popup_index.min(self.mark_sizes.len().saturating_sub(1));
+    }
+
+    pub fn toggle_dry_run(&mut self) {
+        self.dry_run = !self.dry_run;
+        self.status = Some(tr(if self.dry_run { "Dry run on: paste, group, delete and sync only report what they would do" } else { "Dry run off" }).to_string());
+    }
+
+    pub fn chmod(&mut self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !perms::SUPPORTED || !self.fs.is_local() { return Err(tr("permission modes can only be changed on local Unix files").into()); }
+        let spec = ModeSpec::parse(input)?;
+        let paths: Vec<PathBuf> = if self.marks.is_empty() { self.properties.as_ref().map(|p| p.path.clone()).into_iter().collect() } else { self.marks.iter().cloned().collect() };
+        if self.dry_run {
+            let report = paths.into_iter().map(|path| Outcome { path, code: Ok(Some(0)), output: trf("would set {}", &[&spec.label()]) }).collect();
+            self.show_dry_run(&format!("chmod {}", spec.label()), report);
+            return Ok(());
+        }
+        let report = perms::apply(&spec, &paths);
+        let failed = report.iter().filter(|o| !o.success()).count();
+        self.refresh_entries()?;
+        self.status = Some(trf("chmod {}: {} ok, {} failed", &[&spec.label(), &(report.len() - failed), &failed]));
+        (self.report, self.report_title, self.report_job, self.popup_index, self.popup_mode) = (report, format!("chmod {}", spec.label()), None, 0, PopupMode::Report);
+        self.properties = None;
+        Ok(())
+    }
+
+    fn show_dry_run(&mut self, title: &str, report: Vec<Outcome>) {
+        self.status = Some(trf("Dry run: nothing was changed ({} planned)", &[&report.len()]));
+        self.report = report;
+        self.report_title = trf("Dry run: {}", &[&title]);
+        self.report_job = None;
+        self.popup_index = 0;
+        self.popup_mode = PopupMode::Report;
+    }
+
+    pub fn paste_marks(&mut self, transfer: Transfer) -> Result<(), Box<dyn std::error::Error>> {
+        if self.marks.is_empty() { return Err(tr("nothing is marked (Space marks the selected entry)").into()); }
+        if transfer == Transfer::Link && !self.fs.is_local() { return Err(tr("symlinks can only be created on local directories").into()); }
+        let (mut clashes, mut folders) = (0, 0);
+        for source in &self.marks {
+            let Some(name) = source.file_name() else { continue };
+            let target = self.focus_dir.join(name);
+            if transfer == Transfer::Move && target == *source { continue; }
+            let existing = if target == *source { self.fs.stat(source)? } else { self.fs.stat(&target)? };
+            let Some(existing) = existing else { continue };
+            clashes += 1;
+            if transfer == Transfer::Move && existing.is_dir && self.mergeable(source, &target)? { folders += 1; }
+        }
+        if clashes == 0 { return self.paste(transfer, Clash::Skip); }
+        (self.pending_transfer, self.popup_mode) = (Some(transfer), PopupMode::Conflict);
+        self.status = Some(if folders > 0 { trf("{} marked entries already exist here, {} of them folders: s skip | b keep both | g merge folders", &[&clashes, &folders]) } else { trf("{} marked entries already exist here: s skip | b keep both", &[&clashes]) });
+        Ok(())
+    }
+
+    pub fn resolve_conflict(&mut self, clash: Clash) -> Result<(), Box<dyn std::error::Error>> {
+        self.popup_mode = PopupMode::None;
+        let Some(transfer) = self.pending_transfer.take() else { return Ok(()) };
+        if clash == Clash::Merge && transfer != Transfer::Move { return Err(tr("only moved folders can be merged").into()); }
+        self.paste(transfer, clash)
+    }
+
+    fn paste_target(&self, transfer: Transfer, source: &Path, clash: Clash) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        let Some(name) = source.file_name() else { return Ok(None) };
+        let target = self.focus_dir.join(name);
+        if target != *source && self.fs.stat(&target)?.is_none() { return Ok(Some(target)); }
+        if clash == Clash::Skip || (transfer == Transfer::Move && target == *source) { return Ok(None); }
+        if clash == Clash::Merge && self.mergeable(source, &target)? { return Ok(Some(target)); }
+        self.free_name(&self.focus_dir, &name.to_string_lossy(), self.fs.stat(source)?.is_some_and(|stat| stat.is_dir)).map(Some)
+    }
+
+    fn mergeable(&self, source: &Path, target: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        let folder = |path: &Path| self.fs.stat(path).map(|stat| stat.is_some_and(|stat| stat.is_dir && !stat.is_symlink));
+        Ok(folder(source)? && folder(target)?)
+    }
+
+    fn free_name(&self, dir: &Path, name: &str, is_dir: bool) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        for n in 1.. {
+            let target = dir.join(vfs::numbered_name(name, is_dir, n));
+            if self.fs.stat(&target)?.is_none() { return Ok(target); }
+        }
+        unreachable!()
+    }
+
+    fn paste(&mut self, transfer: Transfer, clash: Clash) -> Result<(), Box<dyn std::error::Error>> {
+        if self.dry_run {
+            let mut report = Vec::new();
+            for source in &self.marks {
+                let target = self.paste_target(transfer, source, clash)?;
+                let merging = match &target { Some(target) => self.fs.stat(target)?.is_some(), None => false };
+                let output = match target {
+                    Some(target) if merging => trf("would merge into {}, keeping both of any clashing files", &[&target.display()]),
+                    Some(target) => trf("would {} to {}", &[&transfer.verb(), &target.display()]),
+                    None => tr("would skip, already exists").to_string(),
+                };
+                report.push(Outcome { path: source.clone(), code: Ok(Some(0)), output });
+            }
+            self.show_dry_run(&trf("{} here", &[&transfer.verb()]), report);
+            return Ok(());
+        }
+        let (mut done, mut skipped, mut failure, mut merges) = (0, 0, None, Vec::new());
+        for source in self.marks.clone() {
+            let Some(target) = self.paste_target(transfer, &source, clash)? else { skipped += 1; continue };
+            let result = if clash == Clash::Merge && target != source && self.mergeable(&source, &target)? { self.merge(&source, &target, &mut merges).map(|_| { self.marks.remove(&source); }) } else { self.transfer(transfer, &source, &target) };
+            match result {
+                Ok(()) => done += 1,
+                Err(e) => { failure.get_or_insert(format!("{}: {}", source.display(), e)); }
+            }
+        }
+        let mut status = trf(match transfer { Transfer::Copy => "Copied {} here", Transfer::Move => "Moved {} here", Transfer::Link => "Linked {} here" }, &[&done]);
+        if skipped > 0 { status.push_str(&trf(", skipped {} that already exist", &[&skipped])); }
+        if let Some(failure) = failure { status.push_str(&trf(", failed {}", &[&failure])); }
+        if !merges.is_empty() {
+            let failed = merges.iter().filter(|o| !o.success()).count();
+            status.push_str(&trf("; merging kept both of {} clashing files, {} failed", &[&(merges.len() - failed), &failed]));
+            (self.report, self.report_title, self.report_job, self.popup_index, self.popup_mode) = (merges, tr("Merge conflicts").to_string(), None, 0, PopupMode::Report);
+        }
+        self.status = Some(status);
+        self.refresh_entries()
+    }
+
+    fn merge(&mut self, source: &Path, target: &Path, report: &mut Vec<Outcome>) -> Result<(), Box<dyn std::error::Error>> {
+        if target.starts_with(source) { return Err(trf("cannot merge {} into itself", &[&source.display()]).into()); }
+        for (name, is_dir) in self.fs.list(source)? {
+            let (from, to) = (source.join(&name), target.join(&name));
+            let result = match self.fs.stat(&to)? {
+                None => self.move_path(&from, &to),
+                Some(_) if self.mergeable(&from, &to)? => self.merge(&from, &to, report),
+                Some(_) => {
+                    let to = self.free_name(target, &name, is_dir)?;
+                    let result = self.move_path(&from, &to);
+                    if result.is_ok() { report.push(Outcome { path: from, code: Ok(Some(0)), output: trf("kept both, arrived as {}", &[&to.file_name().unwrap_or_default().to_string_lossy()]) }); }
+                    result
+                }
+            };
+            if let Err(e) = result { report.push(Outcome { path: source.join(&name), code: Err(e.to_string()), output: String::new() }); }
+        }
+        if self.fs.list(source)?.is_empty() { self.fs.remove(source)?; }
+        Ok(())
+    }
+
+    fn move_path(&mut self, source: &Path, target: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.fs.rename(source, target).or_else(|_| self.fs.copy(source, target).and_then(|_| self.fs.remove(source)))?;
+        self.retag(source, Some(target));
+        Ok(())
+    }
+
+    fn transfer(&mut self, transfer: Transfer, source: &Path, target: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        match transfer {
+            Transfer::Copy => self.fs.copy(source, target)?,
+            Transfer::Move => self.move_path(source, target)?,
+            Transfer::Link => vfs::copy_symlink(source, target)?,
+        }
+        self.marks.remove(source);
+        Ok(())
+    }
+
+    pub fn group_marks(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.marks.is_empty() { return Err(tr("nothing is marked (Space marks the selected entry)").into()); }
+        let folder = self.focus_dir.join(name.trim());
+        if self.fs.stat(&folder)?.is_some() { return Err(trf("{} already exists", &[&name.trim()]).into()); }
+        let sources: Vec<PathBuf> = self.marks.iter().filter(|source| !folder.starts_with(source)).cloned().collect();
+        if self.dry_run {
+            let mut report = vec![Outcome { path: folder.clone(), code: Ok(Some(0)), output: tr("would create this folder").to_string() }];
+            report.extend(sources.into_iter().map(|source| Outcome { output: trf("would move into {}", &[&name.trim()]), path: source, code: Ok(Some(0)) }));
+            self.show_dry_run(&trf("group into {}", &[&name.trim()]), report);
+            return Ok(());
+        }
+        self.fs.mkdir(&folder)?;
+        let (mut done, mut failure) = (0, None);
+        for source in sources {
+            let Some(file) = source.file_name() else { continue };
+            let target = folder.join(file);
+            if self.fs.stat(&target)?.is_some() { failure.get_or_insert(trf("{}: another marked entry has the same name", &[&source.display()])); continue; }
+            match self.transfer(Transfer::Move, &source, &target) {
+                Ok(()) => done += 1,
+                Err(e) => { failure.get_or_insert(format!("{}: {}", source.display(), e)); }
+            }
+        }
+        let mut status = trf("Moved {} into {}", &[&done, &name.trim()]);
+        if let Some(failure) = failure { status.push_str(&trf(", failed {}", &[&failure])); }
+        self.refresh_entries()?;
+        self.apply(Action::Select(name.trim().to_string()))?;
+        self.status = Some(status);
+        Ok(())
+    }
+
+    fn retag(&mut self, from: &Path, to: Option<&Path>) {
+        if !self.fs.is_local() { return; }
+        let result = match to { Some(to) => self.tags.rename(from, to), None => self.tags.forget(from) };
+        if let Err(e) = result { self.status = Some(trf("Could not update tags: {}", &[&e])); }
+    }
+
+    pub fn deletes_to_trash(&self) -> bool {
+        self.config.trash && self.fs.is_local() && trash::available()
+    }
+
+    pub fn request(&mut self, action: Action) -> Result<(), Box<dyn std::error::Error>> {
+        if self.dry_run && let Action::Delete(name) | Action::Shred(name) = &action {
+            let path = self.focus_dir.join(name);
+            let items = if self.fs.is_local() { count_items(&path, 100_000) } else { 1 };
+            let verb = match action { Action::Shred(_) => "securely wipe", _ if self.deletes_to_trash() => "move to the trash", _ => "permanently delete" };
+            let scope = if items >= 100_000 { tr(" (100000+ items)").to_string() } else if items > 1 { trf(" ({} items)", &[&items]) } else { String::new() };
+            let output = trf("would {}{}", &[&tr(verb), &scope]);
+            self.show_dry_run(tr(if matches!(action, Action::Shred(_)) { "secure wipe" } else { "delete" }), vec![Outcome { path, code: Ok(Some(0)), output }]);
+            return Ok(());
+        }
+        let policy = &self.config.confirm;
+        let (prompt, action) = match action {
+            Action::Delete(name) => {
+                let items = if !self.fs.is_local() { 1 } else { count_items(&self.focus_dir.join(&name), policy.bulk_threshold + 1) };
+                let prompt = if items > policy.bulk_threshold { Some(trf("WARNING: Delete {}+ items?", &[&policy.bulk_threshold])) }
+                    else if policy.permanent_delete && !self.deletes_to_trash() { Some(tr("WARNING: Permanently delete item?").to_string()) }
+                    else if policy.delete && self.deletes_to_trash() { Some(trf("Move item to the {}?", &[&trash::NAME])) }
+                    else if policy.delete { Some(tr("WARNING: Delete item?").to_string()) }
+                    else { None };
+                (prompt.map(|message| (PopupMode::Delete, message)), Action::Delete(name))
+            }
+            Action::Rename(from, to) if from != to && self.fs.stat(&self.focus_dir.join(&to))?.is_some() => {
+                let prompt = policy.overwrite.then(|| (PopupMode::Overwrite, trf("WARNING: Replace existing '{}'?", &[&to])));
+                (prompt, Action::Replace(from, to))
+            }
+            Action::Shred(name) => (Some((PopupMode::Shred, tr("WARNING: Securely wipe item?").to_string())), Action::Shred(name)),
+            action => (None, action),
+        };
+        match prompt {
+            Some((mode, message)) => {
+                self.popup_mode = mode;
+                self.confirm_message = message;
+                self.confirm_action = Some(action);
+                self.input_buffer.clear();
+                Ok(())
+            }
+            None => self.apply(action),
+        }
+    }
+
+    pub fn palette_items(&self) -> Vec<(String, Option<char>, ExternalTask)> {
+        let filter = self.input_buffer.to_lowercase();
+        let commands = self.config.commands.iter().enumerate().map(|(i, c)| (c.name.clone(), c.key, ExternalTask::Command(i)));
+        let plugins = self.plugins.iter().enumerate().map(|(i, p)| (p.name.clone(), p.key, ExternalTask::Plugin(i)));
+        commands.chain(plugins).filter(|(name, _, _)| name.to_lowercase().contains(&filter)).collect()
+    }
+
+    pub fn clamp_selection(&mut self) {
+        self.selected_index = self.selected_index.min(self.entries.len().saturating_sub(1));
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    pub fn describe(&self, dir_changed: bool) -> String {
+        let selection = match self.entries.get(self.selected_index) {
+            Some(entry) => trf("{}, {}, {} of {}", &[entry, &tr(if self.dir_names.contains(entry) { "directory" } else { "file" }), &(self.selected_index + 1), &self.entries.len()]),
+            None => tr("no entries").to_string(),
+        };
+        if dir_changed { format!("{}: {}", self.fs.location(&self.focus_dir), selection) } else { selection }
+    }
+
+    pub fn get_selected_path(&self) -> Option<PathBuf> {
+        self.entries.get(self.selected_index).map(|entry| self.focus_dir.join(entry))
+    }
+}
+
+fn count_items(path: &Path, cap: usize) -> usize {
+    if !path.is_dir() || path.is_symlink() { return 1; }
+    let mut count = 0;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(read) = fs::read_dir(&dir) else { continue };
+        for entry in read.filter_map(|e| e.ok()) {
+            count += 1;
+            if count >= cap { return count; }
+            if entry.file_type().is_ok_and(|t| t.is_dir()) { stack.push(entry.path()); }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{config::Document, scratch::ScratchDir, vfs::{LocalFs, MemoryFs}};
+
+    pub(crate) fn app(paths: &[&str]) -> AppState {
+        let fs = MemoryFs::default();
+        fs.mkdir(Path::new("/work")).unwrap();
+        for path in paths {
+            match path.strip_suffix('/') {
+                Some(dir) => fs.mkdir(Path::new(dir)).unwrap(),
+                None => {
+                    fs.mkdir(Path::new(path).parent().unwrap()).unwrap();
+                    fs.write(Path::new(path), path.as_bytes()).unwrap();
+                }
+            }
+        }
+        AppState::with_fs(config(), Box::new(fs), PathBuf::from("/work"), Stores::default()).unwrap()
+    }
+
+    fn config() -> Config {
+        Config::from_document(&Document::parse("[preview]\ngraphics = \"none\"\n").unwrap(), PathBuf::from("/config.toml"))
+    }
+
+    pub(crate) fn exists(app_state: &AppState, path: &str) -> bool {
+        app_state.fs.stat(Path::new(path)).unwrap().is_some()
+    }
+
+    fn contents(app_state: &AppState, path: &str) -> String {
+        String::from_utf8(app_state.fs.read(Path::new(path)).unwrap()).unwrap()
+    }
+
+    fn mark(app_state: &mut AppState, paths: &[&str]) {
+        app_state.marks.extend(paths.iter().map(PathBuf::from));
+    }
+
+    #[test]
+    fn pastes_marks_without_clashes_directly() {
+        let mut app_state = app(&["/src/a.txt", "/src/dir/b.txt"]);
+        mark(&mut app_state, &["/src/a.txt", "/src/dir"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert_eq!(contents(&app_state, "/work/dir/b.txt"), "/src/dir/b.txt");
+        assert!(exists(&app_state, "/src/a.txt"));
+        assert!(app_state.marks.is_empty());
+        assert_eq!(app_state.entries, ["a.txt", "dir"]);
+    }
+
+    #[test]
+    fn moving_removes_the_source() {
+        let mut app_state = app(&["/src/a.txt"]);
+        mark(&mut app_state, &["/src/a.txt"]);
+        app_state.paste_marks(Transfer::Move).unwrap();
+        assert_eq!(contents(&app_state, "/work/a.txt"), "/src/a.txt");
+        assert!(!exists(&app_state, "/src/a.txt"));
+    }
+
+    #[test]
+    fn clashes_ask_before_pasting() {
+        let mut app_state = app(&["/src/a.txt", "/src/c.txt", "/work/a.txt"]);
+        mark(&mut app_state, &["/src/a.txt", "/src/c.txt"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::Conflict);
+        assert_eq!(app_state.pending_transfer, Some(Transfer::Copy));
+        assert!(!exists(&app_state, "/work/c.txt"));
+    }
+
+    #[test]
+    fn skipping_clashes_keeps_them_marked() {
+        let mut app_state = app(&["/src/a.txt", "/src/c.txt", "/work/a.txt"]);
+        mark(&mut app_state, &["/src/a.txt", "/src/c.txt"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        app_state.resolve_conflict(Clash::Skip).unwrap();
+        assert_eq!(contents(&app_state, "/work/a.txt"), "/work/a.txt");
+        assert!(exists(&app_state, "/work/c.txt"));
+        assert_eq!(app_state.marks, BTreeSet::from([PathBuf::from("/src/a.txt")]));
+    }
+
+    #[test]
+    fn keeping_both_picks_the_next_free_number() {
+        let mut app_state = app(&["/src/a.txt", "/work/a.txt", "/work/a (1).txt"]);
+        mark(&mut app_state, &["/src/a.txt"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        app_state.resolve_conflict(Clash::KeepBoth).unwrap();
+        assert_eq!(contents(&app_state, "/work/a (2).txt"), "/src/a.txt");
+        assert_eq!(contents(&app_state, "/work/a.txt"), "/work/a.txt");
+        assert!(app_state.marks.is_empty());
+    }
+
+    #[test]
+    fn keeping_both_duplicates_marks_in_their_own_directory() {
+        let mut app_state = app(&["/work/notes.md"]);
+        mark(&mut app_state, &["/work/notes.md"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        app_state.resolve_conflict(Clash::KeepBoth).unwrap();
+        assert_eq!(contents(&app_state, "/work/notes (1).md"), "/work/notes.md");
+    }
+
+    #[test]
+    fn moving_a_mark_onto_itself_is_a_no_op() {
+        let mut app_state = app(&["/work/notes.md"]);
+        mark(&mut app_state, &["/work/notes.md"]);
+        app_state.paste_marks(Transfer::Move).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert_eq!(app_state.entries, ["notes.md"]);
+    }
+
+    #[test]
+    fn merging_folders_keeps_both_of_clashing_files() {
+        let mut app_state = app(&["/src/photos/x.jpg", "/src/photos/y.jpg", "/src/photos/inner/z", "/work/photos/x.jpg", "/work/photos/inner/z"]);
+        mark(&mut app_state, &["/src/photos"]);
+        app_state.paste_marks(Transfer::Move).unwrap();
+        assert!(app_state.status.as_deref().is_some_and(|status| status.contains("g merge")));
+        app_state.resolve_conflict(Clash::Merge).unwrap();
+        assert_eq!(contents(&app_state, "/work/photos/x.jpg"), "/work/photos/x.jpg");
+        assert_eq!(contents(&app_state, "/work/photos/x (1).jpg"), "/src/photos/x.jpg");
+        assert_eq!(contents(&app_state, "/work/photos/y.jpg"), "/src/photos/y.jpg");
+        assert_eq!(contents(&app_state, "/work/photos/inner/z (1)"), "/src/photos/inner/z");
+        assert!(!exists(&app_state, "/src/photos"));
+        assert_eq!(app_state.popup_mode, PopupMode::Report);
+        assert_eq!(app_state.report.len(), 2);
+        assert!(app_state.report.iter().all(Outcome::success));
+    }
+
+    #[test]
+    fn merging_only_applies_to_moves() {
+        let mut app_state = app(&["/src/photos/x.jpg", "/work/photos/"]);
+        mark(&mut app_state, &["/src/photos"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        assert!(app_state.resolve_conflict(Clash::Merge).is_err());
+        assert!(!exists(&app_state, "/work/photos/x.jpg"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn merging_never_follows_a_symlinked_folder() {
+        let dir = env::temp_dir().join(format!("quickfind-merge-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["real", "src", "work/photos"] { fs::create_dir_all(dir.join(sub)).unwrap(); }
+        fs::write(dir.join("real/x.jpg"), "x").unwrap();
+        fs::write(dir.join("work/photos/y.jpg"), "y").unwrap();
+        std::os::unix::fs::symlink(dir.join("real"), dir.join("src/photos")).unwrap();
+        let mut app_state = AppState::with_fs(config(), Box::new(LocalFs), dir.join("work"), Stores::default()).unwrap();
+        app_state.marks.insert(dir.join("src/photos"));
+        app_state.paste_marks(Transfer::Move).unwrap();
+        let offered_merge = app_state.status.as_deref().is_some_and(|status| status.contains("merge"));
+        app_state.resolve_conflict(Clash::Merge).unwrap();
+        let outcome = (dir.join("real/x.jpg").exists(), fs::read_link(dir.join("work/photos (1)")).is_ok(), dir.join("work/photos/x.jpg").exists(), dir.join("src/photos").exists());
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!offered_merge);
+        assert_eq!(outcome, (true, true, false, false));
+    }
+
+    #[test]
+    fn uploads_run_as_background_jobs() {
+        let mut app_state = app(&[]);
+        let scratch = ScratchDir::new().unwrap();
+        scratch.create("report.txt").unwrap().1.write_all(b"numbers").unwrap();
+        app_state.local_dir = scratch.path().to_path_buf();
+        app_state.apply(Action::Upload("report.txt".to_string())).unwrap();
+        assert_eq!(app_state.jobs.active(), 1);
+        while app_state.jobs.active() > 0 { app_state.poll_jobs(); std::thread::sleep(Duration::from_millis(10)); }
+        assert_eq!(contents(&app_state, "/work/report.txt"), "numbers");
+        assert_eq!(app_state.entries, ["report.txt"]);
+        assert!(app_state.apply(Action::Upload("missing.txt".to_string())).is_err());
+    }
+
+    #[test]
+    fn dry_run_pastes_only_report() {
+        let mut app_state = app(&["/src/a.txt", "/work/a.txt"]);
+        app_state.toggle_dry_run();
+        mark(&mut app_state, &["/src/a.txt"]);
+        app_state.paste_marks(Transfer::Copy).unwrap();
+        app_state.resolve_conflict(Clash::KeepBoth).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::Report);
+        assert!(app_state.report[0].output.contains("/work/a (1).txt"));
+        assert!(!exists(&app_state, "/work/a (1).txt"));
+        assert!(!app_state.marks.is_empty());
+    }
+
+    #[test]
+    fn groups_marks_into_a_new_folder() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt", "/work/c.txt"]);
+        mark(&mut app_state, &["/work/a.txt", "/work/b.txt"]);
+        app_state.group_marks("pair").unwrap();
+        assert!(exists(&app_state, "/work/pair/a.txt") && exists(&app_state, "/work/pair/b.txt"));
+        assert_eq!(app_state.entries, ["c.txt", "pair"]);
+        assert_eq!(app_state.entries[app_state.selected_index], "pair");
+        assert!(app_state.group_marks("pair").is_err());
+    }
+
+    #[test]
+    fn creates_and_selects_entries() {
+        let mut app_state = app(&["/work/b.txt"]);
+        app_state.apply(Action::Mkdir("docs".to_string())).unwrap();
+        app_state.apply(Action::Touch("a.txt".to_string())).unwrap();
+        assert_eq!(app_state.entries, ["a.txt", "b.txt", "docs"]);
+        assert!(app_state.dir_names.contains("docs"));
+        app_state.apply(Action::Select("b.txt".to_string())).unwrap();
+        assert_eq!(app_state.get_selected_path(), Some(PathBuf::from("/work/b.txt")));
+        assert!(app_state.apply(Action::Select("missing".to_string())).is_err());
+    }
+
+    #[test]
+    fn touching_an_existing_file_keeps_its_contents() {
+        let mut app_state = app(&["/work/a.txt"]);
+        app_state.apply(Action::Touch("a.txt".to_string())).unwrap();
+        assert_eq!(contents(&app_state, "/work/a.txt"), "/work/a.txt");
+    }
+
+    #[test]
+    fn renames_drop_the_stale_mark() {
+        let mut app_state = app(&["/work/a.txt"]);
+        mark(&mut app_state, &["/work/a.txt"]);
+        app_state.request(Action::Rename("a.txt".to_string(), "z.txt".to_string())).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::None);
+        assert_eq!(app_state.entries, ["z.txt"]);
+        assert!(app_state.marks.is_empty());
+    }
+
+    #[test]
+    fn renaming_onto_an_entry_asks_to_replace_it() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt"]);
+        app_state.request(Action::Rename("a.txt".to_string(), "b.txt".to_string())).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::Overwrite);
+        assert_eq!(app_state.confirm_action, Some(Action::Replace("a.txt".to_string(), "b.txt".to_string())));
+        let action = app_state.confirm_action.take().unwrap();
+        app_state.apply(action).unwrap();
+        assert_eq!(app_state.entries, ["b.txt"]);
+        assert_eq!(contents(&app_state, "/work/b.txt"), "/work/a.txt");
+    }
+
+    #[test]
+    fn deleting_asks_first() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt"]);
+        app_state.request(Action::Delete("a.txt".to_string())).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::Delete);
+        assert!(exists(&app_state, "/work/a.txt"));
+        let action = app_state.confirm_action.take().unwrap();
+        app_state.apply(action).unwrap();
+        assert_eq!(app_state.entries, ["b.txt"]);
+    }
+
+    #[test]
+    fn dry_run_deletes_only_report() {
+        let mut app_state = app(&["/work/a.txt"]);
+        app_state.toggle_dry_run();
+        app_state.request(Action::Delete("a.txt".to_string())).unwrap();
+        assert_eq!(app_state.popup_mode, PopupMode::Report);
+        assert_eq!(app_state.report[0].output, "would permanently delete");
+        assert!(exists(&app_state, "/work/a.txt"));
+        app_state.toggle_dry_run();
+        assert!(!app_state.dry_run);
+    }
+
+    #[test]
+    fn marks_follow_selection_commands() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt", "/work/c.txt"]);
+        app_state.toggle_mark();
+        assert!(app_state.is_marked("a.txt"));
+        assert_eq!(app_state.selected_index, 1);
+        app_state.select(Selection::Invert);
+        assert!(!app_state.is_marked("a.txt") && app_state.is_marked("b.txt") && app_state.is_marked("c.txt"));
+        app_state.select(Selection::All);
+        assert_eq!(app_state.marks.len(), 3);
+        app_state.select(Selection::None);
+        assert!(app_state.marks.is_empty());
+    }
+
+    #[test]
+    fn marks_of_vanished_entries_are_dropped() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt"]);
+        app_state.select(Selection::All);
+        app_state.fs.remove(Path::new("/work/a.txt")).unwrap();
+        app_state.refresh_entries().unwrap();
+        assert_eq!(app_state.marks, BTreeSet::from([PathBuf::from("/work/b.txt")]));
+    }
+
+    #[test]
+    fn hidden_entries_toggle() {
+        let mut app_state = app(&["/work/.env", "/work/a.txt"]);
+        assert_eq!(app_state.entries, [".env", "a.txt"]);
+        app_state.toggle_hidden().unwrap();
+        assert_eq!(app_state.entries, ["a.txt"]);
+        app_state.toggle_hidden().unwrap();
+        assert_eq!(app_state.entries, [".env", "a.txt"]);
+    }
+
+    #[test]
+    fn reversing_the_sort_keeps_the_selection() {
+        let mut app_state = app(&["/work/a.txt", "/work/b.txt", "/work/c.txt"]);
+        app_state.apply(Action::Select("a.txt".to_string())).unwrap();
+        app_state.cycle_sort(true).unwrap();
+        assert_eq!(app_state.entries, ["c.txt", "b.txt", "a.txt"]);
+        assert_eq!(app_state.entries[app_state.selected_index], "a.txt");
+    }
+
+    #[test]
+    fn filters_narrow_the_listing() {
+        let mut app_state = app(&["/work/notes.md", "/work/main.rs", "/work/lib.rs"]);
+        app_state.filter = "rs".to_string();
+        app_state.refilter();
+        assert_eq!(app_state.entries, ["lib.rs", "main.rs"]);
+        app_state.select(Selection::All);
+        assert_eq!(app_state.marks.len(), 2);
+    }
+
+    #[test]
+    fn cd_and_reveal_move_the_focus() {
+        let mut app_state = app(&["/work/docs/guide.md", "/work/a.txt"]);
+        app_state.apply(Action::Cd("docs".to_string())).unwrap();
+        assert_eq!(app_state.focus_dir, Path::new("/work/docs"));
+        assert_eq!(app_state.entries, ["guide.md"]);
+        app_state.apply(Action::Reveal("/work/a.txt".to_string())).unwrap();
+        assert_eq!(app_state.focus_dir, Path::new("/work"));
+        assert_eq!(app_state.entries[app_state.selected_index], "a.txt");
+    }
+
+    #[test]
+    fn default_stores_stay_in_memory() {
+        let mut app_state = app(&["/work/a.txt"]);
+        app_state.tags.set(Path::new("/work/a.txt"), vec!["red".to_string()]).unwrap();
+        app_state.history.push("cd", "/work").unwrap();
+        assert!(app_state.tags.has(Path::new("/work/a.txt"), "red"));
+        assert_eq!(app_state.history.entries("cd"), ["/work"]);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{devices, state::AppState, vfs::human_size};
+
+pub const FIELDS: [&str; 11] = ["path", "name", "selected", "total", "sort", "filter", "free", "marked", "git", "jobs", "mode"];
+
+pub fn render(template: &str, app_state: &AppState) -> String {
+    let mut line = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        line.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}').map(|end| (&after[..end], &after[end + 1..])) {
+            Some((name, tail)) if FIELDS.contains(&name) => { line.push_str(&field(name, app_state)); rest = tail; }
+            _ => { line.push('{'); rest = after; }
+        }
+    }
+    line.push_str(rest);
+    line.trim_end().to_string()
+}
+
+fn field(name: &str, app_state: &AppState) -> String {
+    match name {
+        "path" => app_state.fs.location(&app_state.focus_dir),
+        "name" => app_state.entries.get(app_state.selected_index).cloned().unwrap_or_default(),
+        "selected" => (app_state.selected_index + 1).min(app_state.entries.len()).to_string(),
+        "total" => app_state.entries.len().to_string(),
+        "sort" if app_state.fs.keeps_order() => String::new(),
+        "sort" => format!("{} {}", app_state.listing.sort.label(), app_state.listing.sort.direction(app_state.listing.reverse)),
+        "filter" if app_state.filter.is_empty() => String::new(),
+        "filter" => format!("/{}", app_state.filter),
+        "free" if !app_state.fs.is_local() => String::new(),
+        "free" => devices::free_space(&app_state.focus_dir).map(human_size).unwrap_or_default(),
+        "marked" if app_state.marks.is_empty() => String::new(),
+        "marked" => format!("+{}", app_state.marks.len()),
+        "git" => app_state.repo.as_ref().map(|repo| {
+            let mut state = repo.branch.clone();
+            if repo.ahead > 0 { state.push_str(&format!(" ↑{}", repo.ahead)); }
+            if repo.behind > 0 { state.push_str(&format!(" ↓{}", repo.behind)); }
+            if repo.dirty || repo.untracked { state.push_str(if repo.dirty { " *" } else { " ?" }); }
+            state
+        }).unwrap_or_default(),
+        "jobs" => match app_state.jobs.active() { 0 => String::new(), n => format!("[{} job{}]", n, if n == 1 { "" } else { "s" }) },
+        "mode" => [app_state.zen.then_some("ZEN"), app_state.dry_run.then_some("DRY RUN"), app_state.choosing.then_some("PICK")].into_iter().flatten().collect::<Vec<_>>().join(" "),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,28 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+pub fn list(path: &Path) -> io::Result<Vec<(String, u64)>> {
+    if !cfg!(windows) { return Ok(Vec::new()); }
+    let script = format!("Get-Item -LiteralPath '{}' -Stream * | ForEach-Object {{ \"$($_.Length)`t$($_.Stream)\" }}", path.to_string_lossy().replace('\'', "''"));
+    let output = std::process::Command::new("powershell").args(["-NoProfile", "-NonInteractive", "-Command", &script]).output()?;
+    if !output.status.success() { return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string())); }
+    Ok(String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(_, name)| *name != ":$DATA")
+        .filter_map(|(size, name)| Some((name.to_string(), size.trim().parse().ok()?)))
+        .collect())
+}
+
+pub fn read(path: &Path, stream: &str) -> io::Result<Vec<u8>> {
+    fs::read(stream_path(path, stream))
+}
+
+pub fn remove(path: &Path, stream: &str) -> io::Result<()> {
+    fs::remove_file(stream_path(path, stream))
+}
+
+fn stream_path(path: &Path, stream: &str) -> PathBuf {
+    let mut joined = path.as_os_str().to_owned();
+    joined.push(":");
+    joined.push(stream);
+    PathBuf::from(joined)
+}
@@ -0,0 +1,110 @@
+use std::{fs::{self, File}, io, path::{Path, PathBuf}};
+use crate::{compare::{self, Difference}, finder::ScanLimits, foreach::Outcome, vfs};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operation {
+    Copy,
+    Update,
+    Touch,
+    Replace,
+    Delete,
+}
+
+impl Operation {
+    pub fn label(self) -> &'static str {
+        match self {
+            Operation::Copy => "copy",
+            Operation::Update => "update",
+            Operation::Touch => "touch",
+            Operation::Replace => "replace",
+            Operation::Delete => "delete",
+        }
+    }
+
+    fn done(self) -> &'static str {
+        match self {
+            Operation::Copy => "copied",
+            Operation::Update => "updated",
+            Operation::Touch => "set modification time",
+            Operation::Replace => "replaced",
+            Operation::Delete => "deleted",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Step {
+    pub operation: Operation,
+    pub path: PathBuf,
+}
+
+pub struct Plan {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub delete: bool,
+    pub steps: Vec<Step>,
+}
+
+impl Plan {
+    pub fn count(&self, operation: Operation) -> usize {
+        self.steps.iter().filter(|s| s.operation == operation).count()
+    }
+}
+
+pub fn plan(source: &Path, target: &Path, delete: bool, limits: &ScanLimits) -> io::Result<Plan> {
+    let mut steps = Vec::new();
+    collect(source, target, Path::new(""), delete, limits, &mut steps)?;
+    Ok(Plan { source: source.to_path_buf(), target: target.to_path_buf(), delete, steps })
+}
+
+fn collect(source: &Path, target: &Path, relative: &Path, delete: bool, limits: &ScanLimits, steps: &mut Vec<Step>) -> io::Result<()> {
+    for (name, difference) in compare::compare(&source.join(relative), &target.join(relative), limits)? {
+        let path = relative.join(name);
+        let operation = match difference {
+            Difference::OnlyHere => Operation::Copy,
+            Difference::OnlyThere if delete => Operation::Delete,
+            Difference::OnlyThere => continue,
+            Difference::Kind => Operation::Replace,
+            Difference::Content if source.join(&path).is_dir() => { collect(source, target, &path, delete, limits, steps)?; continue }
+            Difference::Size | Difference::Content => Operation::Update,
+            Difference::Modified => Operation::Touch,
+        };
+        steps.push(Step { operation, path });
+    }
+    Ok(())
+}
+
+pub fn run_step(plan: &Plan, step: &Step) -> Outcome {
+    let (from, to) = (plan.source.join(&step.path), plan.target.join(&step.path));
+    let result = match step.operation {
+        Operation::Copy => copy(&from, &to),
+        Operation::Update => fs::copy(&from, &to).and_then(|_| touch(&from, &to)),
+        Operation::Touch => touch(&from, &to),
+        Operation::Replace => remove(&to).and_then(|_| copy(&from, &to)),
+        Operation::Delete => remove(&to),
+    };
+    match result {
+        Ok(()) => Outcome { path: step.path.clone(), code: Ok(Some(0)), output: step.operation.done().to_string() },
+        Err(e) => Outcome { path: step.path.clone(), code: Err(format!("{}: {}", step.operation.label(), e)), output: String::new() },
+    }
+}
+
+fn copy(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.is_symlink() { return vfs::copy_symlink(&fs::read_link(from)?, to); }
+    if !meta.is_dir() { fs::copy(from, to)?; return touch(from, to); }
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+fn touch(from: &Path, to: &Path) -> io::Result<()> {
+    File::options().write(true).open(to)?.set_modified(fs::metadata(from)?.modified()?)
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) }
+}
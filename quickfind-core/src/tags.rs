@@ -0,0 +1,74 @@
+use std::{collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+use crate::config;
+
+#[derive(Default)]
+pub struct Tags {
+    path: Option<PathBuf>,
+    entries: BTreeMap<PathBuf, Vec<String>>,
+}
+
+impl Tags {
+    pub fn load() -> Tags {
+        let path = config::config_dir().join("tags");
+        let entries = fs::read_to_string(&path).unwrap_or_default().lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(tags, file)| (PathBuf::from(file), parse(tags)))
+            .filter(|(_, tags)| !tags.is_empty())
+            .collect();
+        Tags { path: Some(path), entries }
+    }
+
+    pub fn get(&self, file: &Path) -> &[String] {
+        self.entries.get(file).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn has(&self, file: &Path, tag: &str) -> bool {
+        self.get(file).iter().any(|t| t == tag)
+    }
+
+    pub fn set(&mut self, file: &Path, tags: Vec<String>) -> io::Result<()> {
+        if file.to_string_lossy().contains('\n') { return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot tag a path containing a newline")); }
+        if tags.is_empty() { self.entries.remove(file); } else { self.entries.insert(file.to_path_buf(), tags); }
+        self.save()
+    }
+
+    pub fn forget(&mut self, file: &Path) -> io::Result<()> {
+        let before = self.entries.len();
+        self.entries.retain(|tagged, _| !tagged.starts_with(file));
+        if self.entries.len() == before { Ok(()) } else { self.save() }
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        let moved: Vec<PathBuf> = self.entries.keys().filter(|tagged| tagged.starts_with(from)).cloned().collect();
+        if moved.is_empty() { return Ok(()); }
+        for old in moved {
+            let Some(tags) = self.entries.remove(&old) else { continue };
+            let new = match old.strip_prefix(from) {
+                Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+                Ok(rest) => to.join(rest),
+                Err(_) => old,
+            };
+            self.entries.insert(new, tags);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        fs::write(path, self.entries.iter().map(|(file, tags)| format!("{}\t{}\n", tags.join(","), file.display())).collect::<String>())
+    }
+}
+
+pub fn parse(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) { tags.push(tag.to_string()); }
+    }
+    tags
+}
+
+pub fn split_query(query: &str) -> (Vec<&str>, String) {
+    let (tags, words): (Vec<&str>, Vec<&str>) = query.split(' ').partition(|word| word.starts_with("tag:"));
+    (tags.into_iter().map(|t| &t[4..]).filter(|t| !t.is_empty()).collect(), words.join(" "))
+}
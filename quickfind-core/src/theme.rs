@@ -0,0 +1,260 @@
+use std::env;
+use tui::{style::{Color, Modifier, Style}, widgets::Borders};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Monochrome,
+}
+
+impl ColorDepth {
+    pub fn detect() -> Self {
+        if let Ok(forced) = env::var("QUICKFIND_COLORS") {
+            match forced.to_lowercase().as_str() {
+                "truecolor" | "24bit" => return ColorDepth::TrueColor,
+                "256" => return ColorDepth::Ansi256,
+                "16" => return ColorDepth::Ansi16,
+                "none" | "mono" => return ColorDepth::Monochrome,
+                _ => {}
+            }
+        }
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) { return ColorDepth::Monochrome; }
+        let colorterm = env::var("COLORTERM").unwrap_or_default().to_lowercase();
+        if colorterm.contains("truecolor") || colorterm.contains("24bit") { return ColorDepth::TrueColor; }
+        if env::var("WT_SESSION").is_ok() { return ColorDepth::TrueColor; }
+        if matches!(env::var("TERM_PROGRAM").unwrap_or_default().as_str(), "iTerm.app" | "WezTerm" | "vscode") { return ColorDepth::TrueColor; }
+        let term = env::var("TERM").unwrap_or_default().to_lowercase();
+        if term.contains("direct") || term.contains("truecolor") { return ColorDepth::TrueColor; }
+        if term.contains("256") { return ColorDepth::Ansi256; }
+        ColorDepth::Ansi16
+    }
+
+    pub fn adapt(&self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::Monochrome, _) => Color::Reset,
+            (ColorDepth::TrueColor, c) => c,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_256(r, g, b)),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => rgb_to_16(r, g, b),
+            (ColorDepth::Ansi16, Color::Indexed(i)) if i >= 16 => { let (r, g, b) = indexed_to_rgb(i); rgb_to_16(r, g, b) }
+            (_, c) => c,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub name: &'static str,
+    pub directory: Color,
+    pub file: Color,
+    pub border: Color,
+    pub selection: Color,
+    pub selection_bg: Color,
+    pub label: Color,
+    pub accent: Color,
+    pub input: Color,
+    pub hint: Color,
+    pub danger: Color,
+    pub matched: Color,
+    pub foreground: Color,
+    pub background: Color,
+    pub borders: Borders,
+    pub monochrome: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: "dark",
+            directory: Color::Rgb(144, 238, 144),
+            file: Color::Green,
+            border: Color::Green,
+            selection: Color::Yellow,
+            selection_bg: Color::Reset,
+            label: Color::Yellow,
+            accent: Color::Cyan,
+            input: Color::Yellow,
+            hint: Color::Gray,
+            danger: Color::Red,
+            matched: Color::LightMagenta,
+            foreground: Color::Reset,
+            background: Color::Reset,
+            borders: Borders::ALL,
+            monochrome: false,
+        }
+    }
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 4] = ["dark", "light", "high-visibility", "high-contrast"];
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::default()),
+            "light" => Some(Theme {
+                name: "light",
+                directory: Color::Rgb(0, 95, 135),
+                file: Color::Rgb(0, 110, 0),
+                border: Color::Rgb(88, 88, 88),
+                selection: Color::Rgb(175, 65, 0),
+                selection_bg: Color::Reset,
+                label: Color::Rgb(0, 70, 160),
+                accent: Color::Rgb(135, 0, 135),
+                input: Color::Rgb(30, 30, 30),
+                hint: Color::Rgb(108, 108, 108),
+                danger: Color::Rgb(180, 0, 0),
+                matched: Color::Rgb(200, 0, 90),
+                foreground: Color::Reset,
+            background: Color::Reset,
+            borders: Borders::ALL,
+            monochrome: false,
+            }),
+            "high-visibility" => Some(Theme {
+                name: "high-visibility",
+                directory: Color::LightCyan,
+                file: Color::White,
+                border: Color::White,
+                selection: Color::Black,
+                selection_bg: Color::LightYellow,
+                label: Color::LightYellow,
+                accent: Color::LightCyan,
+                input: Color::LightYellow,
+                hint: Color::White,
+                danger: Color::LightRed,
+                matched: Color::LightMagenta,
+                foreground: Color::Reset,
+            background: Color::Reset,
+            borders: Borders::ALL,
+            monochrome: false,
+            }),
+            "high-contrast" => Some(Theme {
+                name: "high-contrast",
+                directory: Color::Rgb(255, 255, 0),
+                file: Color::Rgb(255, 255, 255),
+                border: Color::Rgb(255, 255, 255),
+                selection: Color::Rgb(0, 0, 0),
+                selection_bg: Color::Rgb(255, 255, 255),
+                label: Color::Rgb(0, 255, 255),
+                accent: Color::Rgb(0, 255, 255),
+                input: Color::Rgb(255, 255, 0),
+                hint: Color::Rgb(200, 200, 200),
+                danger: Color::Rgb(255, 140, 140),
+                matched: Color::Rgb(255, 170, 255),
+                foreground: Color::Rgb(255, 255, 255),
+                background: Color::Rgb(0, 0, 0),
+                borders: Borders::ALL,
+                monochrome: false,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn next_name(&self) -> &'static str {
+        let index = Theme::NAMES.iter().position(|n| *n == self.name).unwrap_or(0);
+        Theme::NAMES[(index + 1) % Theme::NAMES.len()]
+    }
+
+    pub fn adapted(&self, depth: ColorDepth) -> Self {
+        Theme {
+            name: self.name,
+            directory: depth.adapt(self.directory),
+            file: depth.adapt(self.file),
+            border: depth.adapt(self.border),
+            selection: depth.adapt(self.selection),
+            selection_bg: depth.adapt(self.selection_bg),
+            label: depth.adapt(self.label),
+            accent: depth.adapt(self.accent),
+            input: depth.adapt(self.input),
+            hint: depth.adapt(self.hint),
+            danger: depth.adapt(self.danger),
+            matched: depth.adapt(self.matched),
+            foreground: depth.adapt(self.foreground),
+            background: depth.adapt(self.background),
+            borders: self.borders,
+            monochrome: depth == ColorDepth::Monochrome,
+        }
+    }
+
+    pub fn paint(&self, color: Color) -> Color {
+        if self.monochrome { Color::Reset } else { color }
+    }
+
+    pub fn entry(&self, is_dir: bool) -> Style {
+        match (is_dir, self.monochrome) {
+            (true, true) => Style::default().add_modifier(Modifier::BOLD),
+            (true, false) => Style::default().fg(self.directory),
+            (false, _) => Style::default().fg(self.file),
+        }
+    }
+
+    pub fn selected(&self) -> Style {
+        let style = Style::default().fg(self.selection).bg(self.selection_bg).add_modifier(Modifier::BOLD);
+        if self.monochrome { style.add_modifier(Modifier::REVERSED) } else { style }
+    }
+
+    pub fn plain(mut self, plain: bool) -> Self {
+        if plain { self.borders = Borders::NONE; }
+        self
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let (dr, dg, db) = (r1 as i32 - r2 as i32, g1 as i32 - g2 as i32, b1 as i32 - b2 as i32);
+    (dr * dr * 2 + dg * dg * 4 + db * db * 3) as u32
+}
+
+fn nearest_level(v: u8) -> usize {
+    CUBE_LEVELS.iter().enumerate().min_by_key(|(_, l)| (**l as i32 - v as i32).abs()).map(|(i, _)| i).unwrap_or(0)
+}
+
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let avg = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+    let gray_index = if avg < 8 { 0 } else { ((avg as u32 - 8) / 10).min(23) as u8 };
+    let gray_value = 8 + gray_index * 10;
+    if distance((r, g, b), (gray_value, gray_value, gray_value)) < distance((r, g, b), cube) {
+        232 + gray_index
+    } else {
+        16 + (36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+fn indexed_to_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => ANSI16[i as usize].1,
+        16..=231 => { let n = i - 16; (CUBE_LEVELS[(n / 36) as usize], CUBE_LEVELS[((n / 6) % 6) as usize], CUBE_LEVELS[(n % 6) as usize]) }
+        _ => { let v = 8 + (i - 232) * 10; (v, v, v) }
+    }
+}
+
+fn rgb_to_16(r: u8, g: u8, b: u8) -> Color {
+    let saturated = r.max(g).max(b) - r.min(g).min(b) > 60;
+    ANSI16.iter()
+        .filter(|(c, _)| !saturated || !matches!(c, Color::Black | Color::Gray | Color::DarkGray | Color::White))
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map(|(c, _)| *c)
+        .unwrap_or(Color::White)
+}
@@ -0,0 +1,118 @@
+use std::{fs, io, path::{Path, PathBuf}, process::{Command, Stdio}, time::UNIX_EPOCH};
+use crate::{finder, media::Kind};
+
+pub const SIZE: u32 = 256;
+
+pub fn get(path: &Path, kind: Kind) -> Option<PathBuf> {
+    let source = fs::canonicalize(path).ok()?;
+    let mtime = fs::metadata(&source).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let dir = cache_dir()?;
+    if source.starts_with(&dir) { return None; }
+    let uri = file_uri(&source);
+    let target = dir.join(format!("{}.png", hex(&md5(uri.as_bytes()))));
+    if fs::read(&target).is_ok_and(|png| text_chunk(&png, "Thumb::MTime").is_some_and(|t| t == mtime.to_string())) { return Some(target); }
+    fs::create_dir_all(&dir).ok()?;
+    let partial = dir.join(format!("quickfind-{}-{}.png", std::process::id(), hex(&md5(uri.as_bytes()))));
+    let made = generate(&source, &partial, kind) && stamp(&partial, &uri, mtime).is_ok() && fs::rename(&partial, &target).is_ok();
+    let _ = fs::remove_file(&partial);
+    made.then_some(target)
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    Some(dirs_next::cache_dir()?.join("thumbnails").join("large"))
+}
+
+fn generate(source: &Path, output: &Path, kind: Kind) -> bool {
+    let size = SIZE.to_string();
+    let box_size = format!("{}x{}>", SIZE, SIZE);
+    let first_frame = format!("{}[0]", source.display());
+    let tools: Vec<(&str, Vec<&std::ffi::OsStr>)> = match kind {
+        Kind::Image => vec![
+            ("gdk-pixbuf-thumbnailer", vec!["-s".as_ref(), size.as_ref(), source.as_ref(), output.as_ref()]),
+            ("magick", vec![first_frame.as_ref(), "-thumbnail".as_ref(), box_size.as_ref(), output.as_ref()]),
+            ("convert", vec![first_frame.as_ref(), "-thumbnail".as_ref(), box_size.as_ref(), output.as_ref()]),
+            ("vipsthumbnail", vec![source.as_ref(), "--size".as_ref(), size.as_ref(), "-o".as_ref(), output.as_ref()]),
+        ],
+        Kind::Video => vec![("ffmpegthumbnailer", vec!["-i".as_ref(), source.as_ref(), "-o".as_ref(), output.as_ref(), "-s".as_ref(), size.as_ref()])],
+        Kind::Audio => Vec::new(),
+    };
+    tools.into_iter().filter(|(program, _)| finder::on_path(program)).any(|(program, args)| {
+        Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok_and(|s| s.success())
+            && fs::read(output).is_ok_and(|png| png.starts_with(b"\x89PNG\r\n\x1a\n"))
+    })
+}
+
+fn file_uri(path: &Path) -> String {
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let raw = if raw.starts_with('/') { raw } else { format!("/{}", raw) };
+    let encoded: String = raw.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b'!' | b'*' | b'\'' | b'(' | b')' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect();
+    format!("file://{}", encoded)
+}
+
+fn text_chunk(png: &[u8], key: &str) -> Option<String> {
+    let mut at = 8;
+    while at + 12 <= png.len() {
+        let length = u32::from_be_bytes(png[at..at + 4].try_into().ok()?) as usize;
+        let (kind, data) = (&png[at + 4..at + 8], png.get(at + 8..at + 8 + length)?);
+        if kind == b"IDAT" { return None; }
+        if kind == b"tEXt" && let Some((name, value)) = data.split_at_checked(key.len()) && name == key.as_bytes() && value.first() == Some(&0) {
+            return Some(String::from_utf8_lossy(&value[1..]).into_owned());
+        }
+        at += 12 + length;
+    }
+    None
+}
+
+fn stamp(png: &Path, uri: &str, mtime: u64) -> io::Result<()> {
+    let data = fs::read(png)?;
+    let header_end = 8 + 12 + u32::from_be_bytes(data.get(8..12).and_then(|b| b.try_into().ok()).unwrap_or_default()) as usize;
+    if data.get(12..16) != Some(b"IHDR") || header_end > data.len() { return Err(io::Error::new(io::ErrorKind::InvalidData, "not a PNG thumbnail")); }
+    let mut out = data[..header_end].to_vec();
+    for (key, value) in [("Thumb::URI", uri.to_string()), ("Thumb::MTime", mtime.to_string()), ("Software", "QuickFind".to_string())] {
+        let body = [b"tEXt".as_slice(), key.as_bytes(), &[0], value.as_bytes()].concat();
+        out.extend((body.len() as u32 - 4).to_be_bytes());
+        out.extend(&body);
+        out.extend(crc32(&body).to_be_bytes());
+    }
+    out.extend(&data[header_end..]);
+    fs::write(png, out)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, b| (0..8).fold(crc ^ *b as u32, |c, _| if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 }))
+}
+
+fn md5(input: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    let constants: Vec<u32> = (0..64).map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32).collect();
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 { message.push(0); }
+    message.extend((input.len() as u64).wrapping_mul(8).to_le_bytes());
+    let mut state: [u32; 4] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476];
+    for block in message.chunks_exact(64) {
+        let words: Vec<u32> = block.chunks_exact(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(constants[i]).wrapping_add(words[g]).rotate_left(SHIFTS[i / 16 * 4 + i % 4]);
+            (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+        }
+        for (word, add) in state.iter_mut().zip([a, b, c, d]) { *word = word.wrapping_add(add); }
+    }
+    let mut digest = [0u8; 16];
+    for (chunk, word) in digest.chunks_exact_mut(4).zip(state) { chunk.copy_from_slice(&word.to_le_bytes()); }
+    digest
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,58 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::config::Document;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeStyle {
+    Absolute,
+    Relative { max_days: u64 },
+}
+
+impl TimeStyle {
+    pub fn from_document(doc: &Document) -> Option<TimeStyle> {
+        match doc.str("listing", "time")? {
+            "relative" => Some(TimeStyle::Relative { max_days: doc.int("listing", "relative_days").unwrap_or(7).max(0) as u64 }),
+            "absolute" => Some(TimeStyle::Absolute),
+            _ => None,
+        }
+    }
+
+    pub fn format(&self, time: SystemTime, now: SystemTime) -> String {
+        if let TimeStyle::Relative { max_days } = *self && let Ok(age) = now.duration_since(time) {
+            let (secs, days) = (age.as_secs(), age.as_secs() / 86_400);
+            if secs < 60 { return "just now".to_string(); }
+            if secs < 3_600 { return format!("{} min ago", secs / 60); }
+            if secs < 86_400 { return format!("{} h ago", secs / 3_600); }
+            if days <= max_days { return format!("{} day{} ago", days, if days == 1 { "" } else { "s" }); }
+        }
+        let secs = match time.duration_since(UNIX_EPOCH) { Ok(d) => d.as_secs() as i64, Err(e) => -(e.duration().as_secs() as i64) };
+        let (year, month, day, hour, minute) = local_parts(secs);
+        format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, hour, minute)
+    }
+}
+
+#[cfg(unix)]
+fn local_parts(secs: i64) -> (i64, u32, u32, u32, u32) {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let time = secs as libc::time_t;
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() { return utc_parts(secs); }
+    (tm.tm_year as i64 + 1900, tm.tm_mon as u32 + 1, tm.tm_mday as u32, tm.tm_hour as u32, tm.tm_min as u32)
+}
+
+#[cfg(not(unix))]
+fn local_parts(secs: i64) -> (i64, u32, u32, u32, u32) {
+    utc_parts(secs)
+}
+
+fn utc_parts(secs: i64) -> (i64, u32, u32, u32, u32) {
+    let (days, rest) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, (rest / 3_600) as u32, (rest % 3_600 / 60) as u32)
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+pub const NAME: &str = if cfg!(windows) { "Recycle Bin" } else { "Trash" };
+
+pub fn available() -> bool {
+    cfg!(windows) || cfg!(target_os = "macos")
+}
+
+#[cfg(windows)]
+pub fn trash(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let script = "Add-Type -AssemblyName Microsoft.VisualBasic; $p = $env:QF_TRASH_PATH; \
+        if (Test-Path -LiteralPath $p -PathType Container) { [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteDirectory($p, 'OnlyErrorDialogs', 'SendToRecycleBin') } \
+        else { [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile($p, 'OnlyErrorDialogs', 'SendToRecycleBin') }";
+    let output = std::process::Command::new("powershell").args(["-NoProfile", "-NonInteractive", "-Command", script]).env("QF_TRASH_PATH", path).output()?;
+    if !output.status.success() { return Err(format!("Recycle Bin: {}", String::from_utf8_lossy(&output.stderr).lines().next().unwrap_or("failed")).into()); }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn trash(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let finder = std::process::Command::new("osascript")
+        .args(["-e", "on run argv", "-e", "tell application \"Finder\" to delete (POSIX file (item 1 of argv) as alias)", "-e", "end run"])
+        .arg(path)
+        .output()?;
+    if finder.status.success() { return Ok(()); }
+    let trash_dir = dirs_next::home_dir().ok_or("no home directory")?.join(".Trash");
+    let name = path.file_name().ok_or("nothing to trash")?.to_string_lossy().into_owned();
+    let mut target = trash_dir.join(&name);
+    let mut copy = 1;
+    while target.exists() {
+        copy += 1;
+        target = trash_dir.join(format!("{} {}", name, copy));
+    }
+    std::fs::rename(path, &target).map_err(|e| format!("Trash: {}", e).into())
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn trash(_: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    Err("no trash support on this platform".into())
+}
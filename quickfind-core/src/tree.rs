@@ -0,0 +1,63 @@
+use std::{fs::{self, FileType}, io::{self, Write}, path::Path};
+use crate::{finder::ScanLimits, listing::ListingSettings};
+
+#[derive(Default)]
+pub struct Counts {
+    pub dirs: usize,
+    pub files: usize,
+}
+
+impl Counts {
+    pub fn summary(&self) -> String {
+        format!("{} director{}, {} file{}", self.dirs, if self.dirs == 1 { "y" } else { "ies" }, self.files, if self.files == 1 { "" } else { "s" })
+    }
+}
+
+pub fn parse_args(args: &[String], limits: &mut ScanLimits) -> Result<Option<String>, String> {
+    let (mut file, mut args) = (None, args.iter());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-L" | "--depth" => {
+                let depth: usize = args.next().and_then(|d| d.parse().ok()).ok_or(format!("{} needs a number", arg))?;
+                limits.max_depth = (depth > 0).then_some(depth);
+            }
+            "-I" | "--ignore" => limits.ignore.push(args.next().ok_or(format!("{} needs a glob", arg))?.clone()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option {} (use -L depth, -I glob)", flag)),
+            _ if file.is_some() => return Err("only one output file can be given".to_string()),
+            name => file = Some(name.to_string()),
+        }
+    }
+    Ok(file)
+}
+
+pub fn write(root: &Path, limits: &ScanLimits, out: &mut dyn Write) -> io::Result<Counts> {
+    let mut counts = Counts::default();
+    writeln!(out, "{}", root.file_name().map_or_else(|| root.display().to_string(), |name| name.to_string_lossy().into_owned()))?;
+    branch(root, root, "", 0, limits, out, &mut counts)?;
+    writeln!(out, "\n{}", counts.summary())?;
+    Ok(counts)
+}
+
+fn branch(root: &Path, dir: &Path, prefix: &str, depth: usize, limits: &ScanLimits, out: &mut dyn Write, counts: &mut Counts) -> io::Result<()> {
+    if limits.max_depth.is_some_and(|max| depth >= max) { return Ok(()); }
+    let Ok(read) = fs::read_dir(dir) else { return Ok(()) };
+    let mut children: Vec<(String, FileType)> = read.flatten().filter_map(|entry| {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let kind = entry.file_type().ok()?;
+        let path = entry.path();
+        let hidden = name.starts_with('.') || limits.ignores(path.strip_prefix(root).unwrap_or(&path)) || kind.is_dir() && limits.skips(&name);
+        (!hidden).then_some((name, kind))
+    }).collect();
+    let order = ListingSettings::default();
+    children.sort_by(|(a, _), (b, _)| order.compare_names(a, b));
+    for (i, (name, kind)) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let path = dir.join(name);
+        let target = if kind.is_symlink() { fs::read_link(&path).map(|t| format!(" -> {}", t.display())).unwrap_or_default() } else { String::new() };
+        writeln!(out, "{}{}{}{}", prefix, if last { "└── " } else { "├── " }, name, target)?;
+        if !kind.is_dir() { counts.files += 1; continue }
+        counts.dirs += 1;
+        branch(root, &path, &format!("{}{}", prefix, if last { "    " } else { "│   " }), depth + 1, limits, out, counts)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,970 @@
+use tui::{
+    backend::{Backend, TestBackend},
+    buffer::Buffer,
+    Frame, Terminal,
+    widgets::{Block, Paragraph, List, ListItem, ListState, Clear, Widget, Wrap},
+    layout::{Layout, Constraint, Direction, Alignment, Rect},
+    style::{Color, Style, Modifier},
+    text::{Spans, Span},
+};
+use std::{ops::Range, time::SystemTime};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::{actions::Action, compare::Difference, graphics::{self, Placement}, i18n::{tr, trf}, jobs::Status, listing::{Column, Group}, media::{self, Kind}, pretty::{self, Line, Token}, preview::{Body, HEX_WIDTH, Jump, Previewer}, search::{MatchOptions, Matcher, Results}, state::{AppState, Diff, PopupMode}, statusline, sync::Operation, tags, theme::Theme, timefmt::TimeStyle, users, vfs::{self, Meta, human_size}, xattr};
+
+const MIN_WIDTH: u16 = 30;
+const MIN_HEIGHT: u16 = 8;
+const TABLE_CELL_WIDTH: usize = 24;
+const FOLD_LINES: usize = 20;
+
+pub fn render(app_state: &mut AppState, width: u16, height: u16) -> std::io::Result<Buffer> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|f| draw(f, app_state))?;
+    Ok(terminal.backend().buffer().clone())
+}
+
+pub fn snapshot(app_state: &mut AppState, width: u16, height: u16) -> std::io::Result<String> {
+    let buffer = render(app_state, width, height)?;
+    let rows = buffer.content.chunks(width.max(1) as usize).map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect::<String>().trim_end().to_string());
+    Ok(rows.collect::<Vec<_>>().join("\n"))
+}
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app_state: &mut AppState) {
+    draw_panels(f, app_state);
+    let theme = &app_state.theme;
+    if theme.background != Color::Reset || theme.foreground != Color::Reset { f.render_widget(Backdrop { fg: theme.foreground, bg: theme.background }, f.size()); }
+}
+
+struct Backdrop {
+    fg: Color,
+    bg: Color,
+}
+
+impl Widget for Backdrop {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                if cell.fg == Color::Reset { cell.fg = self.fg; }
+                if cell.bg == Color::Reset { cell.bg = self.bg; }
+            }
+        }
+    }
+}
+
+fn draw_panels<B: Backend>(f: &mut Frame<B>, app_state: &mut AppState) {
+    let size = f.size();
+    if (size.width < MIN_WIDTH || size.height < MIN_HEIGHT) && !app_state.zen { return render_too_small(f, &app_state.theme); }
+    if app_state.popup_mode == PopupMode::Diff && let Some(diff) = app_state.diff.as_mut() { app_state.previewer.placement = None; return render_diff(f, size, diff, &app_state.theme); }
+    if app_state.zen { return draw_zen(f, app_state); }
+    let panels = &app_state.config.panels;
+    let (show_path, show_help) = (panels.path, panels.help);
+    let chunks = match show_path || show_help {
+        true => Layout::default().direction(Direction::Vertical).constraints([Constraint::Percentage(panels.list_height), Constraint::Percentage(100 - panels.list_height)]).split(size),
+        false => vec![size],
+    };
+    let theme = &app_state.theme;
+    let filter = Matcher::new(&tags::split_query(&app_state.filter).1, app_state.config.search).ok();
+    let visual = app_state.visual_range();
+    let local = app_state.fs.is_local();
+    let plain = app_state.config.accessible;
+    let results = app_state.results.as_ref().filter(|r| r.visible);
+    let preview = results.is_none() && app_state.show_preview;
+    let panes = match results.is_some() || preview {
+        true => Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(50), Constraint::Percentage(50)]).split(chunks[0]),
+        false => vec![chunks[0]],
+    };
+    let row_width = panes[0].width.saturating_sub(6) as usize;
+    let now = SystemTime::now();
+    let numbers = if app_state.listing.number || app_state.listing.relative_number { app_state.entries.len().max(1).to_string().len() + 1 } else { 0 };
+    let gutter = numbers + if app_state.marks.is_empty() && visual.is_none() { 0 } else { 2 };
+    let columns = app_state.listing.fit(row_width.saturating_sub(gutter));
+    let name_at = columns.iter().position(|c| *c == Column::Name).unwrap_or(0);
+    let fixed: usize = columns.iter().filter(|c| **c != Column::Name).map(|c| app_state.listing.width(*c) + 2).sum();
+    let name_width = row_width.saturating_sub(gutter + fixed);
+    let list_items: Vec<ListItem> = app_state.entries.iter().enumerate().map(|(i, entry)| {
+        let style = theme.entry(app_state.dir_names.contains(entry));
+        let mut name = highlighted(entry, &filter.as_ref().map(|m| m.ranges(entry)).unwrap_or_default(), style, theme);
+        if theme.monochrome && app_state.dir_names.contains(entry) { name.push(Span::styled("/", style)); }
+        if app_state.listing.dir_counts && app_state.dir_names.contains(entry) && let Some(count) = app_state.dir_counts.get(&app_state.focus_dir.join(entry)) { name.push(Span::styled(format!("  {}", count), Style::default().fg(theme.hint))); }
+        if local && app_state.favorites.contains(&app_state.focus_dir.join(entry)) { name.push(Span::styled(if plain { tr(" (starred)") } else { " ★" }, Style::default().fg(theme.label))); }
+        for tag in if local { app_state.tags.get(&app_state.focus_dir.join(entry)) } else { &[] } {
+            name.push(Span::raw(" "));
+            name.push(Span::styled(format!(" {} ", tag), Style::default().fg(theme.paint(Color::Black)).bg(theme.paint(tag_color(tag))).add_modifier(if theme.monochrome { Modifier::REVERSED } else { Modifier::empty() })));
+        }
+        let mut spans = Vec::new();
+        if let Some(number) = app_state.listing.line_number(i, app_state.selected_index) { spans.push(Span::styled(format!("{:>w$} ", number, w = numbers - 1), Style::default().fg(if i == app_state.selected_index { theme.label } else { theme.hint }))); }
+        if visual.as_ref().is_some_and(|range| range.contains(&i)) { spans.push(Span::styled("~ ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))); }
+        else if app_state.is_marked(entry) { spans.push(Span::styled("+ ", Style::default().fg(theme.label).add_modifier(Modifier::BOLD))); }
+        else if gutter > numbers { spans.push(Span::raw("  ")); }
+        let meta = app_state.entry_meta.get(entry);
+        for column in &columns[..name_at] { spans.push(cell(*column, entry, meta, app_state, now)); spans.push(Span::raw("  ")); }
+        spans.extend(fit_spans(name, name_width, name_at + 1 < columns.len()));
+        for column in &columns[name_at + 1..] { spans.push(Span::raw("  ")); spans.push(cell(*column, entry, meta, app_state, now)); }
+        ListItem::new(Spans::from(spans))
+    }).collect();
+    let (list_items, selected) = if app_state.listing.group { grouped(list_items, app_state) } else { (list_items, app_state.selected_index) };
+    let rows = list_items.len();
+
+    let border_color = theme.border;
+
+    let list = List::new(list_items)
+        .block(Block::default().borders(theme.borders).border_style(Style::default().fg(border_color)).title(list_title(app_state)))
+        .highlight_style(theme.selected())
+        .highlight_symbol(if plain { "> " } else { " #  " });
+
+    let help_text = vec![
+        Spans::from(vec![Span::styled(tr("Navigation: "), Style::default().fg(theme.label)), Span::raw(tr("↑/↓ Select | ←/→ Navigate | Enter Exit"))]),
+        Spans::from(vec![Span::styled(tr("File Ops: "), Style::default().fg(theme.accent)), Span::raw(tr("N New File | Shift+N New Dir | D Delete | Shift+G Group Marks"))]),
+        Spans::from(vec![Span::raw(tr("R Rename | T Theme | : Commands | f Find | g Grep | Shift+F Results | Ctrl+F Fuzzy"))]),
+    ];
+
+    let help_display = Paragraph::new(help_text)
+        .style(Style::default().fg(border_color))
+        .block(Block::default().borders(theme.borders).border_style(Style::default().fg(border_color)).title(tr("Controls")))
+        .alignment(Alignment::Left);
+
+    let mut location = vec![Span::raw(match &app_state.config.status_format { Some(format) => statusline::render(format, app_state), None => app_state.fs.location(&app_state.focus_dir) })];
+    if app_state.config.status_format.is_none() && let Some(repo) = &app_state.repo {
+        let mut state = format!("  {}", repo.branch);
+        if repo.ahead > 0 { state.push_str(&format!(" ↑{}", repo.ahead)); }
+        if repo.behind > 0 { state.push_str(&format!(" ↓{}", repo.behind)); }
+        location.push(Span::styled(state, Style::default().fg(theme.accent)));
+        if repo.dirty || repo.untracked { location.push(Span::styled(format!(" {}", if repo.dirty { "*" } else { "?" }), Style::default().fg(theme.label))); }
+    }
+    let mut path_lines = vec![Spans::from(location)];
+    if let Some(status) = &app_state.status { path_lines.push(Spans::from(vec![Span::styled(status.as_str(), Style::default().fg(theme.hint))])); }
+    if !show_help { path_lines.extend(app_state.jobs.list.iter().filter(|job| job.status.is_active()).map(|job| Spans::from(vec![Span::styled(format!("[{}] {} {}/{} {}", job.id, job.title, job.done(), job.work.len(), tr(job.status.label())), Style::default().fg(theme.accent))]))); }
+    let path_title = if show_help { tr("Current Path").to_string() } else { format!("{} ─ {}", tr("Current Path"), tr("? for help")) };
+    let path_display = Paragraph::new(path_lines)
+        .style(Style::default().fg(border_color))
+        .block(Block::default().borders(theme.borders).border_style(Style::default().fg(border_color)).title(path_title));
+
+    let help_chunks = match (show_path, show_help) {
+        (true, true) => Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(panels.path_width), Constraint::Percentage(100 - panels.path_width)]).split(chunks[1]),
+        (true, false) => vec![chunks[1], Rect::default()],
+        (false, true) => vec![Rect::default(), chunks[1]],
+        (false, false) => vec![Rect::default(); 2],
+    };
+
+    app_state.list_state.select(Some(selected));
+    f.render_stateful_widget(list, panes[0], &mut app_state.list_state);
+    app_state.list_state.select(Some(app_state.selected_index));
+    if plain && rows > 0 {
+        let inner = Block::default().borders(theme.borders).title("").inner(panes[0]);
+        let offset = app_state.list_offset.min(rows - 1).min(selected);
+        app_state.list_offset = if selected >= offset + inner.height as usize { selected + 1 - inner.height as usize } else { offset };
+        f.set_cursor(inner.x, inner.y + (selected - app_state.list_offset) as u16);
+    }
+    if let Some(results) = results { render_results(f, panes[1], results, theme, app_state.popup_mode == PopupMode::Results); }
+    if preview { render_preview(f, panes[1], preview_lines(app_state), &mut app_state.previewer, theme, app_state.config.search, matches!(app_state.popup_mode, PopupMode::Preview | PopupMode::PreviewSearch)); }
+    if show_path { f.render_widget(path_display, help_chunks[0]); }
+    if show_help { f.render_widget(help_display, help_chunks[1]); }
+
+    if app_state.popup_mode != PopupMode::None { render_popup(f, app_state); }
+    if !preview || !matches!(app_state.popup_mode, PopupMode::None | PopupMode::Preview | PopupMode::Filter | PopupMode::Visual) { app_state.previewer.placement = None; }
+}
+
+fn render_diff<B: Backend>(f: &mut Frame<B>, area: Rect, diff: &mut Diff, theme: &Theme) {
+    let name = diff.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let title = trf("Diff: {} ({}) (s {} | ↑/↓ PgUp/PgDn scroll | Esc close)", &[&name, &tr(if diff.staged { "staged" } else { "unstaged" }), &tr(if diff.staged { "unstaged" } else { "staged" })]);
+    let block = Block::default().borders(theme.borders).title(title).border_style(Style::default().fg(theme.accent));
+    let height = block.inner(area).height as usize;
+    diff.scroll = diff.scroll.min(diff.lines.len().saturating_sub(height));
+    let lines: Vec<Spans> = if diff.lines.is_empty() {
+        vec![Spans::from(vec![Span::styled(tr(if diff.staged { "No staged changes" } else { "No unstaged changes" }), Style::default().fg(theme.hint))])]
+    } else {
+        diff.lines.iter().skip(diff.scroll).take(height).map(|line| {
+            let style = match line.as_bytes().first() {
+                _ if line.starts_with("diff ") || line.starts_with("index ") || line.starts_with("+++") || line.starts_with("---") || line.starts_with("new file") || line.starts_with("deleted file") => Style::default().fg(theme.label).add_modifier(Modifier::BOLD),
+                Some(b'@') => Style::default().fg(theme.accent),
+                Some(b'+') => Style::default().fg(theme.paint(Color::Green)),
+                Some(b'-') => Style::default().fg(theme.danger),
+                _ => Style::default(),
+            };
+            Spans::from(vec![Span::styled(line.clone(), style)])
+        }).collect()
+    };
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn render_devices<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let block = Block::default().borders(theme.borders).title(tr("Removable Devices (Enter open | u unmount | Esc)")).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    if app_state.devices.is_empty() { return f.render_widget(Paragraph::new(Span::styled(tr("No removable devices found"), Style::default().fg(theme.hint))), inner); }
+    let items: Vec<ListItem> = app_state.devices.iter().map(|device| {
+        let name = if device.label.is_empty() { device.path.clone() } else { format!("{} ({})", device.label, device.path) };
+        let state = match &device.mountpoint {
+            Some(mountpoint) => trf("  {} {} at {}", &[&device.size, &device.fstype, &mountpoint.display()]),
+            None => trf("  {} {} not mounted", &[&device.size, &device.fstype]),
+        };
+        ListItem::new(Spans::from(vec![Span::raw(name), Span::styled(state, Style::default().fg(theme.hint))]))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn render_drives<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let block = Block::default().borders(theme.borders).title(tr("Drives")).style(Style::default().fg(theme.accent));
+    let items: Vec<ListItem> = app_state.drives.iter().map(|drive| ListItem::new(drive.to_string_lossy().into_owned())).collect();
+    let list = List::new(items).block(block).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, popup_area, &mut list_state);
+}
+
+fn render_marks<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let total: u64 = app_state.mark_sizes.iter().filter_map(|(_, size)| *size).sum();
+    let unknown = if app_state.mark_sizes.iter().any(|(_, size)| size.is_none()) { "+" } else { "" };
+    let title = trf(if app_state.mark_sizes.len() == 1 { "Marked: {} item, {}{} (Space unmark | Enter reveal | Esc)" } else { "Marked: {} items, {}{} (Space unmark | Enter reveal | Esc)" }, &[&app_state.mark_sizes.len(), &human_size(total), &unknown]);
+    let block = Block::default().borders(theme.borders).title(title).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    if app_state.mark_sizes.is_empty() { return f.render_widget(Paragraph::new(Span::styled(tr("Nothing marked"), Style::default().fg(theme.hint))), inner); }
+    let items: Vec<ListItem> = app_state.mark_sizes.iter().map(|(path, size)| {
+        let size = size.map_or_else(|| "?".to_string(), human_size);
+        ListItem::new(Spans::from(vec![Span::styled(format!("{:>10}  ", size), Style::default().fg(theme.hint)), Span::raw(path.to_string_lossy().into_owned())]))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn render_comparison<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let Some(comparison) = &app_state.comparison else { return };
+    let title = trf("Compared with {}: {} differences (Enter reveal | s sync there | Esc)", &[&comparison.other.display(), &comparison.differences.len()]);
+    let block = Block::default().borders(theme.borders).title(title).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let items: Vec<ListItem> = comparison.differences.iter().map(|(name, difference)| {
+        let style = match difference {
+            Difference::OnlyHere | Difference::OnlyThere => Style::default().fg(theme.label),
+            Difference::Modified => Style::default().fg(theme.hint),
+            _ => Style::default().fg(theme.danger),
+        };
+        ListItem::new(Spans::from(vec![Span::styled(format!("{:<12}", difference.label()), style), Span::raw(name.clone())]))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn render_jobs<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let block = Block::default().borders(theme.borders).title(tr("Jobs (p pause/resume | x cancel | +/- priority | r retry | Enter report | c clear done | Esc)")).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    if app_state.jobs.list.is_empty() { return f.render_widget(Paragraph::new(Span::styled(tr("No background jobs"), Style::default().fg(theme.hint))), inner); }
+    let items: Vec<ListItem> = app_state.jobs.list.iter().map(|job| {
+        let (done, total, failed) = (job.done(), job.outcomes.len(), job.failed());
+        let filled = (done * 10).checked_div(total).unwrap_or(10);
+        let style = match job.status {
+            Status::Running => Style::default().fg(theme.label),
+            Status::Cancelled => Style::default().fg(theme.danger),
+            _ => Style::default().fg(theme.hint),
+        };
+        let mut spans = vec![
+            Span::styled(format!("{:<10}", tr(job.status.label())), style),
+            Span::styled(format!("[{}{}] {}/{}  ", "#".repeat(filled), " ".repeat(10 - filled), done, total), Style::default().fg(theme.hint)),
+            Span::raw(job.title.clone()),
+        ];
+        if failed > 0 { spans.push(Span::styled(trf("  {} failed", &[&failed]), Style::default().fg(theme.danger))); }
+        ListItem::new(Spans::from(spans))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn render_sync<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let Some(plan) = &app_state.sync_plan else { return };
+    let title = trf("Mirror into {} (y run | d delete extra: {} | Esc back)", &[&plan.target.display(), &tr(if plan.delete { "on" } else { "off" })]);
+    let block = Block::default().borders(theme.borders).title(title).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let rows = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(2), Constraint::Min(0)]).split(inner);
+    let summary: Vec<String> = [Operation::Copy, Operation::Update, Operation::Touch, Operation::Replace, Operation::Delete].iter()
+        .map(|op| (op, plan.count(*op))).filter(|(_, n)| *n > 0).map(|(op, n)| format!("{} {}", n, op.label())).collect();
+    let summary = if summary.is_empty() { tr("Nothing to do").to_string() } else { summary.join(", ") };
+    f.render_widget(Paragraph::new(Span::styled(summary, Style::default().fg(theme.label))), rows[0]);
+    let items: Vec<ListItem> = plan.steps.iter().map(|step| {
+        let style = match step.operation {
+            Operation::Delete | Operation::Replace => Style::default().fg(theme.danger),
+            Operation::Touch => Style::default().fg(theme.hint),
+            _ => Style::default().fg(theme.label),
+        };
+        ListItem::new(Spans::from(vec![Span::styled(format!("{:<9}", step.operation.label()), style), Span::raw(step.path.to_string_lossy().into_owned())]))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, rows[1], &mut list_state);
+}
+
+fn render_properties<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let Some(properties) = &app_state.properties else { return };
+    let mut rows = vec![(tr("Path"), properties.path.to_string_lossy().into_owned())];
+    match &properties.meta {
+        Some(meta) => {
+            rows.push((tr("Type"), tr(kind_name(meta.kind)).to_string()));
+            if meta.kind != 'd' { rows.push((tr("Size"), format!("{} ({} bytes)", human_size(meta.size), meta.size))); }
+            if let Some(modified) = meta.modified { rows.push((tr("Modified"), TimeStyle::Absolute.format(modified, SystemTime::now()))); }
+            if let Some(mode) = meta.mode { rows.push((tr("Permissions"), format!("{} ({:04o})  {}", mode_string(meta.kind, mode), mode & 0o7777, tr("m change")))); }
+            if let Some(uid) = meta.uid { rows.push((tr("Owner"), format!("{} ({})", account(users::user_name(uid), uid), uid))); }
+            if let Some(attributes) = meta.attributes {
+                let names: Vec<&str> = vfs::ATTRIBUTES.iter().filter(|(bit, _, _)| attributes & bit != 0).map(|(_, _, name)| *name).collect();
+                rows.push((tr("Attributes"), if names.is_empty() { tr("none").to_string() } else { names.join(", ") }));
+            }
+            if let Some(gid) = meta.gid { rows.push((tr("Group"), format!("{} ({})", account(users::group_name(gid), gid), gid))); }
+        }
+        None => rows.push((tr("Type"), tr(if properties.is_dir { "dir" } else { "file" }).to_string())),
+    }
+    if let Some(target) = &properties.link_target { rows.push((tr("Target"), target.to_string_lossy().into_owned())); }
+    let mut lines: Vec<Spans> = rows.into_iter().map(|(label, value)| Spans::from(vec![Span::styled(format!("{:<12}", label), Style::default().fg(theme.label)), Span::raw(value)])).collect();
+    if !properties.streams.is_empty() {
+        lines.push(Spans::from(vec![]));
+        lines.push(Spans::from(vec![Span::styled(tr("Alternate data streams (v view | d delete)"), Style::default().fg(theme.label))]));
+        for (i, (name, size)) in properties.streams.iter().enumerate() {
+            let style = if i == app_state.popup_index { theme.selected() } else { Style::default() };
+            lines.push(Spans::from(vec![Span::styled(format!("{} {}", if i == app_state.popup_index { ">" } else { " " }, name), style), Span::styled(format!("  {}", human_size(*size)), Style::default().fg(theme.hint))]));
+        }
+    }
+    if xattr::SUPPORTED && app_state.fs.is_local() {
+        lines.push(Spans::from(vec![]));
+        lines.push(Spans::from(vec![Span::styled(tr("Extended attributes (a add | d delete)"), Style::default().fg(theme.label))]));
+        if properties.xattrs.is_empty() { lines.push(Spans::from(vec![Span::styled(tr("  none"), Style::default().fg(theme.hint))])); }
+        for (i, (name, value)) in properties.xattrs.iter().enumerate() {
+            let selected = properties.streams.len() + i == app_state.popup_index;
+            let style = if selected { theme.selected() } else { Style::default() };
+            lines.push(Spans::from(vec![Span::styled(format!("{} {}", if selected { ">" } else { " " }, name), style), Span::styled(format!("  {}", xattr::display(value)), Style::default().fg(theme.hint))]));
+        }
+    }
+    if let Some(preview) = &properties.preview {
+        lines.push(Spans::from(vec![]));
+        lines.extend(preview.lines().map(|line| Spans::from(vec![Span::styled(line.to_string(), Style::default().fg(theme.input))])));
+    }
+    let popup = Paragraph::new(lines)
+        .block(Block::default().borders(theme.borders).title(tr("Properties (Esc close)")).style(Style::default().fg(theme.accent)))
+        .wrap(Wrap { trim: false });
+    f.render_widget(popup, popup_area);
+}
+
+fn render_favorites<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let block = Block::default().borders(theme.borders).title(tr("Favorites (Enter go | Space unstar | Esc)")).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let items: Vec<ListItem> = app_state.favorites.iter().map(|path| {
+        let style = theme.entry(path.is_dir());
+        let missing = if path.symlink_metadata().is_err() { tr("  (missing)") } else { "" };
+        ListItem::new(Spans::from(vec![Span::styled(path.to_string_lossy().into_owned(), style), Span::styled(missing, Style::default().fg(theme.hint))]))
+    }).collect();
+    if items.is_empty() { return f.render_widget(Paragraph::new(Span::styled(tr("No favorites yet (s stars the selected entry)"), Style::default().fg(theme.hint))), inner); }
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn render_report<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let failed = app_state.report.iter().filter(|o| !o.success()).count();
+    let retry = if failed > 0 && app_state.report_job.is_some() { tr("r retry failed | ") } else { "" };
+    let title = trf("{}: {} ok, {} failed ({}Esc close)", &[&app_state.report_title, &(app_state.report.len() - failed), &failed, &retry]);
+    let block = Block::default().borders(theme.borders).title(title).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let items: Vec<ListItem> = app_state.report.iter().map(|outcome| {
+        let status = match &outcome.code {
+            Ok(Some(0)) => Span::styled("  ok    ", Style::default().fg(theme.label)),
+            Ok(Some(code)) => Span::styled(format!("exit {:<3} ", code), Style::default().fg(theme.danger)),
+            Ok(None) => Span::styled(tr("killed  "), Style::default().fg(theme.danger)),
+            Err(_) => Span::styled(tr("error   "), Style::default().fg(theme.danger)),
+        };
+        let detail = match &outcome.code { Err(e) => e.as_str(), Ok(_) => outcome.output.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("") };
+        ListItem::new(Spans::from(vec![status, Span::raw(outcome.path.to_string_lossy().into_owned()), Span::styled(format!("  {}", detail.trim()), Style::default().fg(theme.hint))]))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(app_state.popup_index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn cell(column: Column, entry: &str, meta: Option<&Meta>, app_state: &AppState, now: SystemTime) -> Span<'static> {
+    let theme = &app_state.theme;
+    let width = app_state.listing.width(column);
+    let hint = Style::default().fg(theme.hint);
+    let (text, style, right) = match (column, meta) {
+        (Column::Git, _) => {
+            let code = app_state.git_status.get(entry).map(String::as_str).unwrap_or("");
+            let staged = code.chars().next().is_some_and(|x| x != ' ' && x != '?');
+            (code.to_string(), Style::default().fg(if staged { theme.label } else { theme.danger }), false)
+        }
+        (_, None) | (Column::Name, _) => (String::new(), hint, false),
+        (Column::Size, Some(meta)) => (if meta.kind == 'd' { "-".to_string() } else { human_size(meta.size) }, hint, true),
+        (Column::Modified, Some(meta)) => (meta.modified.map(|t| app_state.listing.time.format(t, now)).unwrap_or_default(), hint, true),
+        (Column::Perms, Some(meta)) => (meta.mode.map(|mode| mode_string(meta.kind, mode)).or_else(|| meta.attributes.map(|a| attribute_string(meta.kind, a))).unwrap_or_default(), hint, false),
+        (Column::Owner, Some(meta)) => (match (meta.uid, meta.gid) { (Some(uid), Some(gid)) => format!("{}:{}", account(users::user_name(uid), uid), account(users::group_name(gid), gid)), _ => String::new() }, hint, false),
+        (Column::Type, Some(meta)) => (kind_name(meta.kind).to_string(), hint, false),
+    };
+    let text = truncate(&text, width);
+    let pad = " ".repeat(width.saturating_sub(text.width()));
+    Span::styled(if right { pad + &text } else { text + &pad }, style)
+}
+
+fn account(name: Option<String>, id: u32) -> String {
+    name.unwrap_or_else(|| id.to_string())
+}
+
+fn mode_string(kind: char, mode: u32) -> String {
+    let mut text = String::from(if kind == '?' { '-' } else { kind });
+    for (i, c) in "rwxrwxrwx".chars().enumerate() { text.push(if mode & (1 << (8 - i)) != 0 { c } else { '-' }); }
+    text
+}
+
+fn attribute_string(kind: char, attributes: u32) -> String {
+    let mut text = String::from(if kind == '?' { '-' } else { kind });
+    for (bit, letter, _) in vfs::ATTRIBUTES { text.push(if attributes & bit != 0 { letter } else { '-' }); }
+    text
+}
+
+fn kind_name(kind: char) -> &'static str {
+    match kind {
+        'd' => "dir",
+        'l' => "link",
+        '-' => "file",
+        'p' => "fifo",
+        's' => "sock",
+        'b' | 'c' => "dev",
+        _ => "",
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width { return text.to_string(); }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width { break; }
+        out.push(c);
+        used += w;
+    }
+    if width > 0 { out.push('…'); }
+    out
+}
+
+fn fit_spans(spans: Vec<Span<'static>>, width: usize, pad: bool) -> Vec<Span<'static>> {
+    let total: usize = spans.iter().map(Span::width).sum();
+    if total <= width {
+        let mut spans = spans;
+        if pad && total < width { spans.push(Span::raw(" ".repeat(width - total))); }
+        return spans;
+    }
+    let mut fitted = Vec::new();
+    let mut used = 0;
+    for span in spans {
+        let room = width.saturating_sub(used);
+        if span.width() < room { used += span.width(); fitted.push(span); continue; }
+        let text = truncate(&span.content, room);
+        used += text.width();
+        fitted.push(Span::styled(text, span.style));
+        break;
+    }
+    if pad && used < width { fitted.push(Span::raw(" ".repeat(width - used))); }
+    fitted
+}
+
+fn tag_color(tag: &str) -> Color {
+    const PALETTE: [Color; 6] = [Color::Cyan, Color::Yellow, Color::Magenta, Color::Green, Color::Blue, Color::Red];
+    PALETTE[tag.bytes().fold(0usize, |hash, b| hash.wrapping_mul(31).wrapping_add(b as usize)) % PALETTE.len()]
+}
+
+fn preview_lines(app_state: &AppState) -> (String, Vec<Spans<'static>>, bool, Option<Placement>) {
+    let theme = &app_state.theme;
+    let selected = app_state.get_selected_path();
+    let preview = app_state.previewer.current.as_ref().filter(|p| Some(&p.path) == selected.as_ref());
+    let title = selected.as_ref().and_then(|p| p.file_name()).map(|n| trf("Preview: {}", &[&n.to_string_lossy()])).unwrap_or_else(|| tr("Preview").to_string());
+    let title = match preview.map(|p| &p.body) {
+        Some(Body::Table(_)) => trf("{} (Shift+←/→ columns)", &[&title]),
+        Some(Body::Structured(_)) => format!("{} (z {})", title, tr(if app_state.previewer.unfolded { "fold" } else { "unfold" })),
+        _ => title,
+    };
+    let hint = |text: &str| vec![Spans::from(vec![Span::styled(text.to_string(), Style::default().fg(theme.hint))])];
+    let mut image = None;
+    let lines = match preview {
+        _ if !app_state.fs.is_local() => hint(tr("Preview is only available for local files")),
+        _ if selected.is_none() => hint(tr("Nothing selected")),
+        None => hint(tr("Loading...")),
+        Some(preview) => {
+            let mut lines: Vec<Spans> = preview.info.iter().map(|(label, value)| Spans::from(vec![Span::styled(format!("{:<12}", label), Style::default().fg(theme.label)), Span::raw(value.clone())])).collect();
+            if !lines.is_empty() { lines.push(Spans::from(vec![])); }
+            let source = preview.thumbnail.clone().or_else(|| Some(preview.path.clone()).filter(|p| media::kind(p) == Some(Kind::Image)));
+            let overlay = app_state.graphics.and_then(|protocol| source.filter(|source| graphics::accepts(protocol, source)));
+            match &preview.body {
+                Body::Text(text) => lines.extend(text.iter().map(|line| Spans::from(vec![Span::styled(line.clone(), Style::default().fg(theme.file))]))),
+                Body::Structured(structured) => lines.extend(structured_lines(structured, app_state.previewer.unfolded, theme)),
+                Body::Archive(entries) => lines.extend(entries.iter().map(|entry| {
+                    let size = |bytes: Option<u64>| bytes.filter(|_| !entry.is_dir).map(human_size).unwrap_or_default();
+                    let mut sizes = format!("{:>9}  ", size(Some(entry.size)));
+                    if entry.compressed.is_some() { sizes = format!("{}{:>9}  ", sizes, size(entry.compressed)); }
+                    Spans::from(vec![Span::styled(sizes, Style::default().fg(theme.hint)), Span::styled(entry.name.clone(), theme.entry(entry.is_dir))])
+                })),
+                Body::Table(rows) => lines.extend(table_lines(rows, app_state.previewer.column, theme)),
+                Body::Listing(names) => lines.extend(names.iter().map(|name| Spans::from(vec![Span::styled(name.clone(), theme.entry(name.ends_with('/')))]))),
+                Body::Hex(bytes) => lines.extend(bytes.chunks(HEX_WIDTH).enumerate().map(|(i, row)| Spans::from(vec![
+                    Span::styled(format!("{:08x}  ", i * HEX_WIDTH), Style::default().fg(theme.hint)),
+                    Span::styled(format!("{:<w$}  ", row.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" "), w = HEX_WIDTH * 3 - 1), Style::default().fg(theme.file)),
+                    Span::styled(row.iter().map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' }).collect::<String>(), Style::default().fg(theme.label)),
+                ]))),
+                Body::Image(art) if overlay.is_some() => {
+                    image = overlay.map(|image| Placement { image, x: 0, y: lines.len() as u16, columns: art.iter().map(Vec::len).max().unwrap_or(0) as u16, rows: art.len() as u16 });
+                    lines.extend(art.iter().map(|_| Spans::from(vec![])));
+                }
+                Body::Image(art) => lines.extend(art.iter().map(|row| {
+                    let mut spans: Vec<Span> = Vec::new();
+                    let mut run: Option<(Style, String)> = None;
+                    for cell in row {
+                        let mut style = Style::default();
+                        if let Some(fg) = cell.fg { style = style.fg(app_state.color_depth.adapt(fg)); }
+                        if let Some(bg) = cell.bg { style = style.bg(app_state.color_depth.adapt(bg)); }
+                        match &mut run {
+                            Some((current, text)) if *current == style => text.push(cell.symbol),
+                            _ => { spans.extend(run.take().map(|(style, text)| Span::styled(text, style))); run = Some((style, cell.symbol.to_string())); }
+                        }
+                    }
+                    spans.extend(run.map(|(style, text)| Span::styled(text, style)));
+                    Spans::from(spans)
+                })),
+                Body::Binary => lines.extend(hint(tr("Binary file - press x for hex"))),
+                Body::Empty if preview.info.is_empty() => lines.extend(hint(tr("Empty"))),
+                Body::Empty => {}
+                Body::Error(e) => lines.push(Spans::from(vec![Span::styled(e.clone(), Style::default().fg(theme.danger))])),
+            }
+            if !preview.can_grow(app_state.previewer.limit) && preview.loaded < preview.size && matches!(preview.body, Body::Text(_) | Body::Hex(_)) {
+                lines.extend(hint(&trf("... preview stops after {} of {} (set max_bytes under [preview])", &[&human_size(preview.loaded), &human_size(preview.size)])));
+            }
+            lines
+        }
+    };
+    (title, lines, preview.is_some(), image)
+}
+
+fn render_preview<B: Backend>(f: &mut Frame<B>, area: Rect, (title, mut lines, ready, image): (String, Vec<Spans<'static>>, bool, Option<Placement>), previewer: &mut Previewer, theme: &Theme, options: MatchOptions, focused: bool) {
+    let block = Block::default().borders(theme.borders).border_style(Style::default().fg(if focused { theme.accent } else { theme.border }));
+    let height = block.inner(area).height as usize;
+    (previewer.width, previewer.height) = (block.inner(area).width as usize, height);
+    let matcher = Matcher::new(&previewer.query, options).ok().filter(|_| !previewer.query.is_empty());
+    let mut matches = Vec::new();
+    if let Some(matcher) = &matcher {
+        for (i, line) in lines.iter_mut().enumerate() {
+            let text: String = line.0.iter().map(|span| span.content.as_ref()).collect();
+            let ranges = matcher.ranges(&text);
+            if ranges.is_empty() { continue; }
+            matches.push(i);
+            *line = mark_ranges(std::mem::take(&mut line.0), &ranges, theme);
+        }
+    }
+    if ready && !matches.is_empty() && let Some(jump) = previewer.jump.take() {
+        let target = match jump {
+            Jump::First => matches.iter().find(|m| **m >= previewer.scroll),
+            Jump::Next => matches.iter().find(|m| **m > previewer.scroll),
+            Jump::Previous => matches.iter().rev().find(|m| **m < previewer.scroll),
+        };
+        previewer.scroll = *target.unwrap_or(if jump == Jump::Previous { &matches[matches.len() - 1] } else { &matches[0] });
+    }
+    if ready { previewer.scroll = previewer.scroll.min(lines.len().saturating_sub(height)); }
+    let mut title = title;
+    if matcher.is_some() {
+        let current = matches.iter().filter(|m| **m <= previewer.scroll).count();
+        title = format!("{} /{} {}/{}", title, previewer.query, current, matches.len());
+    }
+    if focused { title = trf("{} (↑/↓ scroll | / search | n/N next | v pager | Tab back)", &[&title]); }
+    let scroll = previewer.scroll.min(lines.len().saturating_sub(height)).min(u16::MAX as usize) as u16;
+    let inner = block.inner(area);
+    previewer.placement = image.filter(|image| image.y >= scroll).map(|image| {
+        let y = inner.y + image.y - scroll;
+        Placement { x: inner.x, y, columns: image.columns.min(inner.width), rows: image.rows.min(inner.bottom().saturating_sub(y)), ..image }
+    }).filter(|p| p.columns > 0 && p.rows > 0);
+    let paragraph = Paragraph::new(lines).block(block.title(title)).scroll((scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn mark_ranges(spans: Vec<Span<'static>>, ranges: &[Range<usize>], theme: &Theme) -> Spans<'static> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for span in spans {
+        let (text, style) = (span.content.into_owned(), span.style);
+        let end = offset + text.len();
+        let mut at = 0;
+        for range in ranges.iter().filter(|r| r.start < end && r.end > offset) {
+            let (start, stop) = (range.start.max(offset) - offset, range.end.min(end) - offset);
+            if start > at { out.push(Span::styled(text[at..start].to_string(), style)); }
+            out.push(Span::styled(text[start..stop].to_string(), style.fg(theme.selection).bg(theme.matched).add_modifier(if theme.monochrome { Modifier::BOLD | Modifier::UNDERLINED } else { Modifier::BOLD })));
+            at = stop;
+        }
+        if at < text.len() { out.push(Span::styled(text[at..].to_string(), style)); }
+        offset = end;
+    }
+    Spans::from(out)
+}
+
+fn structured_lines(lines: &[Line], unfolded: bool, theme: &Theme) -> Vec<Spans<'static>> {
+    let folded;
+    let lines = if unfolded { lines } else { folded = pretty::fold(lines, FOLD_LINES); &folded };
+    lines.iter().map(|line| {
+        let mut spans = vec![Span::raw("  ".repeat(line.indent))];
+        spans.extend(line.tokens.iter().map(|(token, text)| {
+            let style = match token {
+                Token::Key => Style::default().fg(theme.directory),
+                Token::Section => Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+                Token::String => Style::default().fg(theme.input),
+                Token::Number | Token::Literal => Style::default().fg(theme.label),
+                Token::Punct | Token::Plain => Style::default().fg(theme.file),
+                Token::Comment => Style::default().fg(theme.hint),
+            };
+            Span::styled(text.clone(), style)
+        }));
+        Spans::from(spans)
+    }).collect()
+}
+
+fn table_lines(rows: &[Vec<String>], skip: usize, theme: &Theme) -> Vec<Spans<'static>> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (skip..columns).map(|c| rows.iter().filter_map(|row| row.get(c)).map(|cell| cell.width()).max().unwrap_or(0).clamp(1, TABLE_CELL_WIDTH)).collect();
+    rows.iter().enumerate().map(|(i, row)| {
+        let style = if i == 0 { Style::default().fg(theme.label).add_modifier(Modifier::BOLD) } else { Style::default().fg(theme.file) };
+        let mut spans = Vec::new();
+        if skip > 0 { spans.push(Span::styled("< ", Style::default().fg(theme.hint))); }
+        for (cell, width) in row.iter().skip(skip).zip(&widths) {
+            let text = truncate(cell, *width);
+            spans.push(Span::styled(format!("{}{}  ", text, " ".repeat(width.saturating_sub(text.width()))), style));
+        }
+        Spans::from(spans)
+    }).collect()
+}
+
+fn render_results<B: Backend>(f: &mut Frame<B>, area: Rect, results: &Results, theme: &Theme, focused: bool) {
+    let title = trf(if focused { "'{}' {}/{} (Enter jump | Tab back | Esc hide)" } else { "'{}' {}/{} (Tab focus | [/] step | Bksp return)" }, &[&results.query, &(results.index + 1), &results.hits.len()]);
+    let block = Block::default().borders(theme.borders).title(title).border_style(Style::default().fg(if focused { theme.accent } else { theme.border }));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if results.hits.is_empty() { return f.render_widget(Paragraph::new(Span::styled(tr("No matches"), Style::default().fg(theme.hint))), inner); }
+    let matcher = Matcher::new(&results.query, results.options).ok();
+    let items: Vec<ListItem> = results.hits.iter().map(|hit| {
+        let style = theme.entry(hit.is_dir);
+        let path = hit.path.to_string_lossy();
+        let spans = match hit.line {
+            Some(line) => {
+                let text = hit.text.trim_start();
+                let mut spans = vec![Span::styled(path.into_owned(), style), Span::styled(format!(":{}  ", line), Style::default().fg(theme.hint))];
+                spans.extend(highlighted(text, &matcher.as_ref().map(|m| m.ranges(text)).unwrap_or_default(), Style::default(), theme));
+                spans
+            }
+            None => {
+                let name_start = path.len() - hit.path.file_name().map_or(0, |n| n.to_string_lossy().len());
+                let ranges: Vec<_> = matcher.as_ref().map(|m| m.ranges(&path[name_start..])).unwrap_or_default().into_iter().map(|r| r.start + name_start..r.end + name_start).collect();
+                highlighted(&path, &ranges, style, theme)
+            }
+        };
+        ListItem::new(Spans::from(spans))
+    }).collect();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(results.index));
+    f.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn draw_zen<B: Backend>(f: &mut Frame<B>, app_state: &mut AppState) {
+    app_state.previewer.placement = None;
+    let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Min(1), Constraint::Length(1)]).split(f.size());
+    let theme = &app_state.theme;
+    if let Some(results) = app_state.results.as_ref().filter(|r| r.visible) { render_results(f, chunks[0], results, theme, app_state.popup_mode == PopupMode::Results); }
+    else {
+        let filter = Matcher::new(&tags::split_query(&app_state.filter).1, app_state.config.search).ok();
+        let items: Vec<ListItem> = app_state.entries.iter().map(|entry| {
+            let style = theme.entry(app_state.dir_names.contains(entry));
+            let mut spans = highlighted(entry, &filter.as_ref().map(|m| m.ranges(entry)).unwrap_or_default(), style, theme);
+            if app_state.is_marked(entry) { spans.insert(0, Span::styled("+", Style::default().fg(theme.label).add_modifier(Modifier::BOLD))); }
+            ListItem::new(Spans::from(spans))
+        }).collect();
+        let list = List::new(items).highlight_style(theme.selected()).highlight_symbol(">");
+        f.render_stateful_widget(list, chunks[0], &mut app_state.list_state);
+    }
+    let theme = &app_state.theme;
+    let line = match (&app_state.status, &app_state.config.status_format) {
+        (Some(status), _) => status.clone(),
+        (None, Some(format)) => statusline::render(format, app_state),
+        (None, None) => {
+            let mut line = format!("{}/{} {}", (app_state.selected_index + 1).min(app_state.entries.len()), app_state.entries.len(), app_state.fs.location(&app_state.focus_dir));
+            if !app_state.filter.is_empty() || app_state.popup_mode == PopupMode::Filter { line.push_str(&format!(" /{}", app_state.filter)); }
+            if app_state.popup_mode == PopupMode::Filter { line.push('_'); }
+            line
+        }
+    };
+    f.render_widget(Paragraph::new(Spans::from(vec![Span::styled(line, Style::default().fg(theme.hint))])), chunks[1]);
+    if app_state.popup_mode != PopupMode::None { render_popup(f, app_state); }
+}
+
+fn grouped<'a>(items: Vec<ListItem<'a>>, app_state: &AppState) -> (Vec<ListItem<'a>>, usize) {
+    let groups: Vec<Group> = app_state.entries.iter().map(|entry| Group::of(entry, app_state.dir_names.contains(entry))).collect();
+    let style = Style::default().fg(app_state.theme.label).add_modifier(Modifier::BOLD);
+    let (mut rows, mut selected) = (Vec::new(), 0);
+    for (i, item) in items.into_iter().enumerate() {
+        if i == 0 || groups[i] != groups[i - 1] {
+            let count = groups[i..].iter().take_while(|group| **group == groups[i]).count();
+            rows.push(ListItem::new(Spans::from(vec![Span::styled(format!("{}{} ({})", if app_state.config.accessible { "" } else { "── " }, groups[i].label(), count), style)])));
+        }
+        if i == app_state.selected_index { selected = rows.len(); }
+        rows.push(item);
+    }
+    (rows, selected)
+}
+
+fn list_title(app_state: &AppState) -> String {
+    let mut title = match (app_state.popup_mode == PopupMode::Filter, app_state.filter.is_empty()) {
+        (true, _) => format!("{} /{}_ [{}]", tr("CLI Navigation"), app_state.filter, app_state.config.search.label()),
+        (false, false) => format!("{} /{}", tr("CLI Navigation"), app_state.filter),
+        (false, true) => tr("CLI Navigation").to_string(),
+    };
+    if !app_state.fs.keeps_order() { title.push_str(&format!(" [{} {}]", app_state.listing.sort.label(), app_state.listing.sort.direction(app_state.listing.reverse))); }
+    if !app_state.marks.is_empty() { title.push_str(&trf(" ({} marked)", &[&app_state.marks.len()])); }
+    if app_state.stream.is_some() { title.push_str(&trf(" [loading… {} read]", &[&app_state.unfiltered.len()])); }
+    if app_state.dry_run { title.push_str(tr(" [DRY RUN]")); }
+    if app_state.choosing { title.push_str(tr(" -- PICK (Enter choose | Esc cancel)")); }
+    let jobs = app_state.jobs.active();
+    if jobs > 0 { title.push_str(&trf(if jobs == 1 { " [{} job]" } else { " [{} jobs]" }, &[&jobs])); }
+    if let Some(range) = app_state.visual_range() { title.push_str(&trf(" -- VISUAL {} (Enter mark | Esc cancel)", &[&range.count()])); }
+    if !app_state.config.panels.path && let Some(status) = &app_state.status { title.push_str(&format!(" -- {}", status)); }
+    title
+}
+
+fn highlighted(text: &str, ranges: &[Range<usize>], style: Style, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut at = 0;
+    for range in ranges {
+        if range.start > at { spans.push(Span::styled(text[at..range.start].to_string(), style)); }
+        spans.push(Span::styled(text[range.clone()].to_string(), style.fg(theme.matched).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)));
+        at = range.end;
+    }
+    if at < text.len() || spans.is_empty() { spans.push(Span::styled(text[at..].to_string(), style)); }
+    spans
+}
+
+fn render_too_small<B: Backend>(f: &mut Frame<B>, theme: &Theme) {
+    let size = f.size();
+    let message = vec![
+        Spans::from(vec![Span::styled(tr("Terminal too small"), Style::default().fg(theme.danger).add_modifier(Modifier::BOLD))]),
+        Spans::from(vec![Span::styled(format!("{}x{} (need {}x{})", size.width, size.height, MIN_WIDTH, MIN_HEIGHT), Style::default().fg(theme.hint))]),
+    ];
+    let top = size.height.saturating_sub(2) / 2;
+    let area = Rect::new(size.x, size.y + top, size.width, size.height - top);
+    f.render_widget(Paragraph::new(message).alignment(Alignment::Center).wrap(Wrap { trim: true }), area);
+}
+
+fn render_popup<B: Backend>(f: &mut Frame<B>, app_state: &AppState) {
+    if matches!(app_state.popup_mode, PopupMode::Results | PopupMode::Filter | PopupMode::Visual | PopupMode::Preview | PopupMode::Yank | PopupMode::Conflict) { return; }
+    let size = f.size();
+    let popup_area = centered_rect(50, 30, size);
+    f.render_widget(Clear, popup_area);
+    let (title, prompt) = match app_state.popup_mode {
+        PopupMode::CreateFile => ("Create New File", "Enter filename:"),
+        PopupMode::CreateDir => ("Create New Directory", "Enter directory name:"),
+        PopupMode::Paste => ("Paste Into New File", "Save the clipboard text as:"),
+        PopupMode::Group => ("Group Into New Folder", "Move the marked entries into a new folder named:"),
+        PopupMode::Delete | PopupMode::Overwrite | PopupMode::Shred => {
+            let confirm_area = centered_rect(50, 40, size);
+            f.render_widget(Clear, confirm_area);
+            return render_confirm_popup(f, confirm_area, app_state);
+        }
+        PopupMode::Rename => ("Rename Item", "Enter new name:"),
+        PopupMode::Tag => ("Tags", "Tags, separated by spaces (empty clears):"),
+        PopupMode::Xattr => ("Extended Attribute", "Enter name=value:"),
+        PopupMode::Chmod if app_state.marks.is_empty() => ("Change Permissions", "Mode (644, 755/644 for dirs/files, -R recursive):"),
+        PopupMode::Chmod => ("Change Permissions", "Mode for every marked entry (644, 755/644 for dirs/files, -R recursive):"),
+        PopupMode::PreviewSearch => ("Search Preview", "Text to find (empty clears):"),
+        PopupMode::Upload => ("Upload", "Local path to upload here:"),
+        PopupMode::Find => ("Find", "File name contains:"),
+        PopupMode::Grep => ("Search Contents", "Text to search for:"),
+        PopupMode::ForEach => ("Run For Each Marked File", "Command ({} path, {name} name, -j N parallel):"),
+        PopupMode::Compare => ("Compare", "Directory to compare with:"),
+        PopupMode::Tree => ("Export Tree", "File to write [-L depth] [-I glob]:"),
+        PopupMode::Report => {
+            let report_area = centered_rect(70, 60, size);
+            f.render_widget(Clear, report_area);
+            return render_report(f, report_area, app_state);
+        }
+        PopupMode::Results | PopupMode::Filter | PopupMode::Visual | PopupMode::Preview | PopupMode::Yank | PopupMode::Conflict | PopupMode::Diff | PopupMode::None => ("", ""),
+        PopupMode::Palette => return render_palette(f, popup_area, app_state),
+        PopupMode::Drives => return render_drives(f, popup_area, app_state),
+        PopupMode::Properties => {
+            let properties_area = centered_rect(60, 50, size);
+            f.render_widget(Clear, properties_area);
+            return render_properties(f, properties_area, app_state);
+        }
+        PopupMode::Favorites => {
+            let favorites_area = centered_rect(70, 60, size);
+            f.render_widget(Clear, favorites_area);
+            return render_favorites(f, favorites_area, app_state);
+        }
+        PopupMode::Comparison => {
+            let comparison_area = centered_rect(70, 60, size);
+            f.render_widget(Clear, comparison_area);
+            return render_comparison(f, comparison_area, app_state);
+        }
+        PopupMode::Sync => {
+            let sync_area = centered_rect(70, 60, size);
+            f.render_widget(Clear, sync_area);
+            return render_sync(f, sync_area, app_state);
+        }
+        PopupMode::Jobs => {
+            let jobs_area = centered_rect(70, 60, size);
+            f.render_widget(Clear, jobs_area);
+            return render_jobs(f, jobs_area, app_state);
+        }
+        PopupMode::Marks => {
+            let marks_area = centered_rect(70, 60, size);
+            f.render_widget(Clear, marks_area);
+            return render_marks(f, marks_area, app_state);
+        }
+        PopupMode::Devices => {
+            let devices_area = centered_rect(60, 40, size);
+            f.render_widget(Clear, devices_area);
+            return render_devices(f, devices_area, app_state);
+        }
+    };
+    let (title, prompt) = (tr(title), tr(prompt));
+    let mode = match app_state.popup_mode {
+        PopupMode::Find | PopupMode::Grep => Spans::from(vec![Span::styled(trf("[{}]  Ctrl+E case | Ctrl+R regex", &[&app_state.config.search.label()]), Style::default().fg(app_state.theme.label))]),
+        _ => Spans::from(vec![]),
+    };
+    let popup_text = vec![
+        Spans::from(vec![Span::raw(prompt)]),
+        Spans::from(vec![Span::styled(&app_state.input_buffer, Style::default().fg(app_state.theme.input))]),
+        mode,
+        Spans::from(vec![Span::styled(tr("Press Enter to confirm, Esc to cancel"), Style::default().fg(app_state.theme.hint))]),
+    ];
+    let popup = Paragraph::new(popup_text)
+        .block(Block::default().borders(app_state.theme.borders).title(title).style(Style::default().fg(app_state.theme.accent)))
+        .alignment(Alignment::Left);
+    f.render_widget(popup, popup_area);
+}
+
+fn render_palette<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let block = Block::default().borders(theme.borders).title(tr("Command Palette")).style(Style::default().fg(theme.accent));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let rows = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(2), Constraint::Min(0)]).split(inner);
+    let prompt = Paragraph::new(Spans::from(vec![Span::styled(": ", Style::default().fg(theme.label)), Span::styled(&app_state.input_buffer, Style::default().fg(theme.input))]));
+    f.render_widget(prompt, rows[0]);
+    let items: Vec<ListItem> = app_state.palette_items().into_iter().map(|(name, key, _)| {
+        let key_hint = key.map(|k| format!("  [{}]", k)).unwrap_or_default();
+        ListItem::new(Spans::from(vec![Span::raw(name), Span::styled(key_hint, Style::default().fg(theme.hint))]))
+    }).collect();
+    let empty = items.is_empty();
+    let list = List::new(items).highlight_style(theme.selected()).highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(if empty { None } else { Some(app_state.popup_index) });
+    if empty { f.render_widget(Paragraph::new(Span::styled(tr("No matching commands"), Style::default().fg(theme.hint))), rows[1]); } else { f.render_stateful_widget(list, rows[1], &mut list_state); }
+}
+
+fn render_confirm_popup<B: Backend>(f: &mut Frame<B>, popup_area: Rect, app_state: &AppState) {
+    let theme = &app_state.theme;
+    let selected_name = match &app_state.confirm_action { Some(Action::Delete(name)) | Some(Action::Replace(name, _)) | Some(Action::Shred(name)) => name.as_str(), _ => "" };
+    let (title, instruction) = match app_state.popup_mode {
+        PopupMode::Delete => ("Delete Confirmation", "Type 'y' or 'yes' to confirm:"),
+        PopupMode::Shred => ("Secure Wipe", "Not reliable on SSDs, CoW/journaling filesystems or snapshots. Type 'shred' to confirm:"),
+        _ => ("Overwrite Confirmation", "Type 'y' or 'yes' to confirm:"),
+    };
+    let (title, instruction) = (tr(title), tr(instruction));
+    let popup_text = vec![
+        Spans::from(vec![Span::styled(app_state.confirm_message.as_str(), Style::default().fg(theme.danger))]),
+        Spans::from(vec![]),
+        Spans::from(vec![Span::raw(tr("Item: ")), Span::styled(selected_name, Style::default().fg(theme.input))]),
+        Spans::from(vec![]),
+        Spans::from(vec![Span::raw(instruction)]),
+        Spans::from(vec![Span::styled(">> ", Style::default().fg(theme.danger)), Span::styled(app_state.input_buffer.as_str(), Style::default().fg(theme.input))]),
+        Spans::from(vec![]),
+        Spans::from(vec![Span::styled(tr("Press Esc to cancel"), Style::default().fg(theme.hint))]),
+    ];
+    let popup = Paragraph::new(popup_text)
+        .block(Block::default().borders(theme.borders).title(title).style(Style::default().fg(theme.danger)))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true });
+    f.render_widget(popup, popup_area);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default().direction(Direction::Vertical).constraints([Constraint::Percentage((100 - percent_y) / 2), Constraint::Percentage(percent_y), Constraint::Percentage((100 - percent_y) / 2)]).split(r);
+    Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage((100 - percent_x) / 2), Constraint::Percentage(percent_x), Constraint::Percentage((100 - percent_x) / 2)]).split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, path::Path};
+    use crate::state::{Selection, tests::app};
+
+    fn assert_snapshot(app_state: &mut AppState, name: &str) {
+        let actual = snapshot(app_state, 80, 24).unwrap() + "\n";
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{name}.txt"));
+        if env::var_os("UPDATE_SNAPSHOTS").is_some() { fs::write(&path, &actual).unwrap(); }
+        let expected = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e} (rerun with UPDATE_SNAPSHOTS=1 to record it)", path.display()));
+        assert_eq!(actual, expected, "{name} snapshot changed (rerun with UPDATE_SNAPSHOTS=1 to accept it)");
+    }
+
+    fn listing() -> AppState {
+        app(&["/work/docs/", "/work/src/", "/work/Cargo.toml", "/work/README.md", "/work/notes.txt"])
+    }
+
+    #[test]
+    fn list() {
+        let mut app_state = listing();
+        app_state.selected_index = 2;
+        assert_snapshot(&mut app_state, "list");
+    }
+
+    #[test]
+    fn marks_and_status() {
+        let mut app_state = listing();
+        app_state.toggle_mark();
+        app_state.select(Selection::Invert);
+        assert_snapshot(&mut app_state, "marks_and_status");
+    }
+
+    #[test]
+    fn create_dir_popup() {
+        let mut app_state = listing();
+        app_state.popup_mode = PopupMode::CreateDir;
+        app_state.input_buffer = "assets".to_string();
+        assert_snapshot(&mut app_state, "create_dir_popup");
+    }
+
+    #[test]
+    fn conflict_popup() {
+        let mut app_state = app(&["/src/notes.txt", "/work/notes.txt"]);
+        app_state.marks.insert("/src/notes.txt".into());
+        app_state.paste_marks(crate::state::Transfer::Copy).unwrap();
+        assert_snapshot(&mut app_state, "conflict_popup");
+    }
+
+    #[test]
+    fn too_small() {
+        let mut app_state = listing();
+        let actual = snapshot(&mut app_state, MIN_WIDTH - 1, MIN_HEIGHT).unwrap();
+        assert!(!actual.contains("README.md"));
+    }
+}
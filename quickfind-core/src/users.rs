@@ -0,0 +1,49 @@
+use std::{collections::HashMap, sync::{Mutex, OnceLock}};
+
+type Names = HashMap<(bool, u32), Option<String>>;
+
+static NAMES: OnceLock<Mutex<Names>> = OnceLock::new();
+
+pub fn user_name(uid: u32) -> Option<String> {
+    cached(false, uid)
+}
+
+pub fn group_name(gid: u32) -> Option<String> {
+    cached(true, gid)
+}
+
+fn cached(group: bool, id: u32) -> Option<String> {
+    let cache = NAMES.get_or_init(Default::default);
+    if let Ok(names) = cache.lock() && let Some(name) = names.get(&(group, id)) { return name.clone(); }
+    let name = lookup(group, id);
+    if let Ok(mut names) = cache.lock() { names.insert((group, id), name.clone()); }
+    name
+}
+
+#[cfg(unix)]
+fn lookup(group: bool, id: u32) -> Option<String> {
+    use std::{ffi::CStr, ptr};
+    let mut buffer = vec![0 as libc::c_char; 1024];
+    loop {
+        let (code, name) = unsafe {
+            if group {
+                let mut entry: libc::group = std::mem::zeroed();
+                let mut result = ptr::null_mut();
+                let code = libc::getgrgid_r(id, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result);
+                (code, (!result.is_null()).then(|| CStr::from_ptr(entry.gr_name).to_string_lossy().into_owned()))
+            } else {
+                let mut entry: libc::passwd = std::mem::zeroed();
+                let mut result = ptr::null_mut();
+                let code = libc::getpwuid_r(id, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result);
+                (code, (!result.is_null()).then(|| CStr::from_ptr(entry.pw_name).to_string_lossy().into_owned()))
+            }
+        };
+        if code == libc::ERANGE && buffer.len() < 1 << 20 { buffer.resize(buffer.len() * 2, 0); continue; }
+        return if code == 0 { name } else { None };
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup(_: bool, _: u32) -> Option<String> {
+    None
+}
@@ -0,0 +1,546 @@
+use std::{collections::BTreeMap, fs, io::{self, Read}, path::{Component, Path, PathBuf}, process::{Child, Output}, sync::{Arc, Mutex, MutexGuard, PoisonError, atomic::{AtomicBool, Ordering}}, thread::{self, JoinHandle}, time::{Duration, SystemTime}};
+use crate::{config::Config, ftp::FtpServer, i18n::tr, sftp::SftpTarget, shell};
+#[cfg(feature = "s3")]
+use crate::s3::S3Bucket;
+
+pub struct Stat {
+    pub is_dir: bool,
+    pub is_symlink: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct Meta {
+    pub kind: char,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub attributes: Option<u32>,
+}
+
+pub trait Filesystem {
+    fn list(&self, dir: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>>;
+    fn stat(&self, path: &Path) -> Result<Option<Stat>, Box<dyn std::error::Error>>;
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>>;
+    fn mkdir(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>>;
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>>;
+    fn remove(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>>;
+    fn resolve(&self, base: &Path, path: &str) -> Result<PathBuf, Box<dyn std::error::Error>>;
+
+    fn metadata(&self, _path: &Path) -> Option<Meta> { None }
+
+    fn is_local(&self) -> bool { false }
+
+    fn keeps_order(&self) -> bool { false }
+
+    fn location(&self, dir: &Path) -> String { dir.to_string_lossy().into_owned() }
+
+    fn shell_hint(&self, dir: &Path) -> String { shell::cd_command(dir) }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.stat(from)?.is_some_and(|s| s.is_dir) { return self.write(to, &self.read(from)?); }
+        if to.starts_with(from) { return Err(format!("cannot copy {} into itself", from.display()).into()); }
+        self.mkdir(to)?;
+        for (name, _) in self.list(from)? { self.copy(&from.join(&name), &to.join(&name))?; }
+        Ok(())
+    }
+
+    fn share(&self) -> Option<Arc<dyn Filesystem + Send + Sync>> { None }
+
+    fn download(&self, path: &Path, local_dir: &Path, _cancel: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        let name = path.file_name().ok_or("nothing to download")?;
+        if self.stat(path)?.is_some_and(|s| s.is_dir) { return Err("directory downloads are not supported here".into()); }
+        Ok(fs::write(local_dir.join(name), self.read(path)?)?)
+    }
+
+    fn upload(&self, local: &Path, dir: &Path, _cancel: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        let name = local.file_name().ok_or("nothing to upload")?;
+        if local.is_dir() { return Err("directory uploads are not supported here".into()); }
+        self.write(&dir.join(name), &fs::read(local)?)
+    }
+}
+
+pub struct LocalFs;
+
+impl Filesystem for LocalFs {
+    fn list(&self, dir: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+        Ok(fs::read_dir(dir)?.filter_map(|x| x.ok()).map(|e| (e.file_name().to_string_lossy().into_owned(), e.path().is_dir())).collect())
+    }
+
+    fn stat(&self, path: &Path) -> Result<Option<Stat>, Box<dyn std::error::Error>> {
+        match fs::symlink_metadata(path) {
+            Ok(meta) => Ok(Some(Stat { is_dir: path.is_dir(), is_symlink: meta.file_type().is_symlink() })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(fs::read(path)?)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(fs::write(path, contents)?)
+    }
+
+    fn mkdir(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(fs::create_dir_all(path)?)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(fs::rename(from, to)?)
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if fs::symlink_metadata(path)?.is_dir() { fs::remove_dir_all(path)?; } else { fs::remove_file(path)?; }
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let metadata = fs::symlink_metadata(from)?;
+        if metadata.is_symlink() { return Ok(copy_symlink(&fs::read_link(from)?, to)?); }
+        if !metadata.is_dir() { fs::copy(from, to)?; return Ok(()); }
+        if to.starts_with(from) { return Err(format!("cannot copy {} into itself", from.display()).into()); }
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            self.copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, base: &Path, path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let target = base.join(expand_tilde(path));
+        if !target.is_dir() { return Err(format!("not a directory: {}", target.display()).into()); }
+        Ok(canonicalize(&target)?)
+    }
+
+    fn metadata(&self, path: &Path) -> Option<Meta> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        let file_type = metadata.file_type();
+        let kind = if file_type.is_dir() { 'd' } else if file_type.is_symlink() { 'l' } else if file_type.is_file() { '-' } else { special_kind(&file_type) };
+        let (mode, uid, gid) = ownership(&metadata);
+        Some(Meta { kind, size: metadata.len(), modified: metadata.modified().ok(), mode, uid, gid, attributes: attributes(&metadata) })
+    }
+
+    fn is_local(&self) -> bool { true }
+}
+
+pub struct LineList {
+    pub lines: Vec<String>,
+}
+
+impl LineList {
+    fn unsupported<T>(&self) -> Result<T, Box<dyn std::error::Error>> {
+        Err("this is a list of lines read from stdin, not files".into())
+    }
+}
+
+impl Filesystem for LineList {
+    fn list(&self, _dir: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+        Ok(self.lines.iter().map(|line| (line.clone(), false)).collect())
+    }
+
+    fn stat(&self, path: &Path) -> Result<Option<Stat>, Box<dyn std::error::Error>> {
+        Ok(self.lines.iter().any(|line| Path::new(line) == path).then_some(Stat { is_dir: false, is_symlink: false }))
+    }
+
+    fn read(&self, _path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> { self.unsupported() }
+
+    fn write(&self, _path: &Path, _contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> { self.unsupported() }
+
+    fn mkdir(&self, _path: &Path) -> Result<(), Box<dyn std::error::Error>> { self.unsupported() }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> Result<(), Box<dyn std::error::Error>> { self.unsupported() }
+
+    fn remove(&self, _path: &Path) -> Result<(), Box<dyn std::error::Error>> { self.unsupported() }
+
+    fn resolve(&self, _base: &Path, _path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> { self.unsupported() }
+
+    fn location(&self, _dir: &Path) -> String { "stdin".to_string() }
+
+    fn keeps_order(&self) -> bool { true }
+}
+
+#[derive(Clone)]
+pub struct MemoryFs {
+    nodes: Arc<Mutex<BTreeMap<PathBuf, Option<Vec<u8>>>>>,
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        MemoryFs { nodes: Arc::new(Mutex::new(BTreeMap::from([(PathBuf::from("/"), None)]))) }
+    }
+}
+
+impl MemoryFs {
+    fn nodes(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Option<Vec<u8>>>> {
+        self.nodes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.nodes().get(path), Some(None))
+    }
+
+    fn missing<T>(path: &Path) -> Result<T, Box<dyn std::error::Error>> {
+        Err(format!("no such file or directory: {}", path.display()).into())
+    }
+
+    fn subtree(&self, root: &Path) -> Vec<PathBuf> {
+        self.nodes().keys().filter(|path| path.starts_with(root)).cloned().collect()
+    }
+}
+
+impl Filesystem for MemoryFs {
+    fn list(&self, dir: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+        if !self.is_dir(dir) { return Err(format!("not a directory: {}", dir.display()).into()); }
+        Ok(self.nodes().iter().filter(|(path, _)| path.parent() == Some(dir)).filter_map(|(path, node)| Some((path.file_name()?.to_string_lossy().into_owned(), node.is_none()))).collect())
+    }
+
+    fn stat(&self, path: &Path) -> Result<Option<Stat>, Box<dyn std::error::Error>> {
+        Ok(self.nodes().get(path).map(|node| Stat { is_dir: node.is_none(), is_symlink: false }))
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match self.nodes().get(path) {
+            Some(Some(data)) => Ok(data.clone()),
+            Some(None) => Err(format!("{} is a directory", path.display()).into()),
+            None => Self::missing(path),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        if !path.parent().is_some_and(|parent| self.is_dir(parent)) { return Self::missing(path); }
+        if self.is_dir(path) { return Err(format!("{} is a directory", path.display()).into()); }
+        self.nodes().insert(path.to_path_buf(), Some(contents.to_vec()));
+        Ok(())
+    }
+
+    fn mkdir(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        for dir in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            match self.nodes().entry(dir.to_path_buf()).or_insert(None) {
+                None => {}
+                Some(_) => return Err(format!("{} is a file", dir.display()).into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if self.stat(from)?.is_none() { return Self::missing(from); }
+        if !to.parent().is_some_and(|parent| self.is_dir(parent)) { return Self::missing(to); }
+        if to.starts_with(from) && to != from { return Err(format!("cannot move {} into itself", from.display()).into()); }
+        if self.is_dir(to) && to != from { return Err(format!("{} already exists", to.display()).into()); }
+        let mut nodes = self.nodes();
+        for old in nodes.keys().filter(|path| path.starts_with(from)).cloned().collect::<Vec<_>>() {
+            let Some(node) = nodes.remove(&old) else { continue };
+            let new = match old.strip_prefix(from) { Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest), _ => to.to_path_buf() };
+            nodes.insert(new, node);
+        }
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if self.stat(path)?.is_none() { return Self::missing(path); }
+        for entry in self.subtree(path) { self.nodes().remove(&entry); }
+        Ok(())
+    }
+
+    fn resolve(&self, base: &Path, path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut target = PathBuf::from("/");
+        for component in base.join(path).components() {
+            match component {
+                Component::ParentDir => { target.pop(); }
+                Component::Normal(name) => target.push(name),
+                _ => {}
+            }
+        }
+        if !self.is_dir(&target) { return Err(format!("not a directory: {}", target.display()).into()); }
+        Ok(target)
+    }
+
+    fn location(&self, dir: &Path) -> String { format!("memory:{}", dir.display()) }
+
+    fn share(&self) -> Option<Arc<dyn Filesystem + Send + Sync>> { Some(Arc::new(self.clone())) }
+}
+
+#[cfg(unix)]
+fn ownership(metadata: &fs::Metadata) -> (Option<u32>, Option<u32>, Option<u32>) {
+    use std::os::unix::fs::MetadataExt;
+    (Some(metadata.mode()), Some(metadata.uid()), Some(metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn ownership(_: &fs::Metadata) -> (Option<u32>, Option<u32>, Option<u32>) {
+    (None, None, None)
+}
+
+pub const ATTRIBUTES: [(u32, char, &str); 4] = [(0x1, 'r', "read-only"), (0x2, 'h', "hidden"), (0x4, 's', "system"), (0x20, 'a', "archive")];
+
+#[cfg(windows)]
+fn attributes(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::windows::fs::MetadataExt;
+    Some(metadata.file_attributes())
+}
+
+#[cfg(not(windows))]
+fn attributes(_: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+pub fn has_hidden_attribute(path: &Path) -> bool {
+    cfg!(windows) && fs::symlink_metadata(path).ok().and_then(|m| attributes(&m)).is_some_and(|a| a & 0x2 != 0)
+}
+
+#[cfg(unix)]
+fn special_kind(file_type: &fs::FileType) -> char {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_fifo() { 'p' } else if file_type.is_socket() { 's' } else if file_type.is_block_device() { 'b' } else if file_type.is_char_device() { 'c' } else { '?' }
+}
+
+#[cfg(not(unix))]
+fn special_kind(_: &fs::FileType) -> char {
+    '?'
+}
+
+pub fn open(location: &str, base: &Path, config: &Config) -> Result<(Box<dyn Filesystem>, PathBuf), Box<dyn std::error::Error>> {
+    if location.starts_with("sftp://") {
+        let (target, path) = SftpTarget::parse(location).ok_or("invalid sftp:// URL, expected sftp://user@host[:port]/path")?;
+        let dir = target.resolve(Path::new("."), &path)?;
+        return Ok((Box::new(target), dir));
+    }
+    if ["ftp://", "ftps://", "ftpes://"].iter().any(|scheme| location.starts_with(scheme)) {
+        let (server, path) = FtpServer::parse(location, config.ftp_active).ok_or("invalid FTP URL, expected ftp://[user[:password]@]host[:port]/path")?;
+        let dir = server.resolve(Path::new("/"), &path)?;
+        return Ok((Box::new(server), dir));
+    }
+    if location.starts_with("s3://") { return open_s3(location); }
+    let dir = LocalFs.resolve(base, location)?;
+    Ok((Box::new(LocalFs), dir))
+}
+
+#[cfg(feature = "s3")]
+fn open_s3(location: &str) -> Result<(Box<dyn Filesystem>, PathBuf), Box<dyn std::error::Error>> {
+    let (bucket, prefix) = S3Bucket::parse(location).ok_or("invalid s3:// URL, expected s3://bucket/prefix")?;
+    let dir = bucket.resolve(Path::new("/"), &prefix)?;
+    Ok((Box::new(bucket), dir))
+}
+
+#[cfg(not(feature = "s3"))]
+fn open_s3(_: &str) -> Result<(Box<dyn Filesystem>, PathBuf), Box<dyn std::error::Error>> {
+    Err("this build has no S3 support; rebuild with `--features s3`".into())
+}
+
+#[cfg(unix)]
+pub fn copy_symlink(target: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, to)
+}
+
+#[cfg(windows)]
+pub fn copy_symlink(target: &Path, to: &Path) -> std::io::Result<()> {
+    if to.parent().unwrap_or(Path::new(".")).join(target).is_dir() { std::os::windows::fs::symlink_dir(target, to) } else { std::os::windows::fs::symlink_file(target, to) }
+}
+
+pub fn wait_cancellable(mut child: Child, cancel: &AtomicBool) -> io::Result<Output> {
+    let (stdout, stderr) = (drain(child.stdout.take()), drain(child.stderr.take()));
+    let status = loop {
+        if let Some(status) = child.try_wait()? { break status; }
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::Interrupted, tr("cancelled")));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    Ok(Output { status, stdout: stdout.join().unwrap_or_default(), stderr: stderr.join().unwrap_or_default() })
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe { let _ = pipe.read_to_end(&mut bytes); }
+        bytes
+    })
+}
+
+pub fn numbered_name(name: &str, is_dir: bool, n: usize) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !is_dir && !stem.is_empty() => format!("{} ({}).{}", stem, n, extension),
+        _ => format!("{} ({})", name, n),
+    }
+}
+
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    path.canonicalize().map(without_verbatim)
+}
+
+#[cfg(windows)]
+fn without_verbatim(path: PathBuf) -> PathBuf {
+    use std::path::Prefix;
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else { return path };
+    let plain = match prefix.kind() {
+        Prefix::VerbatimDisk(disk) => format!("{}:", disk as char),
+        Prefix::VerbatimUNC(server, share) => format!(r"\\{}\{}", server.to_string_lossy(), share.to_string_lossy()),
+        _ => return path,
+    };
+    let mut simple = PathBuf::from(plain);
+    simple.extend(components);
+    if simple.as_os_str().len() < 260 { simple } else { path }
+}
+
+#[cfg(not(windows))]
+fn without_verbatim(path: PathBuf) -> PathBuf {
+    path
+}
+
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs_next::home_dir().map(|home| home.join(rest.trim_start_matches('/'))).unwrap_or_else(|| PathBuf::from(path)),
+        _ => PathBuf::from(path),
+    }
+}
+
+pub fn parse_long_listing(line: &str) -> Option<(String, bool)> {
+    let mut fields = 0;
+    let mut in_field = false;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), in_field) {
+            (false, false) if fields == 8 => {
+                let name = &line[i..];
+                let name = if line.starts_with('l') { name.split(" -> ").next().unwrap_or(name) } else { name };
+                return (name != "." && name != "..").then(|| (name.to_string(), line.starts_with('d')));
+            }
+            (false, false) => { in_field = true; fields += 1; }
+            (true, true) => in_field = false,
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn skip_fields(line: &str, count: usize) -> Option<&str> {
+    let mut rest = line.trim_start();
+    for _ in 0..count { rest = rest.split_once(char::is_whitespace)?.1.trim_start(); }
+    Some(rest)
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 { return format!("{} B", bytes); }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() { size /= 1024.0; unit += 1; }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> MemoryFs {
+        let fs = MemoryFs::default();
+        fs.mkdir(Path::new("/home/docs/old")).unwrap();
+        fs.write(Path::new("/home/docs/a.txt"), b"alpha").unwrap();
+        fs.write(Path::new("/home/docs/old/b.txt"), b"beta").unwrap();
+        fs
+    }
+
+    fn names(fs: &MemoryFs, dir: &str) -> Vec<(String, bool)> {
+        let mut names = fs.list(Path::new(dir)).unwrap();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn lists_direct_children_only() {
+        let fs = tree();
+        assert_eq!(names(&fs, "/home/docs"), [("a.txt".to_string(), false), ("old".to_string(), true)]);
+        assert_eq!(names(&fs, "/home"), [("docs".to_string(), true)]);
+        assert!(fs.list(Path::new("/home/docs/a.txt")).is_err());
+        assert!(fs.list(Path::new("/nowhere")).is_err());
+    }
+
+    #[test]
+    fn stats_files_and_directories() {
+        let fs = tree();
+        assert!(fs.stat(Path::new("/home/docs/old")).unwrap().is_some_and(|stat| stat.is_dir));
+        assert!(fs.stat(Path::new("/home/docs/a.txt")).unwrap().is_some_and(|stat| !stat.is_dir));
+        assert!(fs.stat(Path::new("/home/docs/c.txt")).unwrap().is_none());
+    }
+
+    #[test]
+    fn writes_need_an_existing_parent() {
+        let fs = tree();
+        assert!(fs.write(Path::new("/home/missing/c.txt"), b"").is_err());
+        assert!(fs.write(Path::new("/home/docs/old"), b"").is_err());
+        assert!(fs.mkdir(Path::new("/home/docs/a.txt/sub")).is_err());
+        fs.write(Path::new("/home/docs/a.txt"), b"again").unwrap();
+        assert_eq!(fs.read(Path::new("/home/docs/a.txt")).unwrap(), b"again");
+    }
+
+    #[test]
+    fn renames_move_whole_subtrees() {
+        let fs = tree();
+        fs.rename(Path::new("/home/docs/old"), Path::new("/home/archive")).unwrap();
+        assert_eq!(fs.read(Path::new("/home/archive/b.txt")).unwrap(), b"beta");
+        assert!(fs.stat(Path::new("/home/docs/old")).unwrap().is_none());
+        assert!(fs.rename(Path::new("/home/archive"), Path::new("/home/archive/inner")).is_err());
+        assert!(fs.rename(Path::new("/home/docs/a.txt"), Path::new("/home/archive")).is_err());
+        assert!(fs.rename(Path::new("/home/gone"), Path::new("/home/x")).is_err());
+    }
+
+    #[test]
+    fn removes_whole_subtrees() {
+        let fs = tree();
+        fs.remove(Path::new("/home/docs/old")).unwrap();
+        assert_eq!(names(&fs, "/home/docs"), [("a.txt".to_string(), false)]);
+        assert!(fs.remove(Path::new("/home/docs/old")).is_err());
+    }
+
+    #[test]
+    fn copies_recursively_through_the_trait() {
+        let fs = tree();
+        fs.copy(Path::new("/home/docs"), Path::new("/home/backup")).unwrap();
+        assert_eq!(fs.read(Path::new("/home/backup/old/b.txt")).unwrap(), b"beta");
+        assert_eq!(fs.read(Path::new("/home/docs/old/b.txt")).unwrap(), b"beta");
+        assert!(fs.copy(Path::new("/home/docs"), Path::new("/home/docs/old/loop")).is_err());
+    }
+
+    #[test]
+    fn resolves_relative_paths_to_directories() {
+        let fs = tree();
+        assert_eq!(fs.resolve(Path::new("/home/docs/old"), "../..").unwrap(), PathBuf::from("/home"));
+        assert_eq!(fs.resolve(Path::new("/home"), "docs/old").unwrap(), PathBuf::from("/home/docs/old"));
+        assert!(fs.resolve(Path::new("/home/docs"), "a.txt").is_err());
+    }
+
+    #[test]
+    fn numbers_names_before_the_extension() {
+        assert_eq!(numbered_name("report.pdf", false, 1), "report (1).pdf");
+        assert_eq!(numbered_name("archive.tar.gz", false, 2), "archive.tar (2).gz");
+        assert_eq!(numbered_name(".bashrc", false, 1), ".bashrc (1)");
+        assert_eq!(numbered_name("photos.2024", true, 3), "photos.2024 (3)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_kills_the_child() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let child = std::process::Command::new("sleep").arg("5").stdout(std::process::Stdio::piped()).spawn().unwrap();
+        let flag = cancel.clone();
+        thread::spawn(move || { thread::sleep(Duration::from_millis(100)); flag.store(true, Ordering::Relaxed); });
+        let started = std::time::Instant::now();
+        assert_eq!(wait_cancellable(child, &cancel).unwrap_err().kind(), io::ErrorKind::Interrupted);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn resolved_local_paths_are_plain() {
+        let dir = LocalFs.resolve(&std::env::temp_dir(), ".").unwrap();
+        assert!(dir.is_absolute() && dir.is_dir());
+        assert!(!dir.to_string_lossy().starts_with(r"\\?\"));
+    }
+}
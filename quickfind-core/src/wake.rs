@@ -0,0 +1,94 @@
+use std::{io, sync::mpsc::{self, Receiver, SendError}};
+#[cfg(unix)]
+use std::{io::{Read, Write}, os::{fd::{AsRawFd, BorrowedFd}, unix::net::UnixStream}, sync::OnceLock, time::Duration};
+
+pub struct Sender<T>(Option<mpsc::Sender<T>>);
+
+impl<T> Sender<T> {
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let sent = match &self.0 { Some(sender) => sender.send(value), None => Err(SendError(value)) };
+        wake();
+        sent
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender(self.0.clone())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        drop(self.0.take());
+        wake();
+    }
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = mpsc::channel();
+    (Sender(Some(sender)), receiver)
+}
+
+#[cfg(unix)]
+static PIPE: OnceLock<(UnixStream, UnixStream)> = OnceLock::new();
+
+#[cfg(unix)]
+pub fn init() -> io::Result<()> {
+    if PIPE.get().is_some() { return Ok(()); }
+    let (reader, writer) = UnixStream::pair()?;
+    reader.set_nonblocking(true)?;
+    writer.set_nonblocking(true)?;
+    signal_hook::low_level::pipe::register(signal_hook::consts::SIGWINCH, writer.try_clone()?)?;
+    let _ = PIPE.set((reader, writer));
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn init() -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn wake() {
+    if let Some((_, writer)) = PIPE.get() { let _ = Write::write(&mut &*writer, &[1]); }
+}
+
+#[cfg(not(unix))]
+pub fn wake() {}
+
+#[cfg(unix)]
+pub fn wait(input: BorrowedFd, timeout: Duration) -> io::Result<()> {
+    let pollfd = |fd| libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    let mut fds = vec![pollfd(input.as_raw_fd())];
+    if let Some((reader, _)) = PIPE.get() { fds.push(pollfd(reader.as_raw_fd())); }
+    let millis = timeout.as_millis().min(i32::MAX as u128) as i32;
+    if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, millis) } < 0 {
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted { return Err(e); }
+    }
+    if let Some((reader, _)) = PIPE.get() {
+        let mut drained = [0; 64];
+        while let Ok(read) = Read::read(&mut &*reader, &mut drained) && read > 0 {}
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{os::fd::AsFd, thread, time::Instant};
+
+    #[test]
+    fn sending_and_hanging_up_both_wake_a_waiting_loop() {
+        init().unwrap();
+        let (idle, _other) = UnixStream::pair().unwrap();
+        let (sender, receiver) = channel();
+        let worker = thread::spawn(move || { thread::sleep(Duration::from_millis(50)); sender.send(7).unwrap(); thread::sleep(Duration::from_millis(50)); });
+        let started = Instant::now();
+        while receiver.try_recv().is_err() { wait(idle.as_fd(), Duration::from_secs(30)).unwrap(); }
+        while receiver.try_recv() != Err(mpsc::TryRecvError::Disconnected) { wait(idle.as_fd(), Duration::from_secs(30)).unwrap(); }
+        worker.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
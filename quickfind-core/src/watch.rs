@@ -0,0 +1,120 @@
+use std::{io, path::{Path, PathBuf}};
+#[cfg(target_os = "linux")]
+use std::{ffi::CString, os::{fd::{AsRawFd, FromRawFd, OwnedFd}, unix::ffi::OsStrExt}, sync::{Arc, atomic::{AtomicI32, Ordering}, mpsc::Receiver}, thread, time::Duration};
+#[cfg(target_os = "linux")]
+use crate::wake;
+
+#[cfg(target_os = "linux")]
+const SETTLE: Duration = Duration::from_millis(200);
+#[cfg(target_os = "linux")]
+const MASK: u32 = libc::IN_CREATE | libc::IN_DELETE | libc::IN_MOVED_FROM | libc::IN_MOVED_TO | libc::IN_CLOSE_WRITE | libc::IN_ATTRIB | libc::IN_DELETE_SELF | libc::IN_MOVE_SELF;
+
+#[derive(Default)]
+pub struct DirWatcher {
+    dir: Option<PathBuf>,
+    watch: Option<Watch>,
+}
+
+impl DirWatcher {
+    pub fn poll(&mut self, dir: Option<&Path>) -> bool {
+        if self.dir.as_deref() != dir {
+            self.dir = dir.map(Path::to_path_buf);
+            if self.watch.is_none() && dir.is_some() { self.watch = Watch::start().ok(); }
+            if let Some(watch) = &self.watch { watch.follow(dir); }
+            return false;
+        }
+        self.watch.as_ref().is_some_and(Watch::changed)
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Watch {
+    fd: Arc<OwnedFd>,
+    current: Arc<AtomicI32>,
+    receiver: Receiver<()>,
+}
+
+#[cfg(target_os = "linux")]
+impl Watch {
+    fn start() -> io::Result<Watch> {
+        let raw = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if raw < 0 { return Err(io::Error::last_os_error()); }
+        let (fd, current) = (Arc::new(unsafe { OwnedFd::from_raw_fd(raw) }), Arc::new(AtomicI32::new(-1)));
+        let (sender, receiver) = wake::channel();
+        let (events, wanted) = (fd.clone(), current.clone());
+        thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = unsafe { libc::read(events.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len()) };
+                if read < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted { continue }
+                if read <= 0 { return }
+                if touches(&buffer[..read as usize], wanted.load(Ordering::Relaxed)) && sender.send(()).is_err() { return }
+                thread::sleep(SETTLE);
+            }
+        });
+        Ok(Watch { fd, current, receiver })
+    }
+
+    fn follow(&self, dir: Option<&Path>) {
+        let old = self.current.swap(-1, Ordering::Relaxed);
+        if old >= 0 { unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), old) }; }
+        if let Some(dir) = dir && let Ok(path) = CString::new(dir.as_os_str().as_bytes()) {
+            self.current.store(unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), path.as_ptr(), MASK) }, Ordering::Relaxed);
+        }
+        while self.receiver.try_recv().is_ok() {}
+    }
+
+    fn changed(&self) -> bool {
+        self.receiver.try_iter().count() > 0
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn touches(mut events: &[u8], wanted: i32) -> bool {
+    while events.len() >= 16 {
+        let field = |at: usize| u32::from_ne_bytes([events[at], events[at + 1], events[at + 2], events[at + 3]]);
+        let (wd, mask, len) = (field(0) as i32, field(4), field(12) as usize);
+        if mask & libc::IN_Q_OVERFLOW != 0 || wd == wanted && mask & libc::IN_IGNORED == 0 { return true; }
+        events = &events[(16 + len).min(events.len())..];
+    }
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+struct Watch;
+
+#[cfg(not(target_os = "linux"))]
+impl Watch {
+    fn start() -> io::Result<Watch> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn follow(&self, _: Option<&Path>) {}
+
+    fn changed(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+    use std::{fs, time::Instant};
+
+    #[test]
+    fn changes_in_the_watched_dir_are_reported() {
+        let (scratch, other) = (ScratchDir::new().unwrap(), ScratchDir::new().unwrap());
+        let mut watcher = DirWatcher::default();
+        assert!(!watcher.poll(Some(scratch.path())));
+        fs::write(other.path().join("elsewhere"), "").unwrap();
+        fs::write(scratch.path().join("new"), "").unwrap();
+        let started = Instant::now();
+        while !watcher.poll(Some(scratch.path())) {
+            assert!(started.elapsed() < Duration::from_secs(10), "no change reported");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!watcher.poll(Some(other.path())));
+        assert!(!watcher.poll(Some(other.path())));
+    }
+}
@@ -0,0 +1,42 @@
+use std::{fs, io::{Seek, SeekFrom, Write}, path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+pub fn shred(path: &Path, passes: usize) -> std::io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? { shred(&entry?.path(), passes)?; }
+        fs::remove_dir(path)
+    } else if metadata.is_file() {
+        overwrite(path, metadata.len(), passes)?;
+        fs::remove_file(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn overwrite(path: &Path, len: u64, passes: usize) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    let mut seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0x2545_f491_4f6c_dd1d) | 1;
+    let mut block = vec![0u8; 64 * 1024];
+    for pass in 0..passes.max(1) + 1 {
+        file.seek(SeekFrom::Start(0))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(block.len() as u64) as usize;
+            if pass < passes.max(1) {
+                for byte in block[..chunk].iter_mut() {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    *byte = seed as u8;
+                }
+            } else {
+                block[..chunk].fill(0);
+            }
+            file.write_all(&block[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()?;
+    }
+    file.set_len(0)?;
+    file.sync_all()
+}
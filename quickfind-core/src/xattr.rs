@@ -0,0 +1,131 @@
+use std::{io, path::Path};
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+mod sys {
+    use std::{ffi::CString, io, os::unix::ffi::OsStrExt, path::Path};
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a NUL byte"))
+    }
+
+    fn c_name(name: &str) -> io::Result<CString> {
+        CString::new(name).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "attribute name contains a NUL byte"))
+    }
+
+    fn sized(mut call: impl FnMut(*mut libc::c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+        loop {
+            let len = call(std::ptr::null_mut(), 0);
+            if len < 0 { return Err(io::Error::last_os_error()); }
+            let mut buf = vec![0u8; len as usize];
+            let read = call(buf.as_mut_ptr().cast(), buf.len());
+            if read >= 0 { buf.truncate(read as usize); return Ok(buf); }
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::ERANGE) { return Err(error); }
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn list(path: &Path) -> io::Result<Vec<u8>> {
+        let path = c_path(path)?;
+        sized(|buf, size| unsafe { libc::listxattr(path.as_ptr(), buf.cast(), size) })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn list(path: &Path) -> io::Result<Vec<u8>> {
+        let path = c_path(path)?;
+        sized(|buf, size| unsafe { libc::listxattr(path.as_ptr(), buf.cast(), size, 0) })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let (path, name) = (c_path(path)?, c_name(name)?);
+        sized(|buf, size| unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf, size) })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let (path, name) = (c_path(path)?, c_name(name)?);
+        sized(|buf, size| unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf, size, 0, 0) })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let (path, name) = (c_path(path)?, c_name(name)?);
+        check(unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0) })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let (path, name) = (c_path(path)?, c_name(name)?);
+        check(unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0, 0) })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+        let (path, name) = (c_path(path)?, c_name(name)?);
+        check(unsafe { libc::removexattr(path.as_ptr(), name.as_ptr()) })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+        let (path, name) = (c_path(path)?, c_name(name)?);
+        check(unsafe { libc::removexattr(path.as_ptr(), name.as_ptr(), 0) })
+    }
+
+    pub fn unsupported(error: &io::Error) -> bool {
+        error.raw_os_error() == Some(libc::ENOTSUP)
+    }
+
+    fn check(result: libc::c_int) -> io::Result<()> {
+        if result == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+mod sys {
+    use std::{io, path::Path};
+
+    pub fn list(_path: &Path) -> io::Result<Vec<u8>> { Ok(Vec::new()) }
+    pub fn unsupported(error: &io::Error) -> bool { error.kind() == io::ErrorKind::Unsupported }
+    pub fn get(_path: &Path, _name: &str) -> io::Result<Vec<u8>> { Err(error()) }
+    pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> { Err(error()) }
+    pub fn remove(_path: &Path, _name: &str) -> io::Result<()> { Err(error()) }
+
+    fn error() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "extended attributes are not supported on this platform")
+    }
+}
+
+pub const SUPPORTED: bool = cfg!(any(target_os = "linux", target_os = "android", target_os = "macos"));
+
+pub fn list(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let names = match sys::list(path) {
+        Err(e) if sys::unsupported(&e) => return Ok(Vec::new()),
+        names => names?,
+    };
+    let mut attrs: Vec<(String, Vec<u8>)> = names.split(|b| *b == 0).filter(|n| !n.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .filter_map(|name| sys::get(path, &name).ok().map(|value| (name, value)))
+        .collect();
+    attrs.sort();
+    Ok(attrs)
+}
+
+pub fn set(path: &Path, name: &str, value: &str) -> io::Result<()> {
+    sys::set(path, &qualify(name), value.as_bytes())
+}
+
+pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+    sys::remove(path, name)
+}
+
+pub fn display(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) if !text.chars().any(char::is_control) => text.to_string(),
+        _ => value.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
+
+fn qualify(name: &str) -> String {
+    if cfg!(any(target_os = "linux", target_os = "android")) && !name.contains('.') { format!("user.{}", name) } else { name.to_string() }
+}
@@ -1,289 +1,314 @@
-use std::{io, env, fs, path::PathBuf, path::Path, time::Duration};
+use std::{io, io::{Read, Write}, collections::HashMap, env, fs, path::PathBuf, process::ExitCode, time::{Duration, Instant}};
 use crossterm::*;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use tui::{
-    backend::CrosstermBackend,
-    Terminal,
-    widgets::{Block, Borders, Paragraph, List, ListItem, ListState, Clear},
-    layout::{Layout, Constraint, Direction, Alignment, Rect},
-    style::{Style, Color, Modifier},
-    text::{Spans, Span},
-};
+use tui::{backend::CrosstermBackend, Terminal, layout::Rect};
 use event::Event;
+use quickfind_core::{clipboard, commands, finder, graphics::{self, Placement, Protocol}, hooks, i18n, input, ipc, plugins, script, shell, ui};
+use quickfind_core::actions::Action;
+use quickfind_core::events::{CrosstermEvents, EventSource};
+use quickfind_core::state::{AppState, ExternalTask};
 
-#[derive(Clone, PartialEq)]
-enum PopupMode {
-    None,
-    CreateFile,
-    CreateDir,
-    Delete,
-    Rename,
-}
-
-struct AppState {
-    focus_dir: PathBuf,
-    entries: Vec<String>,
-    selected_index: usize,
-    list_state: ListState,
-    popup_mode: PopupMode,
-    input_buffer: String,
-    break_now: bool,
-}
+mod term;
 
-impl AppState {
-    fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let focus_dir = env::current_dir()?;
-        let entries = read_entries(&focus_dir)?;
-        let mut list_state = ListState::default();
-        list_state.select(Some(0));
-        Ok(AppState { focus_dir, entries, selected_index: 0, list_state, popup_mode: PopupMode::None, input_buffer: String::new(), break_now: false })
-    }
+const IDLE_TICK: Duration = Duration::from_secs(60);
 
-    fn refresh_entries(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.entries = read_entries(&self.focus_dir)?;
-        if self.selected_index >= self.entries.len() && !self.entries.is_empty() {
-            self.selected_index = self.entries.len() - 1;
-        }
-        self.list_state.select(Some(self.selected_index));
-        Ok(())
-    }
-
-    fn get_selected_path(&self) -> Option<PathBuf> {
-        self.entries.get(self.selected_index).map(|entry| self.focus_dir.join(entry))
+fn main() -> ExitCode {
+    match launch() {
+        Ok(code) => code,
+        Err(e) => { eprintln!("Error: {}", e); ExitCode::from(2) }
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut app_state = AppState::new()?;
+fn launch() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("exec") => return exec(&args[1..]).map(|_| ExitCode::SUCCESS),
+        Some("send") => return send(&args[1..]).map(|_| ExitCode::SUCCESS),
+        _ => {}
+    }
+    let options = Options::parse(args)?;
+    let control = match options.socket.clone().or_else(|| options.listen.then(ipc::default_path)) {
+        Some(path) => {
+            unsafe { env::set_var(ipc::ENV_VAR, &path) };
+            Some(ipc::listen(&path)?)
+        }
+        None => None,
+    };
+    let lines = if term::stdin_is_piped() { Some(read_lines()?) } else { None };
+    let mut cwd_out = if options.print_cwd || options.choose || lines.is_some() { Some(term::divert_stdout()?) } else { None };
+    let mut app_state = AppState::new(options.location.as_deref().unwrap_or("."))?;
+    app_state.choosing = options.choose;
+    app_state.control = control;
+    app_state.zen |= options.zen;
+    let picking_lines = lines.is_some();
+    if let Some(lines) = lines { app_state.pick_lines(lines)?; }
     let mut out = io::stdout();
     terminal::enable_raw_mode()?;
-    out.execute(terminal::Clear(terminal::ClearType::All))?;
+    out.execute(terminal::EnterAlternateScreen)?;
     out.execute(cursor::Hide)?;
+    out.execute(event::EnableFocusChange)?;
+    term::push_title(&mut out)?;
     let backend = CrosstermBackend::new(&mut out);
     let mut terminal = Terminal::new(backend)?;
+    let result = run(&mut terminal, &mut app_state, &mut CrosstermEvents::new()?);
+    drop(terminal);
 
-    'outer: loop {
-        if app_state.break_now { break 'outer; }
-
-        terminal.draw(|f| {
-            let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Percentage(85), Constraint::Percentage(15)]).split(f.size());
-            let list_items: Vec<ListItem> = app_state.entries.iter().map(|entry| {
-                let entry_path = app_state.focus_dir.join(entry);
-                let style = if entry_path.is_dir() { Style::default().fg(Color::Rgb(144, 238, 144)) } else { Style::default().fg(Color::Green) };
-                ListItem::new(entry.as_str()).style(style)
-            }).collect();
-
-            let border_color = Color::Green;
-
-            let list = List::new(list_items)
-                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border_color)).title("CLI Navigation"))
-                .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-                .highlight_symbol(" #  ");
-
-            let help_text = vec![
-                Spans::from(vec![Span::styled("Navigation: ", Style::default().fg(Color::Yellow)), Span::raw("↑/↓ Select | ←/→ Navigate | Enter Exit")]),
-                Spans::from(vec![Span::styled("File Ops: ", Style::default().fg(Color::Cyan)), Span::raw("N New File | Shift+N New Dir | D Delete")]),
-                Spans::from(vec![Span::raw("R Rename | Esc Cancel")]),
-            ];
-
-            let help_display = Paragraph::new(help_text)
-                .style(Style::default().fg(border_color))
-                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border_color)).title("Controls"))
-                .alignment(Alignment::Left);
-
-            let path_display = Paragraph::new(app_state.focus_dir.to_string_lossy())
-                .style(Style::default().fg(border_color))
-                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border_color)).title("Current Path"));
-
-            let help_chunks = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(70), Constraint::Percentage(30)]).split(chunks[1]);
+    let mut out_post = io::stdout();
+    out_post.execute(event::DisableFocusChange)?;
+    term::pop_title(&mut out_post)?;
+    out_post.execute(terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    out_post.execute(cursor::Show)?;
+    result?;
+    if app_state.jobs.active() > 0 { eprintln!("{}", i18n::tr("Waiting for running jobs to finish; queued ones are dropped")); }
+    app_state.jobs.finish();
 
-            f.render_stateful_widget(list, chunks[0], &mut app_state.list_state);
-            f.render_widget(path_display, help_chunks[0]);
-            f.render_widget(help_display, help_chunks[1]);
+    if picking_lines {
+        let order: HashMap<PathBuf, usize> = app_state.unfiltered.iter().enumerate().map(|(i, (line, _))| (PathBuf::from(line), i)).collect();
+        app_state.chosen.sort_by_key(|path| order.get(path).copied());
+    }
+    hooks::fire_and_wait(app_state.config.hooks.on_exit.as_ref(), "exit", &app_state.focus_dir, app_state.get_selected_path().as_deref())?;
+    match cwd_out.as_mut() {
+        Some(_) if app_state.cancelled || app_state.choosing && app_state.chosen.is_empty() => return Ok(ExitCode::from(1)),
+        Some(out) if app_state.choosing => for path in &app_state.chosen {
+            let choice = if picking_lines { path.to_string_lossy().into_owned() } else { commands::format_choice(&options.format, path) };
+            write!(out, "{}{}", choice, if options.print0 { '\0' } else { '\n' })?;
+        },
+        Some(_) if !app_state.fs.is_local() => return Err(i18n::trf("{} is not a local directory", &[&app_state.fs.location(&app_state.focus_dir)]).into()),
+        Some(out) => writeln!(out, "{}", app_state.focus_dir.display())?,
+        None => hand_off(&app_state)?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-            if app_state.popup_mode != PopupMode::None { render_popup(f, &app_state); }
-        })?;
+struct Options {
+    location: Option<String>,
+    print_cwd: bool,
+    choose: bool,
+    format: String,
+    print0: bool,
+    listen: bool,
+    socket: Option<PathBuf>,
+    zen: bool,
+}
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
-                handle_input(&mut app_state, code, modifiers)?;
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Options, String> {
+        let mut options = Options { location: None, print_cwd: false, choose: false, format: "{path}".to_string(), print0: false, listen: false, socket: None, zen: false };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--print-cwd" => options.print_cwd = true,
+                "--choose-file" => options.choose = true,
+                "--format" => options.format = args.next().ok_or("--format needs a template such as '{path}'")?,
+                "--print0" => options.print0 = true,
+                "--listen" => options.listen = true,
+                "--zen" => options.zen = true,
+                "--socket" => options.socket = Some(args.next().ok_or("--socket needs a path")?.into()),
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ if options.location.is_some() => return Err("only one starting location can be given".to_string()),
+                _ => options.location = Some(arg),
             }
         }
+        Ok(options)
     }
+}
 
-    let mut out_post = io::stdout();
-    out_post.execute(terminal::Clear(terminal::ClearType::All))?;
-    terminal::disable_raw_mode()?;
-    out_post.execute(cursor::MoveTo(0, 0))?;
-    out_post.execute(cursor::Show)?;
+fn read_lines() -> io::Result<Vec<String>> {
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    Ok(String::from_utf8_lossy(&input).lines().filter(|line| !line.is_empty()).map(str::to_string).collect())
+}
 
-    std::process::Command::new("sh").arg("-c").arg(format!("echo cd '\"{}\"' | clip.exe", app_state.focus_dir.display())).output()?;
-    Ok(())
+fn exec(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(commands) = args.first() else { return Err("usage: QuickNav exec \"<commands>\" [location]".into()) };
+    let script = if commands == "-" { let mut input = String::new(); io::stdin().read_to_string(&mut input)?; input } else { commands.clone() };
+    let mut app_state = AppState::new(args.get(1).map(String::as_str).unwrap_or("."))?;
+    script::run(&mut app_state, &script, &mut io::stdout().lock())
 }
 
-fn handle_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
-    if app_state.popup_mode != PopupMode::None { handle_popup_input(app_state, code, modifiers)?; } else { handle_main_input(app_state, code, modifiers)?; }
+fn send(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (path, commands) = match args {
+        [flag, path, commands] if flag == "--socket" => (PathBuf::from(path), commands),
+        [commands] => (ipc::client_path(), commands),
+        _ => return Err("usage: QuickNav send [--socket <path>] \"<commands>\"".into()),
+    };
+    ipc::send(&path, &script::split_commands(commands)?)
+}
+
+fn hand_off(app_state: &AppState) -> Result<(), Box<dyn std::error::Error>> {
+    let command = app_state.fs.shell_hint(&app_state.focus_dir);
+    let last_dir_file = env::var_os("QUICKFIND_LASTDIR").filter(|_| app_state.fs.is_local());
+    match (app_state.config.exit_action.as_str(), last_dir_file) {
+        ("none", _) => {}
+        ("print", _) => println!("{}", command),
+        ("auto", Some(file)) => fs::write(file, app_state.focus_dir.to_string_lossy().as_bytes())?,
+        _ => match clipboard::copy(&command) {
+            Ok(mechanism) => eprintln!("{}", i18n::trf("Copied `{}` to the clipboard via {}", &[&command, &mechanism])),
+            Err(err) => eprintln!("{}", i18n::trf("Could not copy to the clipboard ({}). To follow QuickFind, run:\n{}", &[&err, &command])),
+        },
+    }
     Ok(())
 }
 
-fn handle_main_input(app_state: &mut AppState, code: KeyCode, modifiers: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
-    match code {
-        KeyCode::Enter | KeyCode::Esc => app_state.break_now = true,
-        KeyCode::Right => {
-            if let Some(entry) = app_state.entries.get(app_state.selected_index) {
-                let path_candidate = app_state.focus_dir.join(entry);
-                if path_candidate.is_dir() {
-                    app_state.focus_dir.push(entry);
-                    app_state.refresh_entries()?;
-                    app_state.selected_index = 0;
-                    app_state.list_state.select(Some(0));
-                }
-            }
-        }
-        KeyCode::Left => {
-            app_state.focus_dir.pop();
-            app_state.refresh_entries()?;
-            app_state.selected_index = 0;
-            app_state.list_state.select(Some(0));
-        }
-        KeyCode::Up => {
-            if app_state.selected_index > 0 {
-                app_state.selected_index -= 1;
-                app_state.list_state.select(Some(app_state.selected_index));
-            }
-        }
-        KeyCode::Down => {
-            if app_state.selected_index + 1 < app_state.entries.len() {
-                app_state.selected_index += 1;
-                app_state.list_state.select(Some(app_state.selected_index));
-            }
+fn run(terminal: &mut Terminal<CrosstermBackend<&mut io::Stdout>>, app_state: &mut AppState, events: &mut impl EventSource) -> Result<(), Box<dyn std::error::Error>> {
+    let mut announced_dir: Option<PathBuf> = None;
+    let mut announced_selection: Option<PathBuf> = None;
+    let mut shown: Option<Placement> = None;
+    let mut redraw = true;
+    'outer: loop {
+        if app_state.break_now { break 'outer; }
+        if let Some(task) = app_state.pending_task.take() {
+            hide_image(terminal, app_state, &mut shown)?;
+            run_external_task(terminal, app_state, task)?;
+            redraw = true;
         }
-        KeyCode::Char('n') if !modifiers.contains(KeyModifiers::SHIFT) => {
-            app_state.popup_mode = PopupMode::CreateFile;
-            app_state.input_buffer.clear();
+        let selected = app_state.get_selected_path();
+        let dir_changed = announced_dir.as_ref() != Some(&app_state.focus_dir);
+        if app_state.config.accessible && app_state.status.is_none() && (dir_changed || announced_selection != selected) { app_state.status = Some(app_state.describe(dir_changed)); }
+        if dir_changed {
+            if app_state.fs.is_local() { term::announce_dir(&mut io::stdout(), &app_state.focus_dir)?; } else { term::set_title(&mut io::stdout(), &app_state.fs.location(&app_state.focus_dir))?; }
+            hooks::fire(app_state.config.hooks.on_dir_enter.as_ref(), "dir_enter", &app_state.focus_dir, selected.as_deref());
+            announced_dir = Some(app_state.focus_dir.clone());
         }
-        KeyCode::Char('N') | KeyCode::Char('n') if modifiers.contains(KeyModifiers::SHIFT) => {
-            app_state.popup_mode = PopupMode::CreateDir;
-            app_state.input_buffer.clear();
+        if announced_selection != selected {
+            hooks::fire(app_state.config.hooks.on_selection_change.as_ref(), "selection_change", &app_state.focus_dir, selected.as_deref());
+            announced_selection = selected;
         }
-        KeyCode::Char('d') | KeyCode::Char('D') => {
-            if !app_state.entries.is_empty() {
-                app_state.popup_mode = PopupMode::Delete;
-                app_state.input_buffer.clear();
-            }
+
+        let changed = app_state.poll_background();
+        if app_state.alert_pending { term::alert(&mut io::stdout(), app_state.config.alert)?; app_state.alert_pending = false; }
+        if redraw || changed {
+            terminal.draw(|f| ui::draw(f, app_state))?;
+            if let Some(protocol) = app_state.graphics && app_state.previewer.placement != shown { show_image(terminal, app_state, protocol, &mut shown)?; }
         }
-        KeyCode::Char('r') | KeyCode::Char('R') => {
-            if let Some(current_name) = app_state.entries.get(app_state.selected_index) {
-                app_state.popup_mode = PopupMode::Rename;
-                app_state.input_buffer = current_name.clone();
+
+        let (timeout, waited) = (if changed { Duration::ZERO } else { IDLE_TICK }, Instant::now());
+        redraw = match events.next_event(timeout)? {
+            Some(event) => {
+                if let Event::Resize(width, height) = event {
+                    hide_image(terminal, app_state, &mut shown)?;
+                    terminal.resize(Rect::new(0, 0, width, height))?;
+                }
+                input::handle_event(app_state, &event);
+                true
             }
-        }
-        _ => {}
+            None => waited.elapsed() >= IDLE_TICK,
+        };
     }
-    Ok(())
+    hide_image(terminal, app_state, &mut shown)
 }
 
-fn handle_popup_input(app_state: &mut AppState, code: KeyCode, _: KeyModifiers) -> Result<(), Box<dyn std::error::Error>> {
-    match code {
-        KeyCode::Esc => { app_state.popup_mode = PopupMode::None; app_state.input_buffer.clear(); }
-        KeyCode::Enter => { execute_popup_action(app_state)?; }
-        KeyCode::Backspace => { app_state.input_buffer.pop(); }
-        KeyCode::Char(c) => { app_state.input_buffer.push(c); }
-        _ => {}
+fn show_image(terminal: &mut Terminal<CrosstermBackend<&mut io::Stdout>>, app_state: &mut AppState, protocol: Protocol, shown: &mut Option<Placement>) -> Result<(), Box<dyn std::error::Error>> {
+    if shown.is_some() {
+        hide_image(terminal, app_state, shown)?;
+        if graphics::clear(protocol).is_empty() { terminal.draw(|f| ui::draw(f, app_state))?; }
     }
+    let Some(placement) = app_state.previewer.placement.clone() else { return Ok(()) };
+    let mut out = io::stdout();
+    match graphics::draw(protocol, &placement) {
+        Ok(sequence) => {
+            out.queue(cursor::MoveTo(placement.x, placement.y))?;
+            out.write_all(sequence.as_bytes())?;
+        }
+        Err(e) => app_state.status = Some(i18n::trf("Image preview: {}", &[&e])),
+    }
+    out.flush()?;
+    *shown = Some(placement);
     Ok(())
 }
 
-fn execute_popup_action(app_state: &mut AppState) -> Result<(), Box<dyn std::error::Error>> {
-    match app_state.popup_mode {
-        PopupMode::CreateFile => {
-            if !app_state.input_buffer.trim().is_empty() {
-                let file_path = app_state.focus_dir.join(&app_state.input_buffer);
-                if !file_path.exists() { fs::write(file_path, "")?; }
+fn hide_image(terminal: &mut Terminal<CrosstermBackend<&mut io::Stdout>>, app_state: &AppState, shown: &mut Option<Placement>) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(protocol), Some(_)) = (app_state.graphics, shown.take()) else { return Ok(()) };
+    let sequence = graphics::clear(protocol);
+    if sequence.is_empty() { return Ok(terminal.clear()?); }
+    let mut out = io::stdout();
+    out.write_all(sequence.as_bytes())?;
+    Ok(out.flush()?)
+}
+
+fn suspended<T>(terminal: &mut Terminal<CrosstermBackend<&mut io::Stdout>>, task: impl FnOnce() -> T) -> Result<T, Box<dyn std::error::Error>> {
+    let mut out = io::stdout();
+    out.execute(event::DisableFocusChange)?;
+    out.execute(terminal::LeaveAlternateScreen)?;
+    out.execute(cursor::Show)?;
+    terminal::disable_raw_mode()?;
+    let result = task();
+    terminal::enable_raw_mode()?;
+    out.execute(terminal::EnterAlternateScreen)?;
+    out.execute(cursor::Hide)?;
+    out.execute(event::EnableFocusChange)?;
+    terminal.clear()?;
+    Ok(result)
+}
+
+fn wait_for_enter() {
+    print!("\n{}", i18n::tr("Press Enter to return to QuickFind"));
+    let _ = io::Write::flush(&mut io::stdout());
+    let _ = io::stdin().read_line(&mut String::new());
+}
+
+fn run_external_task(terminal: &mut Terminal<CrosstermBackend<&mut io::Stdout>>, app_state: &mut AppState, task: ExternalTask) -> Result<(), Box<dyn std::error::Error>> {
+    match task {
+        ExternalTask::Plugin(index) => {
+            let Some(plugin) = app_state.plugins.get(index) else { return Ok(()) };
+            let (name, pause) = (plugin.name.clone(), plugin.pause);
+            let selected = app_state.get_selected_path();
+            let result = suspended(terminal, || {
+                let result = plugins::run(plugin, &app_state.focus_dir, selected.as_deref(), &app_state.marks);
+                if pause { wait_for_enter(); }
+                result
+            })?;
+            let (status, commands) = match result {
+                Ok(outcome) => outcome,
+                Err(e) => { app_state.status = Some(i18n::trf("{}: {}", &[&name, &e])); return Ok(()); }
+            };
+            if !status.success() { app_state.status = Some(i18n::trf("{} exited with {}", &[&name, &status])); }
+            for line in commands {
+                if let Err(e) = Action::parse(&line).map_err(Into::into).and_then(|action| app_state.apply(action)) {
+                    app_state.status = Some(i18n::trf("{}: {}", &[&name, &i18n::translate(&e.to_string())]));
+                    break;
+                }
             }
         }
-        PopupMode::CreateDir => {
-            if !app_state.input_buffer.trim().is_empty() {
-                let dir_path = app_state.focus_dir.join(&app_state.input_buffer);
-                if !dir_path.exists() { fs::create_dir(dir_path)?; }
+        ExternalTask::Finder(command) => {
+            match suspended(terminal, || finder::pick(&command, &app_state.focus_dir, &app_state.scan_limits()))? {
+                Ok(Some(path)) => app_state.reveal(&path)?,
+                Ok(None) => {}
+                Err(e) => app_state.status = Some(i18n::trf("{}: {}", &[&command, &e])),
             }
         }
-        PopupMode::Delete => {
-            if matches!(app_state.input_buffer.to_lowercase().as_str(), "y" | "yes") {
-                if let Some(entry) = app_state.entries.get(app_state.selected_index) {
-                    let target_path = app_state.focus_dir.join(entry);
-                    if target_path.is_dir() { fs::remove_dir_all(target_path)?; } else { fs::remove_file(target_path)?; }
-                }
+        ExternalTask::Pager(path) => {
+            let pager = env::var("PAGER").ok().filter(|p| !p.trim().is_empty()).unwrap_or_else(|| if cfg!(windows) { "more" } else { "less" }.to_string());
+            let script = format!("{} {}", pager, shell::quote(&path.to_string_lossy()));
+            match suspended(terminal, || shell::command(&script).current_dir(&app_state.focus_dir).status())? {
+                Ok(status) if !status.success() => app_state.status = Some(i18n::trf("{} exited with {}", &[&pager, &status])),
+                Err(e) => app_state.status = Some(i18n::trf("{}: {}", &[&pager, &e])),
+                Ok(_) => {}
             }
         }
-        PopupMode::Rename => {
-            if !app_state.input_buffer.trim().is_empty() {
-                if let Some(old_name) = app_state.entries.get(app_state.selected_index) {
-                    let old_path = app_state.focus_dir.join(old_name);
-                    let new_path = app_state.focus_dir.join(&app_state.input_buffer);
-                    if old_path != new_path && !new_path.exists() { fs::rename(old_path, new_path)?; }
-                }
+        ExternalTask::Copy(text, what) => app_state.status = Some(match clipboard::copy(&text) {
+            Ok(mechanism) => i18n::trf("Copied {} via {}", &[&what, &mechanism]),
+            Err(e) => i18n::trf("Could not copy to the clipboard: {}", &[&e]),
+        }),
+        ExternalTask::Paste(name) => {
+            if let Err(e) = clipboard::paste().and_then(|text| app_state.save_pasted(&name, &text)) { app_state.status = Some(i18n::translate(&e.to_string())); }
+            return Ok(());
+        }
+        ExternalTask::Command(index) => {
+            let Some(command) = app_state.config.commands.get(index) else { return Ok(()) };
+            let script = command.expand(&app_state.focus_dir, app_state.get_selected_path().as_deref());
+            let pause = command.pause;
+            let status = suspended(terminal, || {
+                let status = shell::command(&script).current_dir(&app_state.focus_dir).status();
+                if pause { wait_for_enter(); }
+                status
+            })?;
+            match status {
+                Ok(status) if !status.success() => app_state.status = Some(i18n::trf("{} exited with {}", &[&command.name, &status])),
+                Err(e) => app_state.status = Some(i18n::trf("{}: {}", &[&command.name, &e])),
+                Ok(_) => {}
             }
         }
-        PopupMode::None => {}
     }
-    app_state.popup_mode = PopupMode::None;
-    app_state.input_buffer.clear();
-    app_state.refresh_entries()?;
-    Ok(())
-}
-
-fn render_popup(f: &mut tui::Frame<CrosstermBackend<&mut io::Stdout>>, app_state: &AppState) {
-    let size = f.size();
-    let popup_area = centered_rect(50, 30, size);
-    f.render_widget(Clear, popup_area);
-    let (title, prompt) = match app_state.popup_mode {
-        PopupMode::CreateFile => ("Create New File", "Enter filename:"),
-        PopupMode::CreateDir => ("Create New Directory", "Enter directory name:"),
-        PopupMode::Delete => {
-            let empty_string = String::new();
-            let selected_name = app_state.entries.get(app_state.selected_index).unwrap_or(&empty_string);
-            return render_delete_popup(f, popup_area, selected_name, &app_state.input_buffer);
-        }
-        PopupMode::Rename => ("Rename Item", "Enter new name:"),
-        PopupMode::None => ("", ""),
-    };
-    let popup_text = vec![
-        Spans::from(vec![Span::raw(prompt)]),
-        Spans::from(vec![Span::styled(&app_state.input_buffer, Style::default().fg(Color::Yellow))]),
-        Spans::from(vec![]),
-        Spans::from(vec![Span::styled("Press Enter to confirm, Esc to cancel", Style::default().fg(Color::Gray))]),
-    ];
-    let popup = Paragraph::new(popup_text)
-        .block(Block::default().borders(Borders::ALL).title(title).style(Style::default().fg(Color::Cyan)))
-        .alignment(Alignment::Left);
-    f.render_widget(popup, popup_area);
-}
-
-fn render_delete_popup(f: &mut tui::Frame<CrosstermBackend<&mut io::Stdout>>, popup_area: Rect, selected_name: &str, input_buffer: &str) {
-    let popup_text = vec![
-        Spans::from(vec![Span::styled("WARNING: Delete item?", Style::default().fg(Color::Red))]),
-        Spans::from(vec![]),
-        Spans::from(vec![Span::raw("Item: "), Span::styled(selected_name, Style::default().fg(Color::Yellow))]),
-        Spans::from(vec![]),
-        Spans::from(vec![Span::raw("Type 'y' or 'yes' to confirm:")]),
-        Spans::from(vec![Span::styled(">> ", Style::default().fg(Color::Red)), Span::styled(input_buffer, Style::default().fg(Color::Yellow))]),
-        Spans::from(vec![]),
-        Spans::from(vec![Span::styled("Press Esc to cancel", Style::default().fg(Color::Gray))]),
-    ];
-    let popup = Paragraph::new(popup_text)
-        .block(Block::default().borders(Borders::ALL).title("Delete Confirmation").style(Style::default().fg(Color::Red)))
-        .alignment(Alignment::Left);
-    f.render_widget(popup, popup_area);
-}
-
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default().direction(Direction::Vertical).constraints([Constraint::Percentage((100 - percent_y) / 2), Constraint::Percentage(percent_y), Constraint::Percentage((100 - percent_y) / 2)]).split(r);
-    Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage((100 - percent_x) / 2), Constraint::Percentage(percent_x), Constraint::Percentage((100 - percent_x) / 2)]).split(popup_layout[1])[1]
-}
-
-fn read_entries(dir: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let mut entries: Vec<String> = fs::read_dir(dir)?.filter_map(|x| x.ok()).map(|e| e.file_name().to_string_lossy().into_owned()).collect();
-    entries.sort_unstable();
-    Ok(entries)
+    app_state.refresh_entries()
 }
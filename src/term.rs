@@ -0,0 +1,86 @@
+use std::{env, fs, io::{self, Write}, path::Path, thread, time::Duration};
+use quickfind_core::notify::Alert;
+
+const FLASH: Duration = Duration::from_millis(100);
+
+pub fn push_title(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1b[22;0t")?;
+    out.flush()
+}
+
+pub fn pop_title(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1b[23;0t")?;
+    out.flush()
+}
+
+pub fn set_title(out: &mut impl Write, location: &str) -> io::Result<()> {
+    let title = format!("QuickFind - {}", location);
+    write!(out, "\x1b]2;{}\x07", title.replace(|c: char| c.is_control(), ""))?;
+    out.flush()
+}
+
+pub fn announce_dir(out: &mut impl Write, dir: &Path) -> io::Result<()> {
+    set_title(out, &dir.to_string_lossy())?;
+    write!(out, "\x1b]7;file://{}{}\x1b\\", hostname(), file_url_path(dir))?;
+    out.flush()
+}
+
+fn hostname() -> String {
+    env::var("HOSTNAME").ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| env::var("COMPUTERNAME").ok())
+        .map(|h| h.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn file_url_path(dir: &Path) -> String {
+    let raw = dir.to_string_lossy().replace('\\', "/");
+    let raw = if raw.starts_with('/') { raw } else { format!("/{}", raw) };
+    raw.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+pub fn alert(out: &mut impl Write, alert: Alert) -> io::Result<()> {
+    match alert {
+        Alert::Off => return Ok(()),
+        Alert::Bell => write!(out, "\x07")?,
+        Alert::Flash => {
+            write!(out, "\x1b[?5h")?;
+            out.flush()?;
+            thread::sleep(FLASH);
+            write!(out, "\x1b[?5l")?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(unix)]
+pub fn stdin_is_piped() -> bool {
+    use std::os::{fd::AsFd, unix::fs::FileTypeExt};
+    let Ok(fd) = io::stdin().as_fd().try_clone_to_owned() else { return false };
+    fs::File::from(fd).metadata().is_ok_and(|meta| meta.file_type().is_fifo() || meta.is_file())
+}
+
+#[cfg(not(unix))]
+pub fn stdin_is_piped() -> bool {
+    use std::io::IsTerminal;
+    !io::stdin().is_terminal()
+}
+
+#[cfg(unix)]
+pub fn divert_stdout() -> io::Result<fs::File> {
+    use std::os::fd::{AsRawFd, FromRawFd};
+    let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if saved < 0 { return Err(io::Error::last_os_error()); }
+    let tty = fs::OpenOptions::new().write(true).open("/dev/tty");
+    let target = tty.as_ref().map_or(libc::STDERR_FILENO, |tty| tty.as_raw_fd());
+    if unsafe { libc::dup2(target, libc::STDOUT_FILENO) } < 0 { return Err(io::Error::last_os_error()); }
+    Ok(unsafe { fs::File::from_raw_fd(saved) })
+}
+
+#[cfg(not(unix))]
+pub fn divert_stdout() -> io::Result<fs::File> {
+    Err(io::Error::other("--print-cwd is only supported on Unix terminals"))
+}